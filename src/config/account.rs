@@ -0,0 +1,32 @@
+use lazy_static::lazy_static;
+use std::env;
+use std::time::Duration;
+
+pub struct AccountConfig {
+    // 注销后保留个人信息的时长，超过后匿名化
+    pub deleted_retention: Duration,
+    // 匿名化任务的执行间隔
+    pub purge_interval: Duration,
+}
+
+impl AccountConfig {
+    pub fn from_env() -> Self {
+        AccountConfig {
+            deleted_retention: Duration::from_secs(
+                env_or("ACCOUNT_DELETED_RETENTION_DAYS", 30) * 24 * 3600,
+            ),
+            purge_interval: Duration::from_secs(env_or("ACCOUNT_PURGE_INTERVAL_SECS", 3600)),
+        }
+    }
+}
+
+fn env_or<T: std::str::FromStr>(key: &str, default: T) -> T {
+    env::var(key)
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(default)
+}
+
+lazy_static! {
+    pub static ref ACCOUNT_CONFIG: AccountConfig = AccountConfig::from_env();
+}
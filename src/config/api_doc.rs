@@ -1,5 +1,14 @@
+use crate::services::api_keys;
+use crate::services::articles;
 use crate::services::auth;
 use crate::services::categories;
+use crate::services::email;
+use crate::services::oauth;
+use crate::services::password;
+use crate::services::permissions;
+use crate::services::profile;
+use crate::services::roles;
+use crate::services::tags;
 use crate::services::user;
 use std::fs::File;
 use std::io::Write;
@@ -11,7 +20,7 @@ use utoipa::OpenApi;
     info(
         title = "Rust Web API",
         version = "1.0",
-        description = "一个简单的Rust web API",
+        description = "一个简单的Rust web API。\n\n错误响应统一为 ErrorResponse 格式，HTTP 状态码与 code 一致，error 为稳定的错误码，参数校验失败时 fields 按字段列出错误；request_id 与响应头 X-Request-Id 相同。",
         terms_of_service = "https://www.rust-web-api.com/terms",
         contact(
             name = "Sunrisies",
@@ -22,12 +31,35 @@ use utoipa::OpenApi;
     paths(
         // 分类模块
         categories::create_category, // 创建分类
+        categories::get_all_categories, // 获取分类列表
+        categories::delete_category, // 删除分类
+
+        // 标签
+        tags::create_tag, // 创建标签
+        tags::get_all_tags, // 获取标签列表
+        tags::batch_tags, // 批量操作标签
+
+        // 文章
+        articles::get_article, // 获取文章列表
+        articles::get_article_by_uuid, // 获取文章详情
+        articles::create_article, // 创建文章
+        articles::update_article, // 修改文章
+        articles::delete_article, // 删除文章
+        articles::batch_articles, // 批量操作文章
 
         // 权限模块的
         auth::register, // 注册
         auth::get_permissions, // 获取权限列表
         auth::login, // 登录
+        auth::login_2fa, // 双重验证登录
         auth::get_permissions_by_id, // 根据ID获取权限
+        password::change_password, // 修改密码
+        password::request_password_reset, // 申请重置密码
+        password::confirm_password_reset, // 确认重置密码
+        email::send_email_verification, // 发送邮箱验证邮件
+        email::verify_email, // 验证邮箱
+        oauth::oauth_authorize, // 第三方登录授权地址
+        oauth::oauth_callback, // 第三方登录回调
 
 
         // 用户模块
@@ -35,6 +67,32 @@ use utoipa::OpenApi;
         user::get_user_by_uuid, // 根据UUID获取用户信息
         user::delete_user,  // 删除用户
         user::update_user, // 更新用户信息
+        user::unlock_user, // 解锁用户
+        user::disable_user, // 禁用用户
+        user::restore_user, // 恢复用户
+        user::batch_users, // 批量操作用户
+        profile::get_profile, // 获取个人资料
+        profile::update_profile, // 修改个人资料
+        profile::delete_account, // 注销账户
+
+        // 角色模块
+        roles::get_roles, // 获取角色列表
+        roles::create_role, // 创建角色
+        roles::update_role, // 更新角色
+        roles::delete_role, // 删除角色
+        roles::get_user_roles, // 获取用户角色
+        roles::assign_user_roles, // 设置用户角色
+
+        // 权限模块
+        permissions::get_user_permissions, // 获取用户有效权限
+        permissions::grant_user_permissions, // 授予用户权限
+        permissions::revoke_user_permissions, // 撤销用户权限
+        permissions::get_permission_routes, // 获取权限对应的接口
+
+        // API密钥
+        api_keys::create_api_key, // 创建API密钥
+        api_keys::list_api_keys, // 获取API密钥列表
+        api_keys::delete_api_key, // 删除API密钥
     )
 )]
 pub struct ApiDoc;
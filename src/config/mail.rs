@@ -0,0 +1,84 @@
+use lazy_static::lazy_static;
+use std::env;
+use std::path::PathBuf;
+use std::time::Duration;
+
+// 邮件投递方式
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MailTransport {
+    // 只写日志
+    Log,
+    // 写入本地目录，便于本地调试时查看邮件内容
+    File,
+    // 通过SMTP服务器发送
+    Smtp,
+}
+
+// SMTP 连接配置
+#[derive(Debug, Clone)]
+pub struct SmtpSettings {
+    pub host: String,
+    pub port: u16,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    // 465 端口一般使用隐式TLS，其余端口使用 STARTTLS
+    pub implicit_tls: bool,
+}
+
+// 邮件相关配置
+pub struct MailConfig {
+    pub transport: MailTransport,
+    // 发件人，例如 "RustWeb <no-reply@example.com>"
+    pub from: String,
+    pub smtp: SmtpSettings,
+    // File 模式下邮件保存的目录
+    pub file_dir: PathBuf,
+    // 邮箱验证链接有效期
+    pub verify_token_ttl: Duration,
+    // 验证链接地址，令牌会以 ?token= 追加在后面
+    pub verify_url: String,
+}
+
+impl MailConfig {
+    pub fn from_env() -> Self {
+        let transport = match env::var("MAIL_TRANSPORT")
+            .unwrap_or_default()
+            .to_lowercase()
+            .as_str()
+        {
+            "smtp" => MailTransport::Smtp,
+            "file" => MailTransport::File,
+            _ => MailTransport::Log,
+        };
+        let port = env_or("SMTP_PORT", 587);
+        MailConfig {
+            transport,
+            from: env::var("MAIL_FROM")
+                .unwrap_or_else(|_| "RustWeb <no-reply@localhost>".to_string()),
+            smtp: SmtpSettings {
+                host: env::var("SMTP_HOST").unwrap_or_else(|_| "localhost".to_string()),
+                port,
+                username: env::var("SMTP_USERNAME").ok(),
+                password: env::var("SMTP_PASSWORD").ok(),
+                implicit_tls: env_or("SMTP_IMPLICIT_TLS", port == 465),
+            },
+            file_dir: PathBuf::from(
+                env::var("MAIL_FILE_DIR").unwrap_or_else(|_| "logs/mail".to_string()),
+            ),
+            verify_token_ttl: Duration::from_secs(env_or("EMAIL_VERIFY_TTL_SECS", 86400)),
+            verify_url: env::var("EMAIL_VERIFY_URL")
+                .unwrap_or_else(|_| "http://127.0.0.1:18080/api/auth/email/verify".to_string()),
+        }
+    }
+}
+
+fn env_or<T: std::str::FromStr>(key: &str, default: T) -> T {
+    env::var(key)
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(default)
+}
+
+lazy_static! {
+    pub static ref MAIL_CONFIG: MailConfig = MailConfig::from_env();
+}
@@ -1,3 +1,9 @@
+pub mod account;
 pub mod api_doc;
 pub mod log;
+pub mod mail;
+pub mod oauth;
+pub mod password;
 pub mod permission;
+pub mod throttle;
+pub mod two_factor;
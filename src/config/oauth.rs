@@ -0,0 +1,107 @@
+use lazy_static::lazy_static;
+use std::collections::HashMap;
+use std::env;
+use std::time::Duration;
+
+// 第三方登录提供方类型，决定如何解析用户信息
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProviderKind {
+    Github,
+    // 标准 OpenID Connect，用户信息来自 userinfo 端点
+    Oidc,
+}
+
+// 单个第三方登录提供方的配置
+#[derive(Debug, Clone)]
+pub struct OAuthProviderConfig {
+    pub name: String,
+    pub kind: ProviderKind,
+    pub client_id: String,
+    pub client_secret: String,
+    pub authorize_url: String,
+    pub token_url: String,
+    pub userinfo_url: String,
+    pub scopes: String,
+    // 提供方授权完成后跳转的地址，需要与提供方后台登记的一致
+    pub redirect_uri: String,
+}
+
+pub struct OAuthConfig {
+    pub providers: HashMap<String, OAuthProviderConfig>,
+    // 授权请求（state 和 PKCE 校验码）的有效期
+    pub state_ttl: Duration,
+}
+
+impl OAuthConfig {
+    // OAUTH_PROVIDERS 为逗号分隔的提供方名称，每个提供方的配置以 OAUTH_<NAME>_ 为前缀
+    pub fn from_env() -> Self {
+        let providers = env::var("OAUTH_PROVIDERS")
+            .unwrap_or_default()
+            .split(',')
+            .map(|name| name.trim().to_lowercase())
+            .filter(|name| !name.is_empty())
+            .filter_map(|name| match provider_from_env(&name) {
+                Some(provider) => Some((name, provider)),
+                None => {
+                    log::error!("第三方登录 {} 配置不完整，已忽略", name);
+                    None
+                }
+            })
+            .collect();
+
+        OAuthConfig {
+            providers,
+            state_ttl: Duration::from_secs(
+                env::var("OAUTH_STATE_TTL_SECS")
+                    .ok()
+                    .and_then(|v| v.parse().ok())
+                    .unwrap_or(600),
+            ),
+        }
+    }
+}
+
+fn provider_from_env(name: &str) -> Option<OAuthProviderConfig> {
+    let prefix = format!("OAUTH_{}_", name.to_uppercase());
+    let var = |key: &str| env::var(format!("{}{}", prefix, key)).ok();
+
+    // github 自带默认端点，其余提供方都按 OIDC 处理且必须配置端点
+    let kind = match var("KIND").as_deref() {
+        Some("github") => ProviderKind::Github,
+        Some(_) => ProviderKind::Oidc,
+        None if name == "github" => ProviderKind::Github,
+        None => ProviderKind::Oidc,
+    };
+    let (authorize_url, token_url, userinfo_url, scopes) = match kind {
+        ProviderKind::Github => (
+            var("AUTHORIZE_URL")
+                .unwrap_or_else(|| "https://github.com/login/oauth/authorize".to_string()),
+            var("TOKEN_URL")
+                .unwrap_or_else(|| "https://github.com/login/oauth/access_token".to_string()),
+            var("USERINFO_URL").unwrap_or_else(|| "https://api.github.com/user".to_string()),
+            var("SCOPES").unwrap_or_else(|| "read:user user:email".to_string()),
+        ),
+        ProviderKind::Oidc => (
+            var("AUTHORIZE_URL")?,
+            var("TOKEN_URL")?,
+            var("USERINFO_URL")?,
+            var("SCOPES").unwrap_or_else(|| "openid profile email".to_string()),
+        ),
+    };
+
+    Some(OAuthProviderConfig {
+        name: name.to_string(),
+        kind,
+        client_id: var("CLIENT_ID")?,
+        client_secret: var("CLIENT_SECRET").unwrap_or_default(),
+        authorize_url,
+        token_url,
+        userinfo_url,
+        scopes,
+        redirect_uri: var("REDIRECT_URI")?,
+    })
+}
+
+lazy_static! {
+    pub static ref OAUTH_CONFIG: OAuthConfig = OAuthConfig::from_env();
+}
@@ -0,0 +1,174 @@
+use lazy_static::lazy_static;
+use std::env;
+use std::time::Duration;
+
+// 密码强度策略
+#[derive(Debug, Clone)]
+pub struct PasswordPolicy {
+    pub min_length: usize,
+    pub max_length: usize,
+    pub require_lowercase: bool,
+    pub require_uppercase: bool,
+    pub require_digit: bool,
+    pub require_symbol: bool,
+    // 密码中不允许包含用户名（忽略大小写）
+    pub forbid_user_name: bool,
+}
+
+impl PasswordPolicy {
+    // 校验密码是否满足策略，不满足时返回可直接展示给用户的提示
+    pub fn check(&self, password: &str, user_name: &str) -> Result<(), String> {
+        let length = password.chars().count();
+        if length < self.min_length || length > self.max_length {
+            return Err(format!(
+                "密码长度必须在{}到{}之间",
+                self.min_length, self.max_length
+            ));
+        }
+        if self.require_lowercase && !password.chars().any(|c| c.is_ascii_lowercase()) {
+            return Err("密码必须包含小写字母".to_string());
+        }
+        if self.require_uppercase && !password.chars().any(|c| c.is_ascii_uppercase()) {
+            return Err("密码必须包含大写字母".to_string());
+        }
+        if self.require_digit && !password.chars().any(|c| c.is_ascii_digit()) {
+            return Err("密码必须包含数字".to_string());
+        }
+        if self.require_symbol && password.chars().all(|c| c.is_alphanumeric()) {
+            return Err("密码必须包含特殊字符".to_string());
+        }
+        let user_name = user_name.trim().to_lowercase();
+        if self.forbid_user_name
+            && !user_name.is_empty()
+            && password.to_lowercase().contains(&user_name)
+        {
+            return Err("密码不能包含用户名".to_string());
+        }
+        Ok(())
+    }
+}
+
+// Argon2id 哈希参数，默认值取自 OWASP 推荐配置
+#[derive(Debug, Clone, Copy)]
+pub struct Argon2Settings {
+    pub memory_kib: u32,
+    pub iterations: u32,
+    pub parallelism: u32,
+}
+
+// 密码相关配置
+pub struct PasswordConfig {
+    pub policy: PasswordPolicy,
+    pub argon2: Argon2Settings,
+    // 重置令牌有效期
+    pub reset_token_ttl: Duration,
+    // 重置邮件中的前端页面地址，令牌会以 ?token= 追加在后面
+    pub reset_url: String,
+}
+
+impl PasswordConfig {
+    pub fn from_env() -> Self {
+        PasswordConfig {
+            policy: PasswordPolicy {
+                min_length: env_or("PASSWORD_MIN_LENGTH", 8),
+                max_length: env_or("PASSWORD_MAX_LENGTH", 100),
+                require_lowercase: env_or("PASSWORD_REQUIRE_LOWERCASE", true),
+                require_uppercase: env_or("PASSWORD_REQUIRE_UPPERCASE", false),
+                require_digit: env_or("PASSWORD_REQUIRE_DIGIT", true),
+                require_symbol: env_or("PASSWORD_REQUIRE_SYMBOL", false),
+                forbid_user_name: env_or("PASSWORD_FORBID_USER_NAME", true),
+            },
+            argon2: Argon2Settings {
+                memory_kib: env_or("ARGON2_MEMORY_KIB", 19456),
+                iterations: env_or("ARGON2_ITERATIONS", 2),
+                parallelism: env_or("ARGON2_PARALLELISM", 1),
+            },
+            reset_token_ttl: Duration::from_secs(env_or("PASSWORD_RESET_TTL_SECS", 1800)),
+            reset_url: env::var("PASSWORD_RESET_URL")
+                .unwrap_or_else(|_| "http://127.0.0.1:5502/reset-password".to_string()),
+        }
+    }
+}
+
+fn env_or<T: std::str::FromStr>(key: &str, default: T) -> T {
+    env::var(key)
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(default)
+}
+
+lazy_static! {
+    pub static ref PASSWORD_CONFIG: PasswordConfig = PasswordConfig::from_env();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy() -> PasswordPolicy {
+        PasswordPolicy {
+            min_length: 8,
+            max_length: 20,
+            require_lowercase: true,
+            require_uppercase: true,
+            require_digit: true,
+            require_symbol: true,
+            forbid_user_name: true,
+        }
+    }
+
+    #[test]
+    fn length_is_counted_in_characters() {
+        let policy = policy();
+        assert!(policy.check("Aa1!", "alice").is_err());
+        assert!(policy.check("Aa1!Aa1!Aa1!Aa1!Aa1!x", "alice").is_err());
+        assert!(policy.check("Aa1!bcde", "alice").is_ok());
+        // 中文按字符而不是字节计算长度
+        let relaxed = PasswordPolicy {
+            require_lowercase: false,
+            require_uppercase: false,
+            require_digit: false,
+            require_symbol: false,
+            ..policy
+        };
+        assert!(relaxed.check("密码密码密码密码", "alice").is_ok());
+        assert!(relaxed.check("密码密码", "alice").is_err());
+    }
+
+    #[test]
+    fn each_character_class_is_required() {
+        let policy = policy();
+        assert_eq!(
+            policy.check("AA1!BCDE", "alice").unwrap_err(),
+            "密码必须包含小写字母"
+        );
+        assert_eq!(
+            policy.check("aa1!bcde", "alice").unwrap_err(),
+            "密码必须包含大写字母"
+        );
+        assert_eq!(
+            policy.check("Aaa!bcde", "alice").unwrap_err(),
+            "密码必须包含数字"
+        );
+        assert_eq!(
+            policy.check("Aa1xbcde", "alice").unwrap_err(),
+            "密码必须包含特殊字符"
+        );
+    }
+
+    #[test]
+    fn password_must_not_contain_user_name() {
+        let policy = policy();
+        assert_eq!(
+            policy.check("xALICE1!y", " Alice ").unwrap_err(),
+            "密码不能包含用户名"
+        );
+        assert!(policy.check("Bob-1234x", "alice").is_ok());
+
+        let allowed = PasswordPolicy {
+            forbid_user_name: false,
+            ..policy
+        };
+        assert!(allowed.check("xALICE1!y", "alice").is_ok());
+    }
+}
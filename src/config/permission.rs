@@ -60,6 +60,87 @@ define_permissions! {
     READ_MESSAGE = 1 << 14, "读取消息权限";
     WRITE_MESSAGE = 1 << 15, "写入消息权限";
     READ_WRITE_MESSAGE = (1 << 14) | (1 << 15), "读写消息权限";
+    MANAGE_ARTICLE = 1 << 16, "管理所有文章权限，可以修改和删除他人的文章";
     READ = (1 << 0) | (1 << 2) | (1 << 4) | (1 << 6) | (1 << 8) | (1 << 10) | (1 << 12) | (1 << 14), "所有读取权限";
     ALL = !0, "所有权限";
 }
+
+impl Permission {
+    // 按权限名称组合权限位，遇到未知名称时返回该名称
+    pub fn from_names<S: AsRef<str>>(names: &[S]) -> Result<Permission, String> {
+        names.iter().try_fold(Permission::NONE, |acc, name| {
+            PERMISSION_MAP
+                .get(name.as_ref())
+                .map(|flag| acc | *flag)
+                .ok_or_else(|| name.as_ref().to_string())
+        })
+    }
+
+    // 解析数据库或令牌中保存的权限位字符串，格式错误视为没有权限
+    pub fn from_stored(stored: Option<&str>) -> Permission {
+        stored
+            .and_then(|s| s.parse::<u64>().ok())
+            .map(Permission::from_bits_retain)
+            .unwrap_or(Permission::NONE)
+    }
+
+    // 列出包含的单一权限名称
+    pub fn names(&self) -> Vec<String> {
+        PERMISSION_LIST
+            .iter()
+            .filter_map(|(name, _)| PERMISSION_MAP.get(name).map(|flag| (name, flag)))
+            .filter(|(_, flag)| flag.bits().count_ones() == 1 && self.contains(**flag))
+            .map(|(name, _)| name.to_string())
+            .collect()
+    }
+
+    // 列出包含的单一权限名称及其说明
+    pub fn descriptions(&self) -> Vec<(&'static str, &'static str)> {
+        PERMISSION_LIST
+            .iter()
+            .filter(|(name, _)| {
+                PERMISSION_MAP
+                    .get(name)
+                    .is_some_and(|flag| flag.bits().count_ones() == 1 && self.contains(*flag))
+            })
+            .copied()
+            .collect()
+    }
+}
+
+// 新注册用户默认分配的角色
+pub const DEFAULT_ROLE: &str = "reader";
+
+// 内置角色：名称、说明、权限，启动时不存在则自动创建
+pub static BUILTIN_ROLES: &[(&str, &str, Permission)] = &[
+    ("admin", "管理员，拥有所有权限", Permission::ALL),
+    (
+        "editor",
+        "编辑，可以管理文章、评论、标签、分类和文件",
+        Permission::READ
+            .union(Permission::WRITE_ARTICLE)
+            .union(Permission::MANAGE_ARTICLE)
+            .union(Permission::WRITE_COMMENT)
+            .union(Permission::WRITE_TAG)
+            .union(Permission::WRITE_CATEGORY)
+            .union(Permission::WRITE_FILE),
+    ),
+    (
+        "author",
+        "作者，可以撰写文章和上传文件",
+        Permission::READ_ARTICLE
+            .union(Permission::WRITE_ARTICLE)
+            .union(Permission::READ_WRITE_COMMENT)
+            .union(Permission::READ_TAG)
+            .union(Permission::READ_CATEGORY)
+            .union(Permission::READ_WRITE_FILE),
+    ),
+    (
+        "reader",
+        "读者，可以阅读内容和发表评论",
+        Permission::READ_ARTICLE
+            .union(Permission::READ_WRITE_COMMENT)
+            .union(Permission::READ_TAG)
+            .union(Permission::READ_CATEGORY),
+    ),
+];
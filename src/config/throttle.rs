@@ -0,0 +1,74 @@
+use lazy_static::lazy_static;
+use std::env;
+use std::net::IpAddr;
+use std::time::Duration;
+
+// 单个维度（用户名或IP）的登录失败限制策略
+#[derive(Debug, Clone)]
+pub struct ThrottlePolicy {
+    // 连续失败多少次以内不做限制
+    pub free_attempts: u32,
+    // 超出后第一次退避的时长，之后每次翻倍
+    pub base_delay: Duration,
+    // 单次退避的上限
+    pub max_delay: Duration,
+    // 连续失败达到该次数后临时锁定
+    pub lockout_threshold: u32,
+    // 锁定时长，同时也是失败记录的保留时长
+    pub lockout_duration: Duration,
+}
+
+pub struct LoginThrottleConfig {
+    pub user: ThrottlePolicy,
+    pub ip: ThrottlePolicy,
+}
+
+impl LoginThrottleConfig {
+    pub fn from_env() -> Self {
+        LoginThrottleConfig {
+            user: ThrottlePolicy {
+                free_attempts: env_or("LOGIN_USER_FREE_ATTEMPTS", 3),
+                base_delay: Duration::from_secs(env_or("LOGIN_BASE_DELAY_SECS", 1)),
+                max_delay: Duration::from_secs(env_or("LOGIN_MAX_DELAY_SECS", 60)),
+                lockout_threshold: env_or("LOGIN_USER_LOCKOUT_THRESHOLD", 10),
+                lockout_duration: Duration::from_secs(env_or("LOGIN_LOCKOUT_SECS", 900)),
+            },
+            ip: ThrottlePolicy {
+                free_attempts: env_or("LOGIN_IP_FREE_ATTEMPTS", 10),
+                base_delay: Duration::from_secs(env_or("LOGIN_BASE_DELAY_SECS", 1)),
+                max_delay: Duration::from_secs(env_or("LOGIN_MAX_DELAY_SECS", 60)),
+                lockout_threshold: env_or("LOGIN_IP_LOCKOUT_THRESHOLD", 50),
+                lockout_duration: Duration::from_secs(env_or("LOGIN_LOCKOUT_SECS", 900)),
+            },
+        }
+    }
+}
+
+// 前置反向代理的地址，逗号分隔。只有直连地址属于这些代理时才读取 X-Forwarded-For，
+// 未配置时一律使用TCP连接的对端地址，防止客户端伪造请求头绕过登录限制
+fn trusted_proxies_from_env() -> Vec<IpAddr> {
+    env::var("TRUSTED_PROXIES")
+        .unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .filter_map(|s| match s.parse() {
+            Ok(ip) => Some(ip),
+            Err(_) => {
+                log::error!("TRUSTED_PROXIES 中的地址 {} 无效，已忽略", s);
+                None
+            }
+        })
+        .collect()
+}
+
+lazy_static! {
+    pub static ref TRUSTED_PROXIES: Vec<IpAddr> = trusted_proxies_from_env();
+}
+
+fn env_or<T: std::str::FromStr>(key: &str, default: T) -> T {
+    env::var(key)
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(default)
+}
@@ -0,0 +1,46 @@
+use base64::engine::general_purpose;
+use base64::engine::Engine as _;
+use lazy_static::lazy_static;
+use std::env;
+
+// 双重验证相关配置
+pub struct TwoFactorConfig {
+    // otpauth URI 中展示的发行方名称
+    pub issuer: String,
+    // 加密TOTP密钥使用的AES-256密钥
+    pub encryption_key: [u8; 32],
+}
+
+impl TwoFactorConfig {
+    pub fn from_env() -> Result<Self, String> {
+        let issuer = env::var("TOTP_ISSUER").unwrap_or_else(|_| "RustWeb".to_string());
+        Ok(TwoFactorConfig {
+            issuer,
+            encryption_key: load_encryption_key()?,
+        })
+    }
+}
+
+// TOTP_ENCRYPTION_KEY 为 base64 编码的32字节密钥，必须单独配置，不能从其他密钥派生
+fn load_encryption_key() -> Result<[u8; 32], String> {
+    let encoded = match env::var("TOTP_ENCRYPTION_KEY") {
+        Ok(encoded) => encoded,
+        // 测试环境没有配置文件，使用固定密钥
+        Err(_) if cfg!(test) => return Ok([7u8; 32]),
+        Err(_) => return Err("未配置 TOTP_ENCRYPTION_KEY".to_string()),
+    };
+    match general_purpose::STANDARD.decode(encoded.trim()) {
+        Ok(bytes) if bytes.len() == 32 => {
+            let mut key = [0u8; 32];
+            key.copy_from_slice(&bytes);
+            Ok(key)
+        }
+        _ => Err("TOTP_ENCRYPTION_KEY 必须是base64编码的32字节密钥".to_string()),
+    }
+}
+
+lazy_static! {
+    // 启动时已经通过 TwoFactorConfig::from_env 校验过配置
+    pub static ref TWO_FACTOR_CONFIG: TwoFactorConfig =
+        TwoFactorConfig::from_env().expect("双重验证配置无效");
+}
@@ -0,0 +1,15 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use validator::Validate;
+
+#[derive(Validate, Debug, Default, Clone, Serialize, Deserialize, ToSchema)]
+pub struct CreateApiKeyRequest {
+    #[validate(length(min = 1, max = 100, message = "密钥名称长度必须在1到100之间"))]
+    pub name: String,
+    // 权限名称列表，只能是当前用户已拥有权限的子集
+    #[validate(length(min = 1, message = "至少需要授予一个权限"))]
+    pub permissions: Vec<String>,
+    // 有效天数，不传表示永不过期
+    #[validate(range(min = 1, max = 3650, message = "有效天数必须在1到3650之间"))]
+    pub expires_in_days: Option<u32>,
+}
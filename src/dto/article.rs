@@ -0,0 +1,102 @@
+use crate::common::MAX_BATCH_SIZE;
+use crate::models::article;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use validator::Validate;
+
+#[derive(Validate, Debug, Default, Clone, Serialize, Deserialize, ToSchema)]
+pub struct CreateArticleRequest {
+    #[validate(length(min = 1, max = 50, message = "文章标题长度必须在1到50之间"))]
+    pub title: String,
+    #[validate(length(min = 1, message = "文章内容不能为空"))]
+    pub content: String,
+    #[serde(default)]
+    #[validate(length(max = 255, message = "封面路径不能超过255个字符"))]
+    pub cover: String,
+    #[serde(default)]
+    #[validate(length(max = 255, message = "文章描述不能超过255个字符"))]
+    pub description: String,
+    pub category_id: Option<i32>,
+    #[serde(default)]
+    pub is_publish: bool,
+}
+
+// 只更新传入的字段
+#[derive(Validate, Debug, Default, Clone, Serialize, Deserialize, ToSchema)]
+pub struct UpdateArticleRequest {
+    #[validate(length(min = 1, max = 50, message = "文章标题长度必须在1到50之间"))]
+    pub title: Option<String>,
+    #[validate(length(min = 1, message = "文章内容不能为空"))]
+    pub content: Option<String>,
+    #[validate(length(max = 255, message = "封面路径不能超过255个字符"))]
+    pub cover: Option<String>,
+    #[validate(length(max = 255, message = "文章描述不能超过255个字符"))]
+    pub description: Option<String>,
+    pub category_id: Option<i32>,
+    pub is_publish: Option<bool>,
+}
+
+// 文章批量操作，action 决定需要的其他字段
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum ArticleBatchAction {
+    Publish,
+    Unpublish,
+    // 移入回收站（软删除）
+    Trash,
+    ChangeCategory { category_id: i32 },
+    AddTags { tag_ids: Vec<i32> },
+    RemoveTags { tag_ids: Vec<i32> },
+}
+
+#[derive(Validate, Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ArticleBatchRequest {
+    #[validate(length(min = 1, max = MAX_BATCH_SIZE, message = "一次需要处理1到100篇文章"))]
+    pub uuids: Vec<String>,
+    #[serde(flatten)]
+    pub action: ArticleBatchAction,
+}
+
+// 返回给前端的文章信息，不包含删除标记等内部字段
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct ArticleInfo {
+    pub id: i32,
+    pub uuid: String,
+    pub title: String,
+    pub content: String,
+    pub cover: String,
+    pub description: String,
+    pub author: String,
+    pub author_uuid: Option<String>,
+    pub category_id: Option<i32>,
+    pub views: i32,
+    pub is_top: bool,
+    pub is_recommend: bool,
+    pub is_publish: bool,
+    pub is_hide: bool,
+    pub publish_time: String,
+    pub update_time: String,
+}
+
+impl From<article::Model> for ArticleInfo {
+    fn from(model: article::Model) -> Self {
+        ArticleInfo {
+            id: model.id,
+            uuid: model.uuid,
+            title: model.title,
+            content: model.content,
+            cover: model.cover,
+            description: model.description,
+            author: model.author,
+            author_uuid: model.author_uuid,
+            category_id: model.category_id,
+            views: model.views,
+            is_top: model.is_top != 0,
+            is_recommend: model.is_recommend != 0,
+            is_publish: model.is_publish != 0,
+            is_hide: model.is_hide != 0,
+            publish_time: model.publish_time.to_string(),
+            update_time: model.update_time.to_string(),
+        }
+    }
+}
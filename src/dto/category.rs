@@ -0,0 +1,25 @@
+use crate::models::categories;
+use crate::models::sea_orm_active_enums::Type;
+use serde::Serialize;
+use utoipa::ToSchema;
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct CategoryInfo {
+    pub id: i32,
+    pub name: String,
+    pub r#type: Type,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+impl From<categories::Model> for CategoryInfo {
+    fn from(model: categories::Model) -> Self {
+        CategoryInfo {
+            id: model.id,
+            name: model.name,
+            r#type: model.r#type,
+            created_at: model.created_at.to_string(),
+            updated_at: model.updated_at.to_string(),
+        }
+    }
+}
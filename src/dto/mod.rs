@@ -1 +1,7 @@
+pub mod api_key;
+pub mod article;
+pub mod category;
+pub mod permission;
+pub mod role;
+pub mod tag;
 pub mod user;
@@ -0,0 +1,10 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use validator::Validate;
+
+#[derive(Validate, Debug, Default, Clone, Serialize, Deserialize, ToSchema)]
+pub struct PermissionNamesRequest {
+    // 权限名称列表，见 /api/auth/permissions
+    #[validate(length(min = 1, message = "权限列表不能为空"))]
+    pub permissions: Vec<String>,
+}
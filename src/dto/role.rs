@@ -0,0 +1,26 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use validator::Validate;
+
+#[derive(Validate, Debug, Default, Clone, Serialize, Deserialize, ToSchema)]
+pub struct CreateRoleRequest {
+    #[validate(length(min = 2, max = 50, message = "角色名称长度必须在2到50之间"))]
+    pub name: String,
+    #[validate(length(max = 255, message = "角色说明不能超过255个字符"))]
+    pub description: Option<String>,
+    // 权限名称列表，见 /api/auth/permissions
+    pub permissions: Vec<String>,
+}
+
+#[derive(Validate, Debug, Default, Clone, Serialize, Deserialize, ToSchema)]
+pub struct UpdateRoleRequest {
+    #[validate(length(max = 255, message = "角色说明不能超过255个字符"))]
+    pub description: Option<String>,
+    pub permissions: Option<Vec<String>>,
+}
+
+#[derive(Validate, Debug, Default, Clone, Serialize, Deserialize, ToSchema)]
+pub struct AssignRolesRequest {
+    // 用户的全部角色名称，会替换原有的角色
+    pub roles: Vec<String>,
+}
@@ -0,0 +1,41 @@
+use crate::common::MAX_BATCH_SIZE;
+use crate::models::sea_orm_active_enums::Type;
+use crate::models::tags;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use validator::Validate;
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct TagInfo {
+    pub id: i32,
+    pub name: String,
+    pub r#type: Type,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+impl From<tags::Model> for TagInfo {
+    fn from(model: tags::Model) -> Self {
+        TagInfo {
+            id: model.id,
+            name: model.name,
+            r#type: model.r#type,
+            created_at: model.created_at.to_string(),
+            updated_at: model.updated_at.to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum TagBatchAction {
+    Delete,
+}
+
+#[derive(Validate, Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct TagBatchRequest {
+    #[validate(length(min = 1, max = MAX_BATCH_SIZE, message = "一次需要处理1到100个标签"))]
+    pub ids: Vec<i32>,
+    #[serde(flatten)]
+    pub action: TagBatchAction,
+}
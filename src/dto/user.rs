@@ -1,9 +1,16 @@
+use crate::common::MAX_BATCH_SIZE;
+use crate::config::password::PASSWORD_CONFIG;
+use crate::models::sea_orm_active_enums::UserStatus;
+use crate::models::user;
 use serde::{Deserialize, Serialize};
 use utoipa::ToSchema;
-use validator::Validate;
+use validator::{Validate, ValidationError};
 #[derive(Validate, Debug, Default, Clone, Serialize, Deserialize, ToSchema)]
 pub struct UserDto {
-    #[validate(length(min = 10, max = 100, message = "用户名长度必须在5到100之间"))]
+    #[validate(
+        length(min = 10, max = 100, message = "用户名长度必须在5到100之间"),
+        custom(function = "validate_user_name")
+    )]
     #[serde(rename = "user_name")]
     pub user_name: String,
     #[validate(
@@ -28,26 +35,319 @@ pub struct UserDto {
 }
 
 #[derive(Validate, Debug, Default, Clone, Serialize, Deserialize, ToSchema)]
+#[validate(schema(function = "validate_register_password"))]
 pub struct RegisterResponse {
-    #[validate(length(min = 5, max = 100, message = "用户名长度必须在5到100之间"))]
+    #[validate(
+        length(min = 5, max = 100, message = "用户名长度必须在5到100之间"),
+        custom(function = "validate_user_name")
+    )]
     pub user_name: String,
-    #[validate(length(min = 6, max = 100, message = "密码长度必须在6到100之间"))]
+    // 长度和复杂度由密码策略统一校验
+    pub pass_word: String,
+    #[validate(email(message = "电子邮件无效"))]
+    pub email: Option<String>,
+    #[validate(custom(function = "validate_phone"))]
+    pub phone: Option<String>,
+}
+
+fn validate_register_password(data: &RegisterResponse) -> Result<(), ValidationError> {
+    password_policy_error(&data.pass_word, &data.user_name)
+}
+
+// 按配置的密码策略校验，供各个请求结构体复用
+pub fn password_policy_error(password: &str, user_name: &str) -> Result<(), ValidationError> {
+    PASSWORD_CONFIG
+        .policy
+        .check(password, user_name)
+        .map_err(|msg| ValidationError::new("password_policy").with_message(msg.into()))
+}
+
+// 登录时用户名、邮箱和手机号共用一个输入框，用户名不能是邮箱或手机号的格式，
+// 否则可以注册一个等于他人邮箱或手机号的用户名来干扰对方登录
+pub fn validate_user_name(user_name: &str) -> Result<(), ValidationError> {
+    if user_name.contains('@') {
+        return Err(ValidationError::new("user_name").with_message("用户名不能包含@".into()));
+    }
+    if is_phone_number(user_name) {
+        return Err(ValidationError::new("user_name").with_message("用户名不能是手机号格式".into()));
+    }
+    Ok(())
+}
+
+fn validate_phone(phone: &str) -> Result<(), ValidationError> {
+    if is_phone_number(phone) {
+        Ok(())
+    } else {
+        Err(ValidationError::new("phone").with_message("手机号格式错误".into()))
+    }
+}
+
+// 邮箱统一去除首尾空白并转为小写后存储和比较
+pub fn normalize_email(email: &str) -> String {
+    email.trim().to_lowercase()
+}
+
+// 手机号去掉常见的分隔符，只保留开头的 + 和数字
+pub fn normalize_phone(phone: &str) -> String {
+    phone
+        .chars()
+        .filter(|c| !matches!(c, ' ' | '-' | '(' | ')'))
+        .collect()
+}
+
+pub fn is_phone_number(phone: &str) -> bool {
+    let phone = normalize_phone(phone);
+    let digits = phone.strip_prefix('+').unwrap_or(&phone);
+    (6..=20).contains(&digits.len()) && digits.chars().all(|c| c.is_ascii_digit())
+}
+
+// 用户修改自己的资料，只更新传入的字段；不接受权限相关字段
+#[derive(Validate, Debug, Default, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct UpdateProfileRequest {
+    #[validate(
+        length(min = 5, max = 100, message = "用户名长度必须在5到100之间"),
+        custom(function = "validate_user_name")
+    )]
+    pub user_name: Option<String>,
+    #[validate(email(message = "电子邮件无效"))]
+    pub email: Option<String>,
+    #[validate(custom(function = "validate_phone"))]
+    pub phone: Option<String>,
+    #[validate(length(max = 255, message = "头像地址不能超过255个字符"))]
+    pub image: Option<String>,
+}
+
+#[derive(Validate, Debug, Default, Clone, Serialize, Deserialize, ToSchema)]
+pub struct DeleteAccountRequest {
+    // 注销账户前需要再次确认密码
+    #[validate(length(min = 1, message = "密码不能为空"))]
     pub pass_word: String,
 }
 
 #[derive(Validate, Debug, Default, Clone, Serialize, Deserialize, ToSchema)]
 pub struct LoginRequest {
-    #[validate(length(min = 5, max = 100, message = "用户名长度必须在5到100之间"))]
-    pub user_name: String,
+    // 用户名、已验证的邮箱或手机号，兼容旧版的 user_name 字段
+    #[serde(alias = "user_name")]
+    #[validate(length(min = 5, max = 255, message = "登录账号长度必须在5到255之间"))]
+    pub identifier: String,
     #[validate(length(min = 6, max = 100, message = "密码长度必须在6到100之间"))]
     pub pass_word: String,
 }
 
-#[derive(Deserialize, Debug, Default, Clone, Serialize, Validate)]
+#[derive(Deserialize, Debug, Default, Clone, Serialize, Validate, ToSchema)]
 pub struct UpdateUserRequest {
-    #[validate(length(min = 5, max = 100, message = "用户名长度必须在5到100之间"))]
+    #[validate(
+        length(min = 5, max = 100, message = "用户名长度必须在5到100之间"),
+        custom(function = "validate_user_name")
+    )]
     pub user_name: String,
     #[serde(rename = "image")]
     pub image: Option<String>,
+    // 在角色权限之外额外授予的权限
     pub permissions: Option<Vec<String>>,
+    // 禁止的权限，即使角色包含也不生效
+    pub denied_permissions: Option<Vec<String>>,
+}
+
+#[derive(Validate, Debug, Default, Clone, Serialize, Deserialize, ToSchema)]
+pub struct TwoFactorLoginRequest {
+    #[validate(length(min = 1, message = "挑战令牌不能为空"))]
+    pub challenge_token: String,
+    // TOTP验证码和恢复码二选一
+    #[validate(length(equal = 6, message = "验证码必须为6位"))]
+    pub code: Option<String>,
+    #[validate(length(min = 1, max = 32, message = "恢复码格式错误"))]
+    pub recovery_code: Option<String>,
+}
+
+#[derive(Validate, Debug, Default, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ChangePasswordRequest {
+    #[validate(length(min = 1, message = "旧密码不能为空"))]
+    pub old_password: String,
+    pub new_password: String,
+}
+
+#[derive(Validate, Debug, Default, Clone, Serialize, Deserialize, ToSchema)]
+pub struct PasswordResetRequest {
+    #[validate(length(min = 5, max = 100, message = "用户名长度必须在5到100之间"))]
+    pub user_name: String,
+}
+
+#[derive(Validate, Debug, Default, Clone, Serialize, Deserialize, ToSchema)]
+pub struct PasswordResetConfirmRequest {
+    #[validate(length(min = 1, message = "重置令牌不能为空"))]
+    pub token: String,
+    pub new_password: String,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct EmailVerifyQuery {
+    pub token: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum UserBatchAction {
+    Disable,
+    // 在原有角色之外追加一个角色
+    AssignRole { role: String },
+}
+
+#[derive(Validate, Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct UserBatchRequest {
+    #[validate(length(min = 1, max = MAX_BATCH_SIZE, message = "一次需要处理1到100个用户"))]
+    pub uuids: Vec<String>,
+    #[serde(flatten)]
+    pub action: UserBatchAction,
+}
+
+// 返回给前端的用户信息，只包含可以公开的字段，密码和双重验证密钥不会出现在响应中
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct UserInfo {
+    pub id: i32,
+    pub uuid: String,
+    pub user_name: String,
+    pub email: Option<String>,
+    pub email_verified: bool,
+    pub image: Option<String>,
+    pub phone: Option<String>,
+    pub role: Option<String>,
+    pub permissions: Option<String>,
+    pub denied_permissions: Option<String>,
+    pub two_factor_enabled: bool,
+    pub status: UserStatus,
+    pub deleted_at: Option<String>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+impl From<user::Model> for UserInfo {
+    fn from(model: user::Model) -> Self {
+        let two_factor_enabled = model.two_factor_enabled();
+        UserInfo {
+            id: model.id,
+            uuid: model.uuid,
+            user_name: model.user_name,
+            email: model.email,
+            email_verified: model.email_verified_at.is_some(),
+            image: model.image,
+            phone: model.phone,
+            role: model.role,
+            permissions: model.permissions,
+            denied_permissions: model.denied_permissions,
+            two_factor_enabled,
+            status: model.status,
+            deleted_at: model.deleted_at.map(|t| t.to_string()),
+            created_at: model.created_at.to_string(),
+            updated_at: model.updated_at.to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    #[test]
+    fn empty_binding_is_reported_as_disabled() {
+        let mut model = user::Model::fixture("u1", "tester");
+        model.binding = Some(String::new());
+        assert!(!UserInfo::from(model.clone()).two_factor_enabled);
+        model.binding = Some("encrypted-secret".to_string());
+        assert!(UserInfo::from(model).two_factor_enabled);
+    }
+
+    #[test]
+    fn user_info_never_contains_secrets() {
+        let model = user::Model {
+            id: 1,
+            uuid: "u1".to_string(),
+            user_name: "tester".to_string(),
+            pass_word: "$argon2id$hash".to_string(),
+            email: None,
+            email_verified_at: Some(Utc::now()),
+            image: None,
+            phone: None,
+            role: None,
+            permissions: None,
+            denied_permissions: None,
+            binding: Some("encrypted-secret".to_string()),
+            pending_binding: Some("pending-secret".to_string()),
+            status: UserStatus::Active,
+            deleted_at: None,
+            anonymized_at: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        };
+        let json = serde_json::to_string(&UserInfo::from(model)).unwrap();
+        assert!(!json.contains("pass_word"));
+        assert!(!json.contains("argon2"));
+        assert!(!json.contains("secret"));
+        assert!(json.contains("\"two_factor_enabled\":true"));
+        assert!(json.contains("\"email_verified\":true"));
+    }
+
+    #[test]
+    fn batch_request_reads_action_from_same_object() {
+        let req: UserBatchRequest = serde_json::from_value(serde_json::json!({
+            "uuids": ["u1", "u2"],
+            "action": "assign_role",
+            "role": "editor"
+        }))
+        .unwrap();
+        assert!(matches!(req.action, UserBatchAction::AssignRole { ref role } if role == "editor"));
+        assert!(req.validate().is_ok());
+
+        let empty: UserBatchRequest =
+            serde_json::from_value(serde_json::json!({"uuids": [], "action": "disable"})).unwrap();
+        assert!(empty.validate().is_err());
+        assert!(serde_json::from_value::<UserBatchRequest>(
+            serde_json::json!({"uuids": ["u1"], "action": "drop"})
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn emails_and_phones_are_normalized() {
+        assert_eq!(normalize_email("  Alice@Example.COM "), "alice@example.com");
+
+        assert_eq!(normalize_phone("+86 (138) 0013-8000"), "+8613800138000");
+        assert_eq!(normalize_phone("138 0013 8000"), "13800138000");
+
+        assert!(is_phone_number("+86 138-0013-8000"));
+        assert!(is_phone_number("123456"));
+        assert!(!is_phone_number("12345"));
+        assert!(!is_phone_number("123456789012345678901"));
+        assert!(!is_phone_number("+"));
+        assert!(!is_phone_number("86+13800138000"));
+        assert!(!is_phone_number("1380013800a"));
+        assert!(!is_phone_number("alice@example.com"));
+    }
+
+    #[test]
+    fn user_names_cannot_look_like_emails_or_phones() {
+        assert!(validate_user_name("alice01").is_ok());
+        assert!(validate_user_name("bob@example.com").is_err());
+        assert!(validate_user_name("13800138000").is_err());
+        assert!(validate_user_name("+86 138-0013-8000").is_err());
+
+        let register = RegisterResponse {
+            user_name: "bob@example.com".into(),
+            pass_word: "Str0ng-Passw0rd!".into(),
+            ..Default::default()
+        };
+        assert!(register.validate().is_err());
+        let profile = UpdateProfileRequest {
+            user_name: Some("13800138000".into()),
+            ..Default::default()
+        };
+        assert!(profile.validate().is_err());
+        let update = UpdateUserRequest {
+            user_name: "bob@example.com".into(),
+            ..Default::default()
+        };
+        assert!(update.validate().is_err());
+    }
 }
@@ -1,9 +1,11 @@
 use super::json_error::parse_json_error;
-use actix_web::{error::ResponseError, HttpResponse};
-use serde::Serialize;
+use actix_web::{error::ResponseError, http::StatusCode, HttpResponse};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use thiserror::Error;
 use utoipa::ToSchema;
-#[derive(Error, Debug, ToSchema)]
+use validator::{ValidationErrors, ValidationErrorsKind};
+#[derive(Error, Debug)]
 pub enum AppError {
     // 状态码400
     #[error("缺少参数: {0}")]
@@ -20,18 +22,26 @@ pub enum AppError {
     // 状态码400
     #[error("请求体解析错误: {0}")]
     DeserializeError(String),
+    // 状态码400，按字段列出校验错误
+    #[error("参数校验失败")]
+    Validation(FieldErrors),
     // 状态码409
     #[error("数据存在: {0}")]
     Conflict(String),
     // 状态码403
     #[error("禁止访问: {0}")]
     Forbidden(String),
+    // 状态码412
+    #[error("前置条件不满足: {0}")]
+    PreconditionFailed(String),
+    // 状态码429
+    #[error("请求过于频繁: {0}")]
+    TooManyRequests(String),
     // 状态码500
     #[error("服务器错误: {0}")]
     InternalServerError(String),
 
-    // 令牌格式不正确错误
-    // 状态码 400
+    // 令牌格式不正确错误 状态码400
     #[error("令牌格式不正确")]
     InvalidTokenFormat,
 
@@ -48,94 +58,197 @@ pub enum AppError {
     DatabaseError(String),
 }
 
-#[derive(Serialize)]
-struct ErrorResponse {
-    code: u16,
-    // error: Option<String>,
-    message: String,
-    data: Option<serde_json::Value>,
+// 字段名到该字段所有校验错误的映射
+pub type FieldErrors = BTreeMap<String, Vec<String>>;
+
+// 统一的错误响应体，HTTP 状态码与 code 一致
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct ErrorResponse {
+    // HTTP 状态码
+    #[schema(example = 404)]
+    pub code: u16,
+    // 稳定的错误码，客户端应根据它而不是 message 判断错误类型
+    #[schema(example = "NOT_FOUND")]
+    pub error: String,
+    // 给用户看的错误信息
+    #[schema(example = "用户不存在")]
+    pub message: String,
+    // 参数校验失败时每个字段的错误
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<HashMap<String, Vec<String>>>, example = json!({"user_name": ["用户名长度必须在3到20个字符之间"]}))]
+    pub fields: Option<FieldErrors>,
+    // 请求ID，与响应头 X-Request-Id 相同，排查问题时提供给管理员
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
 }
-// 错误处理
-impl ResponseError for AppError {
-    fn error_response(&self) -> HttpResponse {
+
+impl ErrorResponse {
+    // 非 AppError 产生的错误（如路由不存在、请求方法不允许），错误码由状态码推导
+    pub fn from_status(status: StatusCode, message: impl Into<String>) -> Self {
+        let error = status
+            .canonical_reason()
+            .unwrap_or("UNKNOWN_ERROR")
+            .to_uppercase()
+            .replace([' ', '-'], "_");
+        ErrorResponse {
+            code: status.as_u16(),
+            error,
+            message: message.into(),
+            fields: None,
+            request_id: None,
+        }
+    }
+
+    pub fn with_request_id(mut self, request_id: Option<String>) -> Self {
+        self.request_id = request_id;
+        self
+    }
+
+    pub fn status(&self) -> StatusCode {
+        StatusCode::from_u16(self.code).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR)
+    }
+
+    pub fn into_response(self) -> HttpResponse {
+        HttpResponse::build(self.status()).json(self)
+    }
+}
+
+impl AppError {
+    pub fn status(&self) -> StatusCode {
         match self {
-            AppError::BadRequest(msg) => HttpResponse::BadRequest().json(ErrorResponse {
-                code: 400,
-                // error: "缺少参数".to_string(),
-                message: msg.to_string(),
-                data: None,
-            }),
-            AppError::NotFound(msg) => HttpResponse::NotFound().json(ErrorResponse {
-                code: 404,
-                message: msg.to_string(),
-                data: None,
-            }),
-            AppError::Unauthorized(msg) => HttpResponse::Unauthorized().json(ErrorResponse {
-                code: 401,
-
-                message: msg.to_string(),
-                data: None,
-            }),
-            AppError::DeserializeError(msg) => HttpResponse::BadRequest().json(ErrorResponse {
-                code: 400,
-                message: msg.to_string(),
-                data: None,
-            }),
-            AppError::Conflict(msg) => {
-                HttpResponse::Conflict().json(serde_json::json!({ "error": msg }))
+            AppError::BadRequest(_)
+            | AppError::DeserializeError(_)
+            | AppError::Validation(_)
+            | AppError::InvalidTokenFormat => StatusCode::BAD_REQUEST,
+            AppError::Unauthorized(_) | AppError::TokenNotFound => StatusCode::UNAUTHORIZED,
+            AppError::Forbidden(_) | AppError::PermissionsEmpty => StatusCode::FORBIDDEN,
+            AppError::NotFound(_) => StatusCode::NOT_FOUND,
+            AppError::Conflict(_) => StatusCode::CONFLICT,
+            AppError::PreconditionFailed(_) => StatusCode::PRECONDITION_FAILED,
+            AppError::TooManyRequests(_) => StatusCode::TOO_MANY_REQUESTS,
+            AppError::InternalServerError(_) | AppError::DatabaseError(_) => {
+                StatusCode::INTERNAL_SERVER_ERROR
             }
+        }
+    }
 
-            AppError::Forbidden(msg) => HttpResponse::Forbidden().json(ErrorResponse {
-                code: 403,
-                message: msg.to_string(),
-                data: None,
-            }),
-
-            AppError::InternalServerError(msg) => {
-                HttpResponse::InternalServerError().json(ErrorResponse {
-                    code: 500,
-                    message: msg.to_string(),
-                    data: None,
-                })
-            }
-            AppError::InvalidTokenFormat => HttpResponse::BadRequest().json(ErrorResponse {
-                code: 400,
-                message: "令牌格式不正确".to_string(),
-                data: None,
-            }),
-            AppError::TokenNotFound => HttpResponse::Unauthorized().json(ErrorResponse {
-                code: 401,
-                message: "令牌未找到".to_string(),
-                data: None,
-            }),
-            AppError::PermissionsEmpty => HttpResponse::Forbidden().json(ErrorResponse {
-                code: 403,
-                message: "权限字符串为空".to_string(),
-                data: None,
-            }),
-            AppError::DatabaseError(msg) => {
-                HttpResponse::InternalServerError().json(ErrorResponse {
-                    code: 500,
-                    message: msg.to_string(),
-                    data: None,
-                })
+    // 每个错误类型对应的错误码，已发布的错误码不要修改
+    pub fn error_code(&self) -> &'static str {
+        match self {
+            AppError::BadRequest(_) => "BAD_REQUEST",
+            AppError::NotFound(_) => "NOT_FOUND",
+            AppError::Unauthorized(_) => "UNAUTHORIZED",
+            AppError::DeserializeError(_) => "INVALID_BODY",
+            AppError::Validation(_) => "VALIDATION_FAILED",
+            AppError::Conflict(_) => "CONFLICT",
+            AppError::Forbidden(_) => "FORBIDDEN",
+            AppError::PreconditionFailed(_) => "PRECONDITION_FAILED",
+            AppError::TooManyRequests(_) => "TOO_MANY_REQUESTS",
+            AppError::InternalServerError(_) => "INTERNAL_ERROR",
+            AppError::InvalidTokenFormat => "INVALID_TOKEN_FORMAT",
+            AppError::TokenNotFound => "TOKEN_NOT_FOUND",
+            AppError::PermissionsEmpty => "PERMISSIONS_EMPTY",
+            AppError::DatabaseError(_) => "DATABASE_ERROR",
+        }
+    }
+
+    // 返回给客户端的信息，不带 Display 中的分类前缀
+    pub fn message(&self) -> String {
+        match self {
+            AppError::BadRequest(msg)
+            | AppError::NotFound(msg)
+            | AppError::Unauthorized(msg)
+            | AppError::DeserializeError(msg)
+            | AppError::Conflict(msg)
+            | AppError::Forbidden(msg)
+            | AppError::PreconditionFailed(msg)
+            | AppError::TooManyRequests(msg)
+            | AppError::InternalServerError(msg)
+            | AppError::DatabaseError(msg) => msg.clone(),
+            AppError::Validation(fields) => fields
+                .iter()
+                .map(|(field, errors)| format!("{}: {}", field, errors.join(", ")))
+                .collect::<Vec<_>>()
+                .join("; "),
+            AppError::InvalidTokenFormat | AppError::TokenNotFound | AppError::PermissionsEmpty => {
+                self.to_string()
             }
         }
     }
+
+    pub fn to_error_response(&self) -> ErrorResponse {
+        ErrorResponse {
+            code: self.status().as_u16(),
+            error: self.error_code().to_string(),
+            message: self.message(),
+            fields: match self {
+                AppError::Validation(fields) => Some(fields.clone()),
+                _ => None,
+            },
+            request_id: None,
+        }
+    }
+}
+
+// 请求ID由 RequestId 中间件补充
+impl ResponseError for AppError {
+    fn status_code(&self) -> StatusCode {
+        self.status()
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        self.to_error_response().into_response()
+    }
 }
 
 // 添加从 JsonPayloadError 的转换
 impl From<actix_web::error::JsonPayloadError> for AppError {
     fn from(err: actix_web::error::JsonPayloadError) -> Self {
         log::error!("JSON解析错误: {}", err);
-        log::error!("JSON解析错误: {}", err.to_string());
         let message = parse_json_error(&err);
         Self::DeserializeError(message)
     }
 }
 
+// 数据库错误的细节只写日志，不返回给客户端
 impl From<sea_orm::DbErr> for AppError {
     fn from(error: sea_orm::DbErr) -> Self {
-        AppError::DatabaseError(error.to_string())
+        log::error!("数据库错误: {}", error);
+        AppError::DatabaseError("服务器异常，请联系管理员".into())
+    }
+}
+
+impl From<ValidationErrors> for AppError {
+    fn from(errors: ValidationErrors) -> Self {
+        let mut fields = FieldErrors::new();
+        collect_field_errors(&errors, "", &mut fields);
+        AppError::Validation(fields)
+    }
+}
+
+// 嵌套结构和列表中的字段使用 a.b、a[0].b 形式的路径
+fn collect_field_errors(errors: &ValidationErrors, prefix: &str, fields: &mut FieldErrors) {
+    for (field, kind) in errors.errors() {
+        let path = match prefix {
+            "" => field.to_string(),
+            _ => format!("{}.{}", prefix, field),
+        };
+        match kind {
+            ValidationErrorsKind::Field(list) => {
+                let messages = fields.entry(path).or_default();
+                for error in list {
+                    messages.push(match &error.message {
+                        Some(message) => message.to_string(),
+                        None => format!("校验规则 {} 未通过", error.code),
+                    });
+                }
+            }
+            ValidationErrorsKind::Struct(inner) => collect_field_errors(inner, &path, fields),
+            ValidationErrorsKind::List(items) => {
+                for (index, inner) in items {
+                    collect_field_errors(inner, &format!("{}[{}]", path, index), fields);
+                }
+            }
+        }
     }
 }
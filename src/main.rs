@@ -1,8 +1,23 @@
 use actix_cors::Cors;
 use actix_web::{middleware::ErrorHandlers, web, App, HttpServer};
 use mysql_user_crud::{
-    api_doc::write_to_file, config_routes, create_db_pool, log::init_logger,
-    middleware::auth::Auth, utils::error_handler::add_error_header, utils::sse::SseNotifier,
+    api_doc::write_to_file,
+    config_routes, create_db_pool,
+    log::init_logger,
+    mail::MAIL_CONFIG,
+    middleware::auth::Auth,
+    middleware::request_id::RequestIdTransform,
+    services::oauth::oauth_http_client,
+    services::roles::seed_builtin_roles,
+    services::routes::{check_route_table, route_table},
+    services::user::spawn_purge_task,
+    two_factor::TwoFactorConfig,
+    utils::error_handler::add_error_header,
+    utils::login_throttle::LoginThrottle,
+    utils::notifier::notifier_from_config,
+    utils::notifier::Notifier,
+    utils::oauth_state::OAuthStateStore,
+    utils::sse::SseNotifier,
     AppError, Logger,
 };
 use std::env;
@@ -10,14 +25,41 @@ use std::env;
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
     init_logger();
+    // 路由表中每个接口都必须声明访问控制，否则拒绝启动
+    check_route_table(&route_table()).map_err(|e| {
+        log::error!("路由表校验失败: {}", e);
+        std::io::Error::other(e)
+    })?;
+    // TOTP密钥加密使用独立的密钥，未配置时拒绝启动
+    TwoFactorConfig::from_env().map_err(|e| {
+        log::error!("双重验证配置无效: {}", e);
+        std::io::Error::other(e)
+    })?;
     let db_pool = create_db_pool().await.map_err(|e| {
         log::error!("数据库连接失败: {}", e);
-        std::io::Error::new(std::io::ErrorKind::Other, e)
+        std::io::Error::other(e)
+    })?;
+    // 补齐内置角色，注册时需要默认角色存在
+    seed_builtin_roles(&db_pool).await.map_err(|e| {
+        log::error!("初始化内置角色失败: {}", e);
+        std::io::Error::other(e.to_string())
     })?;
     write_to_file();
     // 将数据库连接池添加到应用程序数据
     let app_data = web::Data::new(db_pool);
+    // 定时匿名化超过保留期的注销账户
+    spawn_purge_task(app_data.clone());
     let notifier = web::Data::new(SseNotifier::new());
+    let login_throttle = web::Data::new(LoginThrottle::default());
+    let oauth_states = web::Data::new(OAuthStateStore::default());
+    let oauth_client = web::Data::new(oauth_http_client().map_err(|e| {
+        log::error!("{}", e);
+        std::io::Error::other(e.to_string())
+    })?);
+    // 通知渠道由 MAIL_TRANSPORT 选择，默认写入日志
+    let mailer: web::Data<dyn Notifier> = web::Data::from(
+        notifier_from_config(&MAIL_CONFIG).map_err(|e| std::io::Error::other(e.to_string()))?,
+    );
     // 获取服务器地址和端口
     let host = env::var("SERVER_HOST").unwrap_or_else(|_| "0.0.0.0".to_string());
     let port = env::var("SERVER_PORT").unwrap_or_else(|_| "18080".to_string());
@@ -28,7 +70,15 @@ async fn main() -> std::io::Result<()> {
         let cors = Cors::default()
             .allowed_origin("http://127.0.0.1:5502")
             .allowed_methods(vec!["GET", "POST", "PUT", "DELETE", "OPTIONS"])
-            .allowed_headers(vec!["Content-Type", "Authorization", "ACCEPT"])
+            .allowed_headers(vec![
+                "Content-Type",
+                "Authorization",
+                "ACCEPT",
+                "If-Match",
+                "If-None-Match",
+                "X-Request-Id",
+            ])
+            .expose_headers(vec!["ETag", "X-Request-Id"])
             .supports_credentials()
             .max_age(3600);
         App::new()
@@ -39,10 +89,16 @@ async fn main() -> std::io::Result<()> {
                     .error_handler(|err, _req| AppError::from(err).into()),
             )
             .app_data(notifier.clone())
+            .app_data(login_throttle.clone())
+            .app_data(oauth_states.clone())
+            .app_data(oauth_client.clone())
+            .app_data(mailer.clone())
             .app_data(app_data.clone())
             .wrap(Logger)
             .wrap(Auth)
             .wrap(actix_web::middleware::Logger::default())
+            // 放在认证之外，认证失败的响应也带有请求ID
+            .wrap(RequestIdTransform)
             .configure(config_routes)
             .wrap(cors)
     })
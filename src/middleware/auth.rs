@@ -1,15 +1,24 @@
-use crate::jsonwebtoken::{extract_token, has_permission};
+use crate::jsonwebtoken::{extract_token, has_permission, AuthToken, TokenClaims};
+use crate::models::user::Entity as UserEntity;
+use crate::services::api_keys::authenticate_api_key;
+use crate::services::roles::effective_permissions;
+use crate::services::routes::{route_table, Access};
+use crate::services::user::ensure_active;
+use crate::utils::current_user::CurrentUser;
 use crate::AppError;
 
 use actix_web::{
-    dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform},
-    Error,
+    body::EitherBody,
+    dev::{forward_ready, ResourceDef, Service, ServiceRequest, ServiceResponse, Transform},
+    http::Method,
+    web, Error, HttpMessage,
 };
 use log::{error, info};
 use sea_orm::DatabaseConnection;
 use std::{
     future::{ready, Future, Ready},
     pin::Pin,
+    rc::Rc,
 };
 
 pub type DbPool = DatabaseConnection;
@@ -17,79 +26,230 @@ pub struct Auth;
 
 impl<S, B> Transform<S, ServiceRequest> for Auth
 where
-    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
     S::Future: 'static,
     B: 'static,
 {
-    type Response = ServiceResponse<B>;
+    type Response = ServiceResponse<EitherBody<B>>;
     type Error = Error;
     type InitError = ();
     type Transform = AuthMiddleware<S>;
     type Future = Ready<Result<Self::Transform, Self::InitError>>;
 
     fn new_transform(&self, service: S) -> Self::Future {
-        ready(Ok(AuthMiddleware { service }))
+        ready(Ok(AuthMiddleware {
+            service: Rc::new(service),
+            public_routes: Rc::new(routes_with(Access::Public)),
+            session_only_routes: Rc::new(routes_with(Access::SessionOnly)),
+        }))
     }
 }
 
 pub struct AuthMiddleware<S> {
-    service: S,
+    service: Rc<S>,
+    public_routes: Rc<Vec<(Method, ResourceDef)>>,
+    session_only_routes: Rc<Vec<(Method, ResourceDef)>>,
+}
+
+// 路由表中标记为 Access::Public 的接口无需认证，Access::SessionOnly 的接口不接受API密钥
+fn routes_with(access: Access) -> Vec<(Method, ResourceDef)> {
+    route_table()
+        .into_iter()
+        .filter(|route| route.access == access)
+        .map(|route| (route.method, ResourceDef::new(route.path)))
+        .collect()
+}
+
+fn matches(routes: &[(Method, ResourceDef)], method: &Method, path: &str) -> bool {
+    routes
+        .iter()
+        .any(|(route_method, def)| route_method == method && def.is_match(path))
 }
 
 impl<S, B> Service<ServiceRequest> for AuthMiddleware<S>
 where
-    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
     S::Future: 'static,
     B: 'static,
 {
-    type Response = ServiceResponse<B>;
+    type Response = ServiceResponse<EitherBody<B>>;
     type Error = Error;
     type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>>>>;
     forward_ready!(service);
 
     fn call(&self, req: ServiceRequest) -> Self::Future {
-        let path = req.path().to_string(); // 克隆 path
-        let headers = req.headers().clone(); // 克隆 headers
-        error!("path: {}, headers: {:?}", path, headers);
-        let fut = self.service.call(req);
+        let service = Rc::clone(&self.service);
+        let is_public = matches(&self.public_routes, req.method(), req.path());
+        let session_only = matches(&self.session_only_routes, req.method(), req.path());
         Box::pin(async move {
-            let public_paths = vec![
-                "/api/auth/login",
-                "/api/auth/register",
-                "/api/posts",
-                "/api/comments",
-                "/api/auth/permissions",
-                "/sse/stream",
-                "/api/sse/stream",
-                "/swagger-ui/",
-            ];
-            if public_paths.contains(&path.as_str()) {
-                let res = fut.await?;
-                Ok(res)
-            } else {
-                let token = extract_token(&headers);
-                if let Some(token) = token {
-                    let permission_result = has_permission(&token);
-                    match permission_result {
-                        Ok(_token_data) => {
-                            info!("令牌有效");
-                            let res = fut.await?;
-                            Ok(res)
-                        }
-                        Err(err) => {
-                            // 处理解码错误
-                            error!("解码令牌时发生错误: {:?}", err);
-                            let err = AppError::Unauthorized("无效的令牌".to_string());
-
-                            Err(err.into())
-                        }
-                    }
-                } else {
-                    // 没有找到令牌
-                    let err = AppError::Unauthorized("令牌未找到".to_string());
-                    Err(err.into())
+            if is_public {
+                return service
+                    .call(req)
+                    .await
+                    .map(ServiceResponse::map_into_left_body);
+            }
+            // 认证失败时返回错误响应而不是 Err，外层的 RequestId 中间件才能补充请求ID
+            match authenticate(&req, session_only).await {
+                Ok(current_user) => {
+                    req.extensions_mut().insert(current_user);
+                    service
+                        .call(req)
+                        .await
+                        .map(ServiceResponse::map_into_left_body)
                 }
+                Err(err) => Ok(req.error_response(err).map_into_right_body()),
             }
         })
     }
 }
+
+// 先完成认证再交给后续服务，路由守卫和处理函数可以直接读取当前用户
+async fn authenticate(req: &ServiceRequest, session_only: bool) -> Result<CurrentUser, AppError> {
+    let db = req
+        .app_data::<web::Data<DbPool>>()
+        .cloned()
+        .ok_or_else(|| AppError::InternalServerError("数据库未配置".into()))?;
+    let claims = match extract_token(req.headers()) {
+        Some(AuthToken::Jwt(token)) => match has_permission(&token) {
+            Ok(token_data) => {
+                // 令牌签发后账户可能已被禁用或注销
+                let user = UserEntity::find_by_uuid(&token_data.claims.user_uuid)
+                    .one(db.as_ref())
+                    .await
+                    .map_err(AppError::from)?
+                    .ok_or_else(|| AppError::Unauthorized("无效的令牌".to_string()))?;
+                ensure_active(&user)?;
+                // 权限按用户当前的角色和授权实时计算，令牌中的权限只是签发时的快照，
+                // 撤销角色或权限后立即生效，不必等旧令牌过期
+                let permissions = effective_permissions(db.as_ref(), &user).await?;
+                info!("令牌有效");
+                TokenClaims {
+                    user_name: user.user_name,
+                    permissions: Some(permissions.bits().to_string()),
+                    ..token_data.claims
+                }
+            }
+            Err(err) => {
+                // 处理解码错误
+                error!("解码令牌时发生错误: {:?}", err);
+                return Err(AppError::Unauthorized("无效的令牌".to_string()));
+            }
+        },
+        Some(AuthToken::ApiKey(_)) if session_only => {
+            return Err(AppError::Forbidden(
+                "该接口只能使用登录令牌访问，不支持API密钥".into(),
+            ));
+        }
+        Some(AuthToken::ApiKey(key)) => {
+            let (claims, context) = authenticate_api_key(db.as_ref(), &key).await?;
+            info!("API密钥 {} 有效", context.key_id);
+            req.extensions_mut().insert(context);
+            claims
+        }
+        None => {
+            // 没有找到令牌
+            return Err(AppError::Unauthorized("令牌未找到".to_string()));
+        }
+    };
+    Ok(CurrentUser::from(claims))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::permission::Permission;
+    use crate::jsonwebtoken::JWT_SECRET;
+    use crate::models::{roles, user, user_roles};
+    use actix_web::{http::header, test, App, HttpResponse};
+    use chrono::Utc;
+    use jsonwebtoken::{encode, EncodingKey, Header};
+    use sea_orm::{DatabaseBackend, MockDatabase};
+
+    #[actix_web::test]
+    async fn api_keys_cannot_reach_session_only_routes() {
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(
+                    MockDatabase::new(DatabaseBackend::MySql)
+                        .append_query_results([Vec::<crate::models::api_keys::Model>::new()])
+                        .into_connection(),
+                ))
+                .wrap(Auth)
+                .route("/api/me", web::put().to(HttpResponse::Ok))
+                .route("/api/users", web::get().to(HttpResponse::Ok)),
+        )
+        .await;
+
+        // 账户管理接口直接拒绝，不会查询密钥
+        let req = test::TestRequest::put()
+            .uri("/api/me")
+            .insert_header((header::AUTHORIZATION, "ApiKey rwk_leaked"))
+            .to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), 403);
+
+        // 普通接口仍按密钥认证
+        let req = test::TestRequest::get()
+            .uri("/api/users")
+            .insert_header((header::AUTHORIZATION, "ApiKey rwk_leaked"))
+            .to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), 401);
+    }
+
+    #[actix_web::test]
+    async fn jwt_permissions_are_recomputed_from_current_roles() {
+        // 令牌签发时用户是管理员，之后角色被调整为只读
+        let claims = TokenClaims {
+            user_uuid: "u-1".into(),
+            user_name: "old_name".into(),
+            exp: (Utc::now().timestamp() + 3600) as usize,
+            permissions: Some(Permission::all().bits().to_string()),
+            jti: Some("jti-1".into()),
+        };
+        let token = encode(
+            &Header::default(),
+            &claims,
+            &EncodingKey::from_secret(JWT_SECRET.as_bytes()),
+        )
+        .unwrap();
+        let db = MockDatabase::new(DatabaseBackend::MySql)
+            .append_query_results([vec![user::Model::fixture("u-1", "alice01")]])
+            .append_query_results([vec![user_roles::Model {
+                id: 1,
+                user_uuid: "u-1".into(),
+                role_id: 4,
+                created_at: Utc::now(),
+            }]])
+            .append_query_results([vec![roles::Model {
+                id: 4,
+                name: "reader".into(),
+                description: None,
+                permissions: Permission::READ_ARTICLE.bits().to_string(),
+                is_builtin: 1,
+                created_at: Utc::now(),
+                updated_at: Utc::now(),
+            }]])
+            .into_connection();
+        let app = test::init_service(App::new().app_data(web::Data::new(db)).wrap(Auth).route(
+            "/api/users",
+            web::get().to(|user: CurrentUser| async move {
+                HttpResponse::Ok().json((user.name, user.permissions.bits()))
+            }),
+        ))
+        .await;
+
+        let req = test::TestRequest::get()
+            .uri("/api/users")
+            .insert_header((header::AUTHORIZATION, format!("Bearer {}", token)))
+            .to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), 200);
+        let (name, bits): (String, u64) = test::read_body_json(res).await;
+        assert_eq!(name, "alice01");
+        assert_eq!(
+            Permission::from_bits_truncate(bits),
+            Permission::READ_ARTICLE
+        );
+    }
+}
@@ -27,68 +27,4 @@ impl<T: Serialize> Resp<T> {
     }
 }
 
-impl Resp<()> {
-    pub fn err(error: AppError) -> Self {
-        match error {
-            AppError::BadRequest(msg) => Resp {
-                code: 400,
-                message: msg,
-                data: None,
-            },
-            AppError::NotFound(msg) => Resp {
-                code: 404,
-                message: msg,
-                data: None,
-            },
-            AppError::Unauthorized(msg) => Resp {
-                code: 401,
-                message: msg,
-                data: None,
-            },
-            AppError::DeserializeError(msg) => Resp {
-                code: 400,
-                message: msg,
-                data: None,
-            },
-            AppError::Conflict(msg) => Resp {
-                code: 409,
-                message: msg,
-                data: None,
-            },
-
-            AppError::Forbidden(msg) => Resp {
-                code: 403,
-                message: msg,
-                data: None,
-            },
-
-            AppError::InternalServerError(msg) => Resp {
-                code: 500,
-                message: msg,
-                data: None,
-            },
-            AppError::InvalidTokenFormat => Resp {
-                code: 401,
-                message: "令牌格式不正确".to_owned(),
-                data: None,
-            },
-            AppError::TokenNotFound => Resp {
-                code: 401,
-                message: "令牌未找到".to_owned(),
-                data: None,
-            },
-            AppError::PermissionsEmpty => Resp {
-                code: 401,
-                message: "权限字符串为空".to_owned(),
-                data: None,
-            },
-            AppError::DatabaseError(msg) => Resp {
-                code: 500,
-                message: msg,
-                data: None,
-            },
-        }
-    }
-}
-
 pub type SimpleResp = Result<HttpResponse, AppError>;
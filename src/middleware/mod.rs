@@ -1,3 +1,4 @@
 pub mod auth;
 pub mod helpers;
 pub mod logger;
+pub mod request_id;
@@ -0,0 +1,158 @@
+use crate::utils::error_handler::error_body;
+use actix_web::{
+    body::{EitherBody, MessageBody},
+    dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform},
+    http::header::{HeaderMap, HeaderName, HeaderValue},
+    Error, HttpMessage, HttpRequest,
+};
+use futures_util::future::{ready, LocalBoxFuture, Ready};
+use uuid::Uuid;
+
+pub const REQUEST_ID_HEADER: HeaderName = HeaderName::from_static("x-request-id");
+// 客户端传入的请求ID最长保留的字符数
+const MAX_REQUEST_ID_LEN: usize = 64;
+
+// 当前请求的ID，写入请求扩展，同时通过 X-Request-Id 响应头和错误响应体返回
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RequestId(pub String);
+
+impl RequestId {
+    pub fn of(req: &HttpRequest) -> Option<String> {
+        req.extensions().get::<RequestId>().map(|id| id.0.clone())
+    }
+
+    // 沿用网关或客户端传入的请求ID，方便串联日志；格式不合法时重新生成
+    fn from_headers(headers: &HeaderMap) -> Self {
+        let incoming = headers
+            .get(&REQUEST_ID_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .filter(|id| {
+                !id.is_empty()
+                    && id.len() <= MAX_REQUEST_ID_LEN
+                    && id
+                        .chars()
+                        .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+            });
+        match incoming {
+            Some(id) => RequestId(id.to_string()),
+            None => RequestId(Uuid::new_v4().simple().to_string()),
+        }
+    }
+}
+
+pub struct RequestIdTransform;
+
+impl<S, B> Transform<S, ServiceRequest> for RequestIdTransform
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = RequestIdMiddleware<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(RequestIdMiddleware { service }))
+    }
+}
+
+pub struct RequestIdMiddleware<S> {
+    service: S,
+}
+
+impl<S, B> Service<ServiceRequest> for RequestIdMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let request_id = RequestId::from_headers(req.headers());
+        req.extensions_mut().insert(request_id.clone());
+        let fut = self.service.call(req);
+
+        Box::pin(async move {
+            let mut res = match fut.await? {
+                // 认证中间件等位于 ErrorHandlers 之外，它们返回的错误响应在这里改写为统一格式
+                res if res.response().error().is_some() => {
+                    let (req, res) = res.into_parts();
+                    let body = error_body(res.error(), res.status())
+                        .with_request_id(Some(request_id.0.clone()));
+                    ServiceResponse::new(req, body.into_response()).map_into_right_body()
+                }
+                res => res.map_into_left_body(),
+            };
+            if let Ok(value) = HeaderValue::from_str(&request_id.0) {
+                res.headers_mut().insert(REQUEST_ID_HEADER, value);
+            }
+            Ok(res)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::error::ErrorResponse;
+    use crate::AppError;
+    use actix_web::{test, web, App, HttpResponse};
+
+    #[actix_web::test]
+    async fn request_id_is_echoed_and_added_to_middleware_errors() {
+        let app = test::init_service(
+            App::new()
+                // 模拟认证中间件直接返回错误
+                .wrap_fn(|req, srv| {
+                    let res = match req.path() {
+                        "/fail" => {
+                            Err(req.error_response(AppError::Unauthorized("令牌未找到".into())))
+                        }
+                        _ => Ok(srv.call(req)),
+                    };
+                    async move {
+                        match res {
+                            Ok(fut) => fut.await.map(ServiceResponse::map_into_left_body),
+                            Err(res) => Ok(res.map_into_right_body()),
+                        }
+                    }
+                })
+                .wrap(RequestIdTransform)
+                .route("/ok", web::get().to(HttpResponse::Ok)),
+        )
+        .await;
+
+        let req = test::TestRequest::get()
+            .uri("/ok")
+            .insert_header((REQUEST_ID_HEADER, "trace-123"))
+            .to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.headers().get(REQUEST_ID_HEADER).unwrap(), "trace-123");
+
+        // 不合法的请求ID会被替换
+        let req = test::TestRequest::get()
+            .uri("/ok")
+            .insert_header((REQUEST_ID_HEADER, "bad id with spaces"))
+            .to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.headers().get(REQUEST_ID_HEADER).unwrap().len(), 32);
+
+        let req = test::TestRequest::get()
+            .uri("/fail")
+            .insert_header((REQUEST_ID_HEADER, "trace-456"))
+            .to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), 401);
+        let body: ErrorResponse = test::read_body_json(res).await;
+        assert_eq!(body.error, "UNAUTHORIZED");
+        assert_eq!(body.request_id.as_deref(), Some("trace-456"));
+    }
+}
@@ -1,5 +1,5 @@
 //! `SeaORM` Entity, @generated by sea-orm-codegen 1.1.12
-use sea_orm::{entity::prelude::*, DeleteResult};
+use sea_orm::entity::prelude::*;
 use serde::{Deserialize, Serialize};
 use serde_json::Value as JsonValue;
 #[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel, Serialize, Deserialize)]
@@ -12,6 +12,7 @@ pub struct Model {
     pub content: String,
     pub cover: String,
     pub author: String,
+    pub author_uuid: Option<String>,
     pub publish_time: DateTime,
     pub update_time: DateTime,
     pub views: i32,
@@ -78,3 +79,31 @@ impl TryFrom<JsonValue> for Model {
 }
 
 impl ActiveModelBehavior for ActiveModel {}
+
+#[cfg(test)]
+impl Model {
+    // 测试用的未删除文章
+    pub fn fixture(uuid: &str, author_uuid: Option<&str>) -> Self {
+        let now = chrono::Utc::now().naive_utc();
+        Model {
+            id: 1,
+            title: "标题".to_string(),
+            content: String::new(),
+            cover: String::new(),
+            author: "tester".to_string(),
+            author_uuid: author_uuid.map(str::to_string),
+            publish_time: now,
+            update_time: now,
+            views: 0,
+            is_top: 0,
+            is_recommend: 0,
+            is_delete: 0,
+            is_publish: 0,
+            is_hide: 0,
+            description: String::new(),
+            size: 0,
+            category_id: None,
+            uuid: uuid.to_string(),
+        }
+    }
+}
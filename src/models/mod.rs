@@ -1,12 +1,19 @@
+pub mod api_keys;
 pub mod article;
 pub mod article_tags;
 pub mod categories;
 pub mod github_commits;
 pub mod github_repositories;
 pub mod library_tags;
+pub mod login_history;
+pub mod password_reset_tokens;
 pub mod prelude;
+pub mod roles;
 pub mod sea_orm_active_enums;
 pub mod storage;
 pub mod tags;
 pub mod third_party_libraries;
+pub mod two_factor_recovery_codes;
 pub mod user;
+pub mod user_identities;
+pub mod user_roles;
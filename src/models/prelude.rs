@@ -1,10 +1,17 @@
+pub use super::api_keys::Entity as ApiKeys;
 pub use super::article::Entity as Article;
 pub use super::article_tags::Entity as ArticleTags;
 pub use super::categories::Entity as Categories;
 pub use super::github_commits::Entity as GithubCommits;
 pub use super::github_repositories::Entity as GithubRepositories;
 pub use super::library_tags::Entity as LibraryTags;
+pub use super::login_history::Entity as LoginHistory;
+pub use super::password_reset_tokens::Entity as PasswordResetTokens;
+pub use super::roles::Entity as Roles;
 pub use super::storage::Entity as Storage;
 pub use super::tags::Entity as Tags;
 pub use super::third_party_libraries::Entity as ThirdPartyLibraries;
+pub use super::two_factor_recovery_codes::Entity as TwoFactorRecoveryCodes;
 pub use super::user::Entity as Users;
+pub use super::user_identities::Entity as UserIdentities;
+pub use super::user_roles::Entity as UserRoles;
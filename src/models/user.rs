@@ -1,3 +1,4 @@
+use super::sea_orm_active_enums::UserStatus;
 use sea_orm::{entity::prelude::*, DeleteResult};
 use serde::{Deserialize, Serialize};
 use serde_json::Value as JsonValue;
@@ -10,13 +11,19 @@ pub struct Model {
     pub uuid: String, // 用户UUID
     #[sea_orm(unique)]
     pub user_name: String, // 用户名
-    pub pass_word: String,           // 密码
-    pub email: Option<String>,       // 邮箱
-    pub image: Option<String>,       // 头像
-    pub phone: Option<String>,       // 手机号
-    pub role: Option<String>,        // 角色
-    pub permissions: Option<String>, // 权限
-    pub binding: Option<String>,     // authentication绑定
+    pub pass_word: String,                      // 密码
+    pub email: Option<String>,                  // 邮箱
+    pub email_verified_at: Option<DateTimeUtc>, // 邮箱验证时间
+    pub image: Option<String>,                  // 头像
+    pub phone: Option<String>,                  // 手机号
+    pub role: Option<String>,                   // 角色
+    pub permissions: Option<String>,            // 额外授予的权限，在角色权限之外追加
+    pub denied_permissions: Option<String>,     // 禁止的权限，优先于角色和额外授予的权限
+    pub binding: Option<String>,                // authentication绑定
+    pub pending_binding: Option<String>,        // 待确认的authentication绑定
+    pub status: UserStatus,                     // 账户状态
+    pub deleted_at: Option<DateTimeUtc>,        // 注销时间
+    pub anonymized_at: Option<DateTimeUtc>,     // 匿名化时间，匿名化后不可恢复
     #[sea_orm(default_value_t = DateTimeUtc::default())]
     pub created_at: DateTimeUtc,
     #[sea_orm(default_value_t = DateTimeUtc::default())]
@@ -60,3 +67,37 @@ impl TryFrom<JsonValue> for Model {
 }
 
 impl ActiveModelBehavior for ActiveModel {}
+
+impl Model {
+    // 是否已开启双重验证，空字符串和 NULL 都视为未绑定
+    pub fn two_factor_enabled(&self) -> bool {
+        self.binding.as_deref().is_some_and(|b| !b.is_empty())
+    }
+}
+
+#[cfg(test)]
+impl Model {
+    // 测试用的正常状态用户
+    pub fn fixture(uuid: &str, user_name: &str) -> Self {
+        Model {
+            id: 1,
+            uuid: uuid.to_string(),
+            user_name: user_name.to_string(),
+            pass_word: String::new(),
+            email: None,
+            email_verified_at: None,
+            image: None,
+            phone: None,
+            role: None,
+            permissions: None,
+            denied_permissions: None,
+            binding: None,
+            pending_binding: None,
+            status: UserStatus::Active,
+            deleted_at: None,
+            anonymized_at: None,
+            created_at: DateTimeUtc::default(),
+            updated_at: DateTimeUtc::default(),
+        }
+    }
+}
@@ -0,0 +1,377 @@
+use crate::common::CommonResponse;
+use crate::dto::api_key::CreateApiKeyRequest;
+use crate::error::error::{AppError, ErrorResponse};
+use crate::jsonwebtoken::TokenClaims;
+use crate::middleware::helpers::{Resp, SimpleResp};
+use crate::models::api_keys::{self, Entity as ApiKeyEntity};
+use crate::models::user::Entity as UserEntity;
+use crate::permission::Permission;
+use crate::services::roles::effective_permissions;
+use crate::services::user::ensure_active;
+use crate::utils::crypto::sha256_hex;
+use crate::utils::current_user::CurrentUser;
+use actix_web::{web, HttpMessage, HttpRequest};
+use base64::engine::general_purpose;
+use base64::engine::Engine as _;
+use chrono::Utc;
+use log::{error, info, warn};
+use rand::RngCore;
+use sea_orm::{
+    ActiveModelTrait, ActiveValue::Set, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter,
+    QueryOrder,
+};
+use serde::Serialize;
+use utoipa::ToSchema;
+use validator::Validate;
+
+// 密钥明文前缀，便于在日志和代码仓库中识别泄露的密钥
+const API_KEY_PREFIX: &str = "rwk_";
+// 列表中展示的密钥前几位长度
+const DISPLAY_PREFIX_LEN: usize = 12;
+
+// 通过API密钥认证的请求会在扩展中附带该信息
+#[derive(Debug, Clone, Copy)]
+pub struct ApiKeyContext {
+    pub key_id: i32,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct ApiKeyInfo {
+    pub id: i32,
+    pub name: String,
+    pub key_prefix: String,
+    pub permissions: Vec<String>,
+    pub expires_at: Option<String>,
+    pub last_used_at: Option<String>,
+    pub created_at: String,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct CreatedApiKey {
+    #[serde(flatten)]
+    pub info: ApiKeyInfo,
+    // 完整密钥只在创建时返回一次
+    pub key: String,
+}
+
+impl From<api_keys::Model> for ApiKeyInfo {
+    fn from(model: api_keys::Model) -> Self {
+        ApiKeyInfo {
+            id: model.id,
+            name: model.name,
+            key_prefix: model.key_prefix,
+            permissions: Permission::from_stored(Some(&model.permissions)).names(),
+            expires_at: model.expires_at.map(|t| t.to_string()),
+            last_used_at: model.last_used_at.map(|t| t.to_string()),
+            created_at: model.created_at.to_string(),
+        }
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/api-keys",
+    request_body = CreateApiKeyRequest,
+    tag = "API密钥",
+    operation_id = "创建API密钥",
+    responses(
+        (status = 200, description = "创建成功，返回的完整密钥只展示这一次", body = CommonResponse<CreatedApiKey>),
+        (status = 400, description = "权限名称无效", body = ErrorResponse),
+        (status = 403, description = "申请的权限超出当前用户的权限", body = ErrorResponse),
+    ),
+)]
+// 为当前用户创建API密钥
+pub async fn create_api_key(
+    db: web::Data<DatabaseConnection>,
+    req: HttpRequest,
+    current_user: CurrentUser,
+    payload: web::Json<CreateApiKeyRequest>,
+) -> SimpleResp {
+    if let Err(e) = payload.validate() {
+        info!("创建API密钥:{:?}", e);
+        return Err(e.into());
+    }
+    let payload = payload.into_inner();
+    // 不允许用API密钥再创建新的密钥，避免密钥泄露后被无限续期
+    if req.extensions().get::<ApiKeyContext>().is_some() {
+        return Err(AppError::Forbidden("API密钥不能用于创建新的密钥".into()));
+    }
+
+    let requested = Permission::from_names(&payload.permissions)
+        .map_err(|name| AppError::BadRequest(format!("无效权限: {}", name)))?;
+    let user = UserEntity::find_by_uuid(&current_user.uuid)
+        .one(db.as_ref())
+        .await?
+        .ok_or_else(|| AppError::NotFound("用户不存在".into()))?;
+    let owned = effective_permissions(db.as_ref(), &user).await?;
+    if !owned.contains(requested) {
+        let missing = (requested - owned).names().join(", ");
+        return Err(AppError::Forbidden(format!(
+            "不能授予自己没有的权限: {}",
+            missing
+        )));
+    }
+
+    let key = generate_api_key();
+    let record = api_keys::ActiveModel {
+        user_uuid: Set(user.uuid.clone()),
+        name: Set(payload.name),
+        key_prefix: Set(key.chars().take(DISPLAY_PREFIX_LEN).collect()),
+        key_hash: Set(sha256_hex(&key)),
+        permissions: Set(requested.bits().to_string()),
+        expires_at: Set(payload
+            .expires_in_days
+            .map(|days| Utc::now() + chrono::Duration::days(days as i64))),
+        created_at: Set(Utc::now()),
+        ..Default::default()
+    }
+    .insert(db.as_ref())
+    .await?;
+
+    info!("用户 {} 创建了API密钥 {}", user.uuid, record.id);
+    let data = CreatedApiKey {
+        info: record.into(),
+        key,
+    };
+    Resp::ok(data, "创建API密钥成功").to_json_result()
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/api-keys",
+    tag = "API密钥",
+    operation_id = "获取API密钥列表",
+    responses(
+        (status = 200, description = "获取成功", body = CommonResponse<Vec<ApiKeyInfo>>),
+    ),
+)]
+// 列出当前用户的API密钥，不包含完整密钥
+pub async fn list_api_keys(
+    db: web::Data<DatabaseConnection>,
+    current_user: CurrentUser,
+) -> SimpleResp {
+    let keys = ApiKeyEntity::find()
+        .filter(api_keys::Column::UserUuid.eq(&current_user.uuid))
+        .order_by_desc(api_keys::Column::Id)
+        .all(db.as_ref())
+        .await?
+        .into_iter()
+        .map(ApiKeyInfo::from)
+        .collect::<Vec<_>>();
+    Resp::ok(keys, "获取API密钥列表成功").to_json_result()
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/api-keys/{id}",
+    tag = "API密钥",
+    operation_id = "删除API密钥",
+    params(
+        ("id" = i32, Path, description = "API密钥ID")
+    ),
+    responses(
+        (status = 200, description = "删除成功", body = CommonResponse<String>),
+        (status = 404, description = "密钥不存在", body = ErrorResponse),
+    ),
+)]
+// 删除当前用户的API密钥，删除后立即失效
+pub async fn delete_api_key(
+    db: web::Data<DatabaseConnection>,
+    current_user: CurrentUser,
+    id: web::Path<i32>,
+) -> SimpleResp {
+    let result = ApiKeyEntity::delete_many()
+        .filter(api_keys::Column::Id.eq(*id))
+        .filter(api_keys::Column::UserUuid.eq(&current_user.uuid))
+        .exec(db.as_ref())
+        .await?;
+    if result.rows_affected == 0 {
+        return Err(AppError::NotFound("API密钥不存在".into()));
+    }
+
+    info!("用户 {} 删除了API密钥 {}", current_user.uuid, id);
+    Resp::ok("", "删除API密钥成功").to_json_result()
+}
+
+// 校验API密钥并转换成与JWT相同的令牌声明，权限取密钥权限与用户当前有效权限的交集
+pub async fn authenticate_api_key(
+    db: &DatabaseConnection,
+    key: &str,
+) -> Result<(TokenClaims, ApiKeyContext), AppError> {
+    let invalid = || AppError::Unauthorized("API密钥无效或已过期".into());
+    if !key.starts_with(API_KEY_PREFIX) {
+        return Err(invalid());
+    }
+
+    let record = ApiKeyEntity::find()
+        .filter(api_keys::Column::KeyHash.eq(sha256_hex(key)))
+        .one(db)
+        .await?
+        .ok_or_else(invalid)?;
+    if record
+        .expires_at
+        .is_some_and(|expires_at| expires_at <= Utc::now())
+    {
+        warn!("API密钥 {} 已过期", record.id);
+        return Err(invalid());
+    }
+    let user = UserEntity::find_by_uuid(&record.user_uuid)
+        .one(db)
+        .await?
+        .ok_or_else(invalid)?;
+    ensure_active(&user)?;
+
+    let permissions = Permission::from_stored(Some(&record.permissions))
+        & effective_permissions(db, &user).await?;
+    let claims = TokenClaims {
+        user_uuid: user.uuid,
+        user_name: user.user_name,
+        exp: record
+            .expires_at
+            .map_or(usize::MAX, |expires_at| expires_at.timestamp() as usize),
+        permissions: Some(permissions.bits().to_string()),
+        jti: Some(format!("api_key:{}", record.id)),
+    };
+    let context = ApiKeyContext { key_id: record.id };
+
+    // 使用时间只用于展示，更新失败不影响本次请求
+    let mut record_active: api_keys::ActiveModel = record.into();
+    record_active.last_used_at = Set(Some(Utc::now()));
+    if let Err(e) = record_active.update(db).await {
+        error!("更新API密钥使用时间失败: {}", e);
+    }
+
+    Ok((claims, context))
+}
+
+fn generate_api_key() -> String {
+    let mut bytes = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    format!(
+        "{}{}",
+        API_KEY_PREFIX,
+        general_purpose::URL_SAFE_NO_PAD.encode(bytes)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::jsonwebtoken::{extract_token, AuthToken};
+    use crate::models::{roles, user, user_roles};
+    use actix_web::http::header::{HeaderMap, HeaderValue, AUTHORIZATION};
+    use sea_orm::{DatabaseBackend, MockDatabase, MockExecResult};
+
+    fn api_key(key: &str, permissions: Permission) -> api_keys::Model {
+        api_keys::Model {
+            id: 7,
+            user_uuid: "u-1".into(),
+            name: "ci".into(),
+            key_prefix: key[..DISPLAY_PREFIX_LEN].to_string(),
+            key_hash: sha256_hex(key),
+            permissions: permissions.bits().to_string(),
+            expires_at: None,
+            last_used_at: None,
+            created_at: Utc::now(),
+        }
+    }
+
+    fn role(permissions: Permission) -> roles::Model {
+        roles::Model {
+            id: 1,
+            name: "editor".into(),
+            description: None,
+            permissions: permissions.bits().to_string(),
+            is_builtin: 1,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn api_keys_are_read_from_their_own_scheme() {
+        let key = generate_api_key();
+        assert!(key.starts_with(API_KEY_PREFIX));
+
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            AUTHORIZATION,
+            HeaderValue::from_str(&format!("ApiKey  {} ", key)).unwrap(),
+        );
+        assert!(matches!(extract_token(&headers), Some(AuthToken::ApiKey(k)) if k == key));
+
+        headers.insert(
+            AUTHORIZATION,
+            HeaderValue::from_static("Bearer abc.def.ghi"),
+        );
+        assert!(matches!(extract_token(&headers), Some(AuthToken::Jwt(_))));
+        headers.insert(
+            AUTHORIZATION,
+            HeaderValue::from_static("Basic dXNlcjpwYXNz"),
+        );
+        assert!(extract_token(&headers).is_none());
+    }
+
+    #[actix_web::test]
+    async fn keys_without_prefix_are_rejected_before_lookup() {
+        let db = MockDatabase::new(DatabaseBackend::MySql).into_connection();
+        let err = authenticate_api_key(&db, "sk_live_abc").await.unwrap_err();
+        assert!(matches!(err, AppError::Unauthorized(_)));
+        assert!(db.into_transaction_log().is_empty());
+    }
+
+    #[actix_web::test]
+    async fn expired_keys_are_rejected() {
+        let key = generate_api_key();
+        let mut record = api_key(&key, Permission::READ_ARTICLE);
+        record.expires_at = Some(Utc::now() - chrono::Duration::seconds(1));
+        let db = MockDatabase::new(DatabaseBackend::MySql)
+            .append_query_results([vec![record]])
+            .into_connection();
+        let err = authenticate_api_key(&db, &key).await.unwrap_err();
+        assert!(matches!(err, AppError::Unauthorized(_)));
+        // 只按摘要查询，不会继续读取用户
+        assert_eq!(db.into_transaction_log().len(), 1);
+    }
+
+    #[actix_web::test]
+    async fn key_is_looked_up_by_hash_and_limited_to_user_permissions() {
+        let key = generate_api_key();
+        let record = api_key(
+            &key,
+            Permission::READ_ARTICLE | Permission::WRITE_ARTICLE | Permission::WRITE_SYSTEM,
+        );
+        let db = MockDatabase::new(DatabaseBackend::MySql)
+            .append_query_results([vec![record.clone()]])
+            .append_query_results([vec![user::Model::fixture("u-1", "alice01")]])
+            .append_query_results([vec![user_roles::Model {
+                id: 1,
+                user_uuid: "u-1".into(),
+                role_id: 1,
+                created_at: Utc::now(),
+            }]])
+            .append_query_results([vec![role(
+                Permission::READ_ARTICLE | Permission::WRITE_ARTICLE,
+            )]])
+            .append_exec_results([MockExecResult {
+                last_insert_id: 0,
+                rows_affected: 1,
+            }])
+            .append_query_results([vec![record]])
+            .into_connection();
+
+        let (claims, context) = authenticate_api_key(&db, &key).await.unwrap();
+        assert_eq!(context.key_id, 7);
+        assert_eq!(claims.jti.as_deref(), Some("api_key:7"));
+        // 密钥声明了系统写权限，但用户本身没有，最终只保留两者的交集
+        let permissions = Permission::from_stored(claims.permissions.as_deref());
+        assert_eq!(
+            permissions,
+            Permission::READ_ARTICLE | Permission::WRITE_ARTICLE
+        );
+
+        let lookup = db.into_transaction_log()[0].statements()[0].to_string();
+        assert!(lookup.contains(&sha256_hex(&key)));
+        assert!(!lookup.contains(&key));
+    }
+}
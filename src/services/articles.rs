@@ -1,91 +1,429 @@
-use crate::common::{
-    CommonResponse, PaginatedResponse, PaginationInfo, PaginationQuery, DEFAULT_PAGE_SIZE,
-    MAX_PAGE_SIZE,
+use crate::common::{dedup_ids, BatchResult, CommonResponse, ListPage, PaginationQuery, Paginator};
+use crate::dto::article::{
+    ArticleBatchAction, ArticleBatchRequest, ArticleInfo, CreateArticleRequest,
+    UpdateArticleRequest,
 };
-use crate::config::permission::{Permission, PERMISSION_MAP};
-use crate::data_processing::{deep_filter_data, filter_value};
-use crate::dto::user::{UpdateUserRequest, UserDto};
-use crate::error::error::AppError;
+use crate::error::error::{AppError, ErrorResponse};
 use crate::middleware::helpers::{Resp, SimpleResp};
 use crate::models::article::{self, Entity as ArticleEntity};
-use crate::models::user::{self, Entity as UserEntity};
+use crate::models::article_tags::{self, Entity as ArticleTagEntity};
+use crate::models::categories::Entity as CategoriesEntity;
+use crate::models::tags::{self, Entity as TagsEntity};
+use crate::utils::current_user::CurrentUser;
+use crate::utils::etag::{check_if_match, conditional_ok, etag_of, tagged_ok, update_if_unchanged};
+use crate::utils::ownership::{authorize_owner, Owned};
 use crate::utils::query_parameter::Query;
-use crate::utils::sse::SseNotifier;
-use actix_web::web;
-use actix_web::HttpResponse;
+use actix_web::{web, HttpRequest};
 use chrono::Utc;
-use log::{error, info};
+use log::info;
 use sea_orm::ActiveValue::Set;
 use sea_orm::{
-    ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, PaginatorTrait, QueryFilter,
-    QueryOrder, QuerySelect,
+    ActiveModelTrait, ColumnTrait, Condition, ConnectionTrait, DatabaseConnection, EntityTrait,
+    PaginatorTrait, QueryFilter, QuerySelect, TransactionTrait,
 };
-use serde::Serialize;
-use utoipa::ToSchema;
-use uuid::Uuid; // 添加uuid crate依赖
+use uuid::Uuid;
 use validator::Validate;
-// 示例接口
+#[utoipa::path(
+    get,
+    path = "/api/articles",
+    tag = "文章",
+    operation_id = "获取文章列表",
+    params(PaginationQuery),
+    responses(
+        (status = 200, description = "获取文章列表成功，传入 cursor 时为游标分页", body = CommonResponse<ListPage<ArticleInfo>>),
+        (status = 400, description = "分页参数错误", body = ErrorResponse),
+    ),
+)]
+// 获取文章列表，带有分页
 pub async fn get_article(
     db: web::Data<DatabaseConnection>,
     query: Query<PaginationQuery>,
 ) -> SimpleResp {
-    // 验证分页参数
-    let validated_query = match query.validate() {
-        Ok(_) => query.into_inner(),
-        Err(e) => {
-            log::error!("分页参数验证失败: {:?}", e);
-
-            return Resp::err(AppError::DatabaseError("分页参数验证失败".to_string()))
-                .to_json_result();
+    let page = Paginator::new(ArticleEntity::find())
+        .filter(article::Column::IsDelete.eq(0))
+        .fetch(db.as_ref(), &query.into_inner())
+        .await?
+        .map(ArticleInfo::from);
+    Resp::ok(page, "获取文章列表成功").to_json_result()
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/articles",
+    request_body = CreateArticleRequest,
+    tag = "文章",
+    operation_id = "创建文章",
+    responses(
+        (status = 200, description = "创建文章成功", body = CommonResponse<ArticleInfo>),
+        (status = 400, description = "参数错误或分类不存在", body = ErrorResponse),
+    ),
+)]
+// 创建文章，作者为当前登录用户
+pub async fn create_article(
+    db: web::Data<DatabaseConnection>,
+    current_user: CurrentUser,
+    payload: web::Json<CreateArticleRequest>,
+) -> SimpleResp {
+    if let Err(e) = payload.validate() {
+        info!("创建文章:{:?}", e);
+        return Err(e.into());
+    }
+    let payload = payload.into_inner();
+    if let Some(category_id) = payload.category_id {
+        ensure_category_exists(db.as_ref(), category_id).await?;
+    }
+
+    let now = Utc::now().naive_utc();
+    let created = article::ActiveModel {
+        title: Set(payload.title),
+        size: Set(payload.content.len() as i32),
+        content: Set(payload.content),
+        cover: Set(payload.cover),
+        description: Set(payload.description),
+        category_id: Set(payload.category_id),
+        is_publish: Set(payload.is_publish as i8),
+        author: Set(current_user.name),
+        author_uuid: Set(Some(current_user.uuid)),
+        publish_time: Set(now),
+        update_time: Set(now),
+        uuid: Set(Uuid::new_v4().to_string()),
+        ..Default::default()
+    }
+    .insert(db.as_ref())
+    .await?;
+
+    info!("用户 {:?} 创建了文章 {}", created.author_uuid, created.uuid);
+    Resp::ok(ArticleInfo::from(created), "创建文章成功").to_json_result()
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/articles/{uuid}",
+    tag = "文章",
+    operation_id = "获取文章详情",
+    params(
+        ("uuid" = String, Path, description = "文章的 UUID")
+    ),
+    responses(
+        (status = 200, description = "获取文章成功，响应头 ETag 为当前版本", body = CommonResponse<ArticleInfo>),
+        (status = 304, description = "If-None-Match 与当前版本一致，文章未修改"),
+        (status = 404, description = "文章不存在", body = ErrorResponse),
+    ),
+)]
+// 获取单篇文章，修改前用返回的 ETag 作为 If-Match 可以避免覆盖他人的修改
+pub async fn get_article_by_uuid(
+    req: HttpRequest,
+    db: web::Data<DatabaseConnection>,
+    uuid: web::Path<String>,
+) -> SimpleResp {
+    let article = ArticleEntity::find()
+        .filter(article::Column::Uuid.eq(uuid.as_str()))
+        .filter(article::Column::IsDelete.eq(0))
+        .one(db.as_ref())
+        .await?
+        .ok_or_else(|| AppError::NotFound("文章不存在".into()))?;
+    conditional_ok(&req, ArticleInfo::from(article), "获取文章成功")
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/articles/{uuid}",
+    request_body = UpdateArticleRequest,
+    tag = "文章",
+    operation_id = "修改文章",
+    params(
+        ("uuid" = String, Path, description = "文章的 UUID")
+    ),
+    responses(
+        (status = 200, description = "修改文章成功", body = CommonResponse<ArticleInfo>),
+        (status = 400, description = "参数错误或分类不存在", body = ErrorResponse),
+        (status = 403, description = "只能修改自己的文章", body = ErrorResponse),
+        (status = 404, description = "文章不存在", body = ErrorResponse),
+        (status = 412, description = "If-Match 与当前版本不一致，或读取后文章已被他人修改", body = ErrorResponse),
+    ),
+)]
+// 修改文章，只有作者本人或管理员可以操作；携带 If-Match 时只在版本一致时修改
+pub async fn update_article(
+    req: HttpRequest,
+    db: web::Data<DatabaseConnection>,
+    article: Owned<article::Model>,
+    payload: web::Json<UpdateArticleRequest>,
+) -> SimpleResp {
+    if let Err(e) = payload.validate() {
+        info!("修改文章:{:?}", e);
+        return Err(e.into());
+    }
+    let payload = payload.into_inner();
+    let article = article.into_inner();
+    check_if_match(&req, &etag_of(&ArticleInfo::from(article.clone())))?;
+
+    let mut article_active: article::ActiveModel = article.clone().into();
+    if let Some(title) = payload.title {
+        article_active.title = Set(title);
+    }
+    if let Some(content) = payload.content {
+        article_active.size = Set(content.len() as i32);
+        article_active.content = Set(content);
+    }
+    if let Some(cover) = payload.cover {
+        article_active.cover = Set(cover);
+    }
+    if let Some(description) = payload.description {
+        article_active.description = Set(description);
+    }
+    if let Some(category_id) = payload.category_id {
+        ensure_category_exists(db.as_ref(), category_id).await?;
+        article_active.category_id = Set(Some(category_id));
+    }
+    if let Some(is_publish) = payload.is_publish {
+        article_active.is_publish = Set(is_publish as i8);
+    }
+    article_active.update_time = Set(Utc::now().naive_utc());
+
+    let updated = update_article_if_unchanged(db.as_ref(), &article, article_active).await?;
+    tagged_ok(ArticleInfo::from(updated), "修改文章成功")
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/articles/{uuid}",
+    tag = "文章",
+    operation_id = "删除文章",
+    params(
+        ("uuid" = String, Path, description = "文章的 UUID")
+    ),
+    responses(
+        (status = 200, description = "删除文章成功", body = CommonResponse<String>),
+        (status = 403, description = "只能删除自己的文章", body = ErrorResponse),
+        (status = 404, description = "文章不存在", body = ErrorResponse),
+        (status = 412, description = "If-Match 与当前版本不一致，或读取后文章已被他人修改", body = ErrorResponse),
+    ),
+)]
+// 删除文章（软删除），只有作者本人或管理员可以操作
+pub async fn delete_article(
+    req: HttpRequest,
+    db: web::Data<DatabaseConnection>,
+    article: Owned<article::Model>,
+) -> SimpleResp {
+    let article = article.into_inner();
+    check_if_match(&req, &etag_of(&ArticleInfo::from(article.clone())))?;
+    let article_uuid = article.uuid.clone();
+    let mut article_active: article::ActiveModel = article.clone().into();
+    article_active.is_delete = Set(1);
+    article_active.update_time = Set(Utc::now().naive_utc());
+    update_article_if_unchanged(db.as_ref(), &article, article_active).await?;
+
+    info!("文章 {} 已删除", article_uuid);
+    Resp::ok("", "删除文章成功").to_json_result()
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/articles/batch",
+    request_body = ArticleBatchRequest,
+    tag = "文章",
+    operation_id = "批量操作文章",
+    responses(
+        (status = 200, description = "批量操作完成，返回每篇文章的处理结果", body = CommonResponse<BatchResult>),
+        (status = 400, description = "参数错误、分类或标签不存在", body = ErrorResponse),
+        (status = 500, description = "数据库错误，整批修改回滚，没有任何文章被修改", body = ErrorResponse),
+    ),
+    description = "整批提交：不存在或无权操作的文章在结果中标记为失败并跳过，数据库错误会回滚整批修改",
+)]
+// 批量发布、取消发布、删除文章或修改分类和标签，每篇文章和单篇接口一样校验归属。
+// 所有修改在同一个事务中提交，数据库出错时整批回滚并返回错误
+pub async fn batch_articles(
+    db: web::Data<DatabaseConnection>,
+    current_user: CurrentUser,
+    payload: web::Json<ArticleBatchRequest>,
+) -> SimpleResp {
+    if let Err(e) = payload.validate() {
+        info!("批量操作文章:{:?}", e);
+        return Err(e.into());
+    }
+    let payload = payload.into_inner();
+    check_batch_action(db.as_ref(), &payload.action).await?;
+
+    let uuids = dedup_ids(&payload.uuids);
+    let txn = db.begin().await?;
+    // 在事务内加锁读取，避免并发修改在检查和写入之间改变文章状态
+    let articles = ArticleEntity::find()
+        .filter(article::Column::Uuid.is_in(uuids.clone()))
+        .filter(article::Column::IsDelete.eq(0))
+        .lock_exclusive()
+        .all(&txn)
+        .await?;
+
+    let mut result = BatchResult::default();
+    for uuid in &uuids {
+        let Some(article) = articles.iter().find(|a| &a.uuid == uuid) else {
+            result.fail(uuid, "文章不存在");
+            continue;
+        };
+        if let Err(e) = authorize_owner(article, &current_user) {
+            result.fail(uuid, e.to_string());
+            continue;
+        }
+        apply_batch_action(&txn, article.clone(), &payload.action).await?;
+        result.ok(uuid);
+    }
+    txn.commit().await?;
+
+    info!(
+        "用户 {} 批量操作文章 {:?}: 成功 {} 篇，失败 {} 篇",
+        current_user.uuid, payload.action, result.succeeded, result.failed
+    );
+    Resp::ok(result, "批量操作文章完成").to_json_result()
+}
+
+// 只在文章的更新时间仍是读取时的值时写入，返回写入后的文章
+async fn update_article_if_unchanged<C: ConnectionTrait>(
+    db: &C,
+    read: &article::Model,
+    article_active: article::ActiveModel,
+) -> Result<article::Model, AppError> {
+    let unchanged = Condition::all()
+        .add(article::Column::Id.eq(read.id))
+        .add(article::Column::UpdateTime.eq(read.update_time));
+    update_if_unchanged(db, article_active, unchanged).await?;
+    ArticleEntity::find_by_id(read.id)
+        .one(db)
+        .await?
+        .ok_or_else(|| AppError::NotFound("文章不存在".into()))
+}
+
+// 分类和标签对所有文章都一样，提前校验
+async fn check_batch_action(
+    db: &DatabaseConnection,
+    action: &ArticleBatchAction,
+) -> Result<(), AppError> {
+    match action {
+        ArticleBatchAction::ChangeCategory { category_id } => {
+            ensure_category_exists(db, *category_id).await?;
         }
-    };
-    let page = validated_query.page.unwrap_or(1);
-    let limit = validated_query.limit.unwrap_or(DEFAULT_PAGE_SIZE);
-
-    // 限制每页数量的范围
-    let limit = if limit == 0 {
-        DEFAULT_PAGE_SIZE
-    } else if limit > MAX_PAGE_SIZE {
-        MAX_PAGE_SIZE
-    } else {
-        limit
-    };
-    let offset = (page - 1) * limit;
-
-    // 获取总数和分页数据
-    let (total, articles) = tokio::try_join!(
-        ArticleEntity::find().count(db.as_ref()),
-        ArticleEntity::find()
-            .order_by_desc(article::Column::Id)
-            .offset(Some(offset))
-            .limit(Some(limit))
-            .all(db.as_ref())
-    )
-    .map_err(|e| {
-        error!("数据库操作获取文章列表失败: {}", e);
-        AppError::DatabaseError("服务器异常，请联系管理员".to_string())
-    })?;
-    let total_pages = (total + limit - 1) / limit; // 整数除法避免浮点误差
-
-    info!("total1: {}, articles: {:?}, ", total, articles);
-    let data = deep_filter_data(articles, vec!["size"]);
-    // 获取分页用户数据
-    let response = PaginatedResponse {
-        data,
-        pagination: PaginationInfo {
-            total,
-            total_pages,
-            current_page: page,
-            limit,
-            has_next: page < total_pages,
-            has_previous: page > 1,
-        },
-    };
-
-    Resp::ok(response, "获取用户列表成功").to_json_result()
+        ArticleBatchAction::AddTags { tag_ids } | ArticleBatchAction::RemoveTags { tag_ids } => {
+            let tag_ids = dedup_ids(tag_ids);
+            if tag_ids.is_empty() {
+                return Err(AppError::BadRequest("标签不能为空".into()));
+            }
+            let found = TagsEntity::find()
+                .filter(tags::Column::Id.is_in(tag_ids.clone()))
+                .count(db)
+                .await?;
+            if found != tag_ids.len() as u64 {
+                return Err(AppError::BadRequest("部分标签不存在".into()));
+            }
+        }
+        ArticleBatchAction::Publish | ArticleBatchAction::Unpublish | ArticleBatchAction::Trash => {
+        }
+    }
+    Ok(())
+}
+
+// 单篇和批量修改共用的分类校验，不存在时返回 400 而不是外键错误
+async fn ensure_category_exists(db: &DatabaseConnection, category_id: i32) -> Result<(), AppError> {
+    if CategoriesEntity::find_by_id(category_id)
+        .one(db)
+        .await?
+        .is_none()
+    {
+        return Err(AppError::BadRequest(format!("分类不存在: {}", category_id)));
+    }
+    Ok(())
 }
 
-pub async fn create_article() -> HttpResponse {
-    //
-    HttpResponse::Ok().body("创建文章")
+async fn apply_batch_action<C: ConnectionTrait>(
+    db: &C,
+    article: article::Model,
+    action: &ArticleBatchAction,
+) -> Result<(), AppError> {
+    let article_id = article.id;
+    let mut article_active: article::ActiveModel = article.into();
+    match action {
+        ArticleBatchAction::Publish => article_active.is_publish = Set(1),
+        ArticleBatchAction::Unpublish => article_active.is_publish = Set(0),
+        ArticleBatchAction::Trash => article_active.is_delete = Set(1),
+        ArticleBatchAction::ChangeCategory { category_id } => {
+            article_active.category_id = Set(Some(*category_id))
+        }
+        ArticleBatchAction::AddTags { tag_ids } => {
+            let existing: Vec<i32> = ArticleTagEntity::find()
+                .filter(article_tags::Column::ArticleId.eq(article_id))
+                .all(db)
+                .await?
+                .into_iter()
+                .map(|t| t.tags_id)
+                .collect();
+            for tag_id in dedup_ids(tag_ids) {
+                if existing.contains(&tag_id) {
+                    continue;
+                }
+                article_tags::ActiveModel {
+                    article_id: Set(article_id),
+                    tags_id: Set(tag_id),
+                }
+                .insert(db)
+                .await?;
+            }
+        }
+        ArticleBatchAction::RemoveTags { tag_ids } => {
+            ArticleTagEntity::delete_many()
+                .filter(article_tags::Column::ArticleId.eq(article_id))
+                .filter(article_tags::Column::TagsId.is_in(tag_ids.clone()))
+                .exec(db)
+                .await?;
+        }
+    }
+    article_active.update_time = Set(Utc::now().naive_utc());
+    article_active.update(db).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::permission::Permission;
+    use actix_web::{dev::Service as _, test, App, HttpMessage};
+    use sea_orm::{DatabaseBackend, MockDatabase};
+    use std::sync::Arc;
+
+    #[actix_web::test]
+    async fn update_with_missing_category_is_rejected() {
+        let db = MockDatabase::new(DatabaseBackend::MySql)
+            .append_query_results([vec![article::Model::fixture("a-1", Some("u-1"))]])
+            .append_query_results([Vec::<crate::models::categories::Model>::new()])
+            .into_connection();
+        let db = web::Data::new(db);
+        let app = test::init_service(
+            App::new()
+                .app_data(db.clone())
+                .wrap_fn(|req, srv| {
+                    req.extensions_mut().insert(CurrentUser {
+                        uuid: "u-1".into(),
+                        name: "tester".into(),
+                        permissions: Permission::WRITE_ARTICLE,
+                        token_id: None,
+                    });
+                    srv.call(req)
+                })
+                .route("/api/articles/{uuid}", web::put().to(update_article)),
+        )
+        .await;
+        let req = test::TestRequest::put()
+            .uri("/api/articles/a-1")
+            .set_json(serde_json::json!({"category_id": 42}))
+            .to_request();
+        let status = test::call_service(&app, req).await.status();
+        assert_eq!(status, 400);
+
+        drop(app);
+        let log = Arc::try_unwrap(db.into_inner())
+            .map(DatabaseConnection::into_transaction_log)
+            .unwrap();
+        // 校验分类后直接返回，不会写入
+        assert_eq!(log.len(), 2);
+        assert!(log[1].statements()[0]
+            .to_string()
+            .contains("`categories`.`id` = 42"));
+    }
 }
@@ -1,43 +1,57 @@
 use crate::common::CommonResponse;
-use crate::dto::user::{LoginRequest, RegisterResponse, UserDto};
-use crate::error::error::AppError;
-use crate::jsonwebtoken::TokenClaims;
+use crate::config::throttle::TRUSTED_PROXIES;
+use crate::dto::user::{
+    is_phone_number, normalize_email, normalize_phone, LoginRequest, RegisterResponse,
+    TwoFactorLoginRequest, UserInfo,
+};
+use crate::error::error::{AppError, ErrorResponse};
+use crate::jsonwebtoken::{
+    decode_challenge_token, generate_challenge_token, TokenClaims, JWT_SECRET,
+};
 use crate::middleware::helpers::{Resp, SimpleResp};
+use crate::models::login_history;
 use crate::models::user::{self, Entity as UserEntity, Model};
 use crate::permission::Permission;
 use crate::permission::{PERMISSION_LIST, PERMISSION_MAP};
-use crate::services::user::UserInfo;
-use actix_web::{web, Result};
-use bcrypt::{hash, verify, DEFAULT_COST};
+use crate::services::authenticator::{
+    consume_recovery_code, reencrypt_legacy_binding, user_totp_secret, verify_totp,
+};
+use crate::services::email::send_verification_email;
+use crate::services::roles::{assign_default_role, effective_permissions};
+use crate::services::user::ensure_active;
+use crate::utils::login_throttle::LoginThrottle;
+use crate::utils::notifier::Notifier;
+use crate::utils::password_hash::{hash_password, needs_rehash, verify_password};
+use actix_web::http::header;
+use actix_web::{web, HttpRequest, Result};
 use chrono::Utc;
 use jsonwebtoken::{encode, EncodingKey, Header};
+use lazy_static::lazy_static;
 use log::{error, info, warn};
 use sea_orm::{
     entity::prelude::*, ActiveModelTrait, ActiveValue::Set, ColumnTrait, DatabaseConnection,
-    EntityTrait, PaginatorTrait, QueryFilter,
+    EntityTrait, PaginatorTrait, QueryFilter, TransactionTrait,
 };
 use serde::{Deserialize, Serialize};
-use std::time::SystemTime;
+use std::net::IpAddr;
+use std::time::{Duration, SystemTime};
 use utoipa::ToSchema;
 use validator::Validate;
 
-// #[derive(Debug, Serialize, ToSchema)]
-// pub struct UserInfo {
-//     pub user_name: String,
-//     pub email: Option<String>,
-//     pub phone: Option<String>,
-//     pub role: Option<String>,
-//     pub permissions: Option<String>,
-//     pub created_at: DateTimeUtc,
-//     pub updated_at: DateTimeUtc,
-//     pub id: i32,
-//     pub uuid: String,
-// }
 #[derive(Debug, Serialize)]
 pub struct DataResponse<T> {
     data: T,
 }
 
+// 双重验证挑战令牌有效期（秒）
+const TWO_FACTOR_CHALLENGE_EXPIRES_IN: u64 = 300;
+
+lazy_static! {
+    // 用户名不存在时用于对齐校验耗时的哈希
+    static ref DUMMY_PASSWORD_HASH: String =
+        hash_password("dummy-password").expect("生成占位哈希失败");
+}
+
 #[derive(Serialize, ToSchema)]
 pub struct LoginData {
     pub user: UserInfo,
@@ -45,6 +59,13 @@ pub struct LoginData {
     pub expires_in: u64,
 }
 
+#[derive(Serialize, ToSchema)]
+pub struct TwoFactorChallengeData {
+    pub two_factor_required: bool,
+    pub challenge_token: String,
+    pub expires_in: u64,
+}
+
 #[utoipa::path(
     post,
     path = "/api/auth/login",
@@ -52,52 +73,354 @@ pub struct LoginData {
     tag = "鉴权模块",
     operation_id = "用户登录",
     responses(
-        (status = 200, description = "登录成功", body = CommonResponse<LoginData>),
-        (status = 400, description = "验证错误", body = SimpleRespData),
-        (status = 404, description = "用户名不存在", body = SimpleRespData),
-        (status = 500, description = "服务器内部错误", body = SimpleRespData),
+        (status = 200, description = "登录成功，已开启双重验证时返回挑战令牌", body = CommonResponse<LoginData>),
+        (status = 400, description = "验证错误", body = ErrorResponse),
+        (status = 401, description = "账号或密码错误", body = ErrorResponse),
+        (status = 429, description = "失败次数过多，账户或IP被临时锁定", body = ErrorResponse),
+        (status = 500, description = "服务器内部错误", body = ErrorResponse),
     ),
 )]
 pub async fn login(
     db: web::Data<DatabaseConnection>,
+    throttle: web::Data<LoginThrottle>,
+    req: HttpRequest,
     user_data: web::Json<LoginRequest>,
 ) -> SimpleResp {
     match user_data.validate() {
         Ok(_) => {}
         Err(e) => {
             info!("{:?}", e); // 打印验证错误
-            return Resp::err(AppError::DeserializeError(e.to_string())).to_json_result();
+            return Err(e.into());
         }
     };
     let user_data = user_data.into_inner(); // 提取内部数据
+    let client = ClientInfo::from_request(&req);
 
-    // 检查用户名是否存在
-    let credentials = UserEntity::find()
-        .filter(user::Column::UserName.eq(&user_data.user_name))
+    let credentials = find_login_user(db.as_ref(), &user_data.identifier)
+        .await
+        .map_err(|e| AppError::InternalServerError(format!("检查用户名时发生错误: {}", e)))?;
+    // 用户名、邮箱登录共用同一个失败计数，避免换标识绕过限制
+    let throttle_key = credentials
+        .as_ref()
+        .map_or(user_data.identifier.as_str(), |c| c.user_name.as_str())
+        .to_string();
+
+    // 被临时锁定期间即使密码正确也不允许登录
+    if let Err(wait) = throttle.check(&throttle_key, &client.ip) {
+        warn!("用户 '{}' 登录被限制，IP: {}", throttle_key, client.ip);
+        record_login(
+            db.as_ref(),
+            None,
+            &throttle_key,
+            &client,
+            false,
+            Some("throttled"),
+        )
+        .await;
+        return Err(too_many_attempts(wait));
+    }
+
+    // 用户不存在与密码错误返回相同的结果，避免泄露账号是否存在
+    let credentials = match credentials {
+        Some(credentials) if verify_password(&user_data.pass_word, &credentials.pass_word) => {
+            credentials
+        }
+        credentials => {
+            if credentials.is_none() {
+                // 执行一次等价的哈希校验，让响应时间与密码错误时一致
+                verify_password(&user_data.pass_word, &DUMMY_PASSWORD_HASH);
+            }
+            throttle.record_failure(&throttle_key, &client.ip);
+            let user_uuid = credentials.as_ref().map(|c| c.uuid.as_str());
+            record_login(
+                db.as_ref(),
+                user_uuid,
+                &throttle_key,
+                &client,
+                false,
+                Some("invalid_credentials"),
+            )
+            .await;
+            return Err(AppError::Unauthorized("账号或密码错误".into()));
+        }
+    };
+
+    // 旧的 bcrypt 哈希或参数过期的哈希在密码校验通过后透明升级
+    let credentials = rehash_if_needed(db.as_ref(), credentials, &user_data.pass_word).await;
+
+    complete_login(db.as_ref(), &throttle, credentials, &client).await
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/auth/login/2fa",
+    request_body = TwoFactorLoginRequest,
+    tag = "鉴权模块",
+    operation_id = "双重验证登录",
+    responses(
+        (status = 200, description = "登录成功", body = CommonResponse<LoginData>),
+        (status = 400, description = "验证错误", body = ErrorResponse),
+        (status = 401, description = "挑战令牌或验证码无效", body = ErrorResponse),
+        (status = 429, description = "失败次数过多，账户或IP被临时锁定", body = ErrorResponse),
+    ),
+)]
+// 使用挑战令牌和TOTP验证码（或一次性恢复码）换取访问令牌
+pub async fn login_2fa(
+    db: web::Data<DatabaseConnection>,
+    throttle: web::Data<LoginThrottle>,
+    req: HttpRequest,
+    payload: web::Json<TwoFactorLoginRequest>,
+) -> SimpleResp {
+    if let Err(e) = payload.validate() {
+        info!("双重验证登录:{:?}", e);
+        return Err(e.into());
+    }
+    let payload = payload.into_inner();
+    let client = ClientInfo::from_request(&req);
+
+    let challenge = decode_challenge_token(&payload.challenge_token)?;
+    let credentials = UserEntity::find_by_uuid(&challenge.user_uuid)
         .one(db.as_ref())
         .await
-        .map_err(|e| AppError::InternalServerError(format!("检查用户名时发生错误: {}", e)))?
-        .ok_or(AppError::NotFound(format!(
-            "用户名 '{}' 不存在",
-            user_data.user_name
-        )))?;
-
-    // 验证密码（使用 bcrypt 验证加密后的密码）
-    if let Err(_) = verify(&user_data.pass_word, &credentials.pass_word) {
-        return Resp::err(AppError::Unauthorized("用户名或密码错误".into())).to_json_result();
+        .map_err(|e| AppError::InternalServerError(format!("查询用户时发生错误: {}", e)))?
+        .ok_or_else(|| AppError::Unauthorized("挑战令牌无效或已过期".into()))?;
+    ensure_active(&credentials)?;
+
+    // 验证码同样计入失败次数，防止在挑战令牌有效期内暴力猜测
+    if let Err(wait) = throttle.check(&credentials.user_name, &client.ip) {
+        record_login(
+            db.as_ref(),
+            Some(&credentials.uuid),
+            &credentials.user_name,
+            &client,
+            false,
+            Some("throttled"),
+        )
+        .await;
+        return Err(too_many_attempts(wait));
     }
 
+    let secret = user_totp_secret(&credentials)?
+        .ok_or_else(|| AppError::Unauthorized("当前账户未开启双重验证".into()))?;
+    let verified = match (&payload.code, &payload.recovery_code) {
+        (Some(code), _) => verify_totp(&secret, code),
+        (None, Some(recovery_code)) => {
+            consume_recovery_code(db.as_ref(), &credentials.uuid, recovery_code).await?
+        }
+        (None, None) => return Err(AppError::BadRequest("请提供验证码或恢复码".into())),
+    };
+    if !verified {
+        warn!("用户 {} 双重验证失败", credentials.uuid);
+        throttle.record_failure(&credentials.user_name, &client.ip);
+        record_login(
+            db.as_ref(),
+            Some(&credentials.uuid),
+            &credentials.user_name,
+            &client,
+            false,
+            Some("invalid_2fa_code"),
+        )
+        .await;
+        return Err(AppError::Unauthorized("验证码无效".into()));
+    }
+    reencrypt_legacy_binding(db.as_ref(), &credentials, &secret).await;
+
+    issue_access_token(db.as_ref(), &throttle, credentials, &client).await
+}
+
+// 身份校验通过后的公共流程：已开启双重验证的账户返回挑战令牌，否则签发访问令牌
+pub async fn complete_login(
+    db: &DatabaseConnection,
+    throttle: &LoginThrottle,
+    credentials: Model,
+    client: &ClientInfo,
+) -> SimpleResp {
+    ensure_active(&credentials)?;
+    // 已绑定双重验证的账户需要再提交TOTP验证码才能拿到访问令牌
+    if credentials.two_factor_enabled() {
+        let challenge_token =
+            generate_challenge_token(&credentials.uuid, TWO_FACTOR_CHALLENGE_EXPIRES_IN)?;
+        let challenge = TwoFactorChallengeData {
+            two_factor_required: true,
+            challenge_token,
+            expires_in: TWO_FACTOR_CHALLENGE_EXPIRES_IN,
+        };
+        return Resp::ok(challenge, "请输入双重验证码").to_json_result();
+    }
+
+    issue_access_token(db, throttle, credentials, client).await
+}
+
+// 按用户当前的有效权限签发访问令牌，并记录登录成功
+async fn issue_access_token(
+    db: &DatabaseConnection,
+    throttle: &LoginThrottle,
+    credentials: Model,
+    client: &ClientInfo,
+) -> SimpleResp {
+    let permissions = effective_permissions(db, &credentials).await?;
     // 生成JWT令牌
     let token = generate_jwt(
         &credentials,
-        "secret_key",
+        permissions,
+        JWT_SECRET,
         3600, // 可配置的过期时间
     )?;
-    Resp::ok(build_login_response(credentials, token), "登录成功").to_json_result()
+    throttle.record_success(&credentials.user_name);
+    record_login(
+        db,
+        Some(&credentials.uuid),
+        &credentials.user_name,
+        client,
+        true,
+        None,
+    )
+    .await;
+    Resp::ok(
+        build_login_response(credentials, permissions, token),
+        "登录成功",
+    )
+    .to_json_result()
+}
+
+// 按登录标识的格式查找用户：含 @ 的先匹配已验证的邮箱，手机号格式的先匹配规范化后的手机号，
+// 都没有命中时再按用户名匹配。邮箱和手机号优先，历史上与之同名的用户名无法遮蔽对方的登录
+async fn find_login_user(
+    db: &DatabaseConnection,
+    identifier: &str,
+) -> Result<Option<Model>, DbErr> {
+    if identifier.contains('@') {
+        // 未验证的邮箱不能用于登录，防止抢注他人邮箱后冒用
+        let by_email = UserEntity::find()
+            .filter(user::Column::Email.eq(normalize_email(identifier)))
+            .filter(user::Column::EmailVerifiedAt.is_not_null())
+            .one(db)
+            .await?;
+        if by_email.is_some() {
+            return Ok(by_email);
+        }
+    } else if is_phone_number(identifier) {
+        // 手机号唯一，并且登录仍需校验该账户的密码，抢注号码无法冒用他人账户
+        let by_phone = UserEntity::find()
+            .filter(user::Column::Phone.eq(normalize_phone(identifier)))
+            .one(db)
+            .await?;
+        if by_phone.is_some() {
+            return Ok(by_phone);
+        }
+    }
+
+    UserEntity::find()
+        .filter(user::Column::UserName.eq(identifier))
+        .one(db)
+        .await
+}
+
+// 使用当前的 Argon2id 参数重新生成密码哈希，失败时保留原哈希继续登录
+async fn rehash_if_needed(db: &DatabaseConnection, credentials: Model, password: &str) -> Model {
+    if !needs_rehash(&credentials.pass_word) {
+        return credentials;
+    }
+    let new_hash = match hash_password(password) {
+        Ok(new_hash) => new_hash,
+        Err(e) => {
+            error!("用户 {} 密码哈希升级失败: {}", credentials.uuid, e);
+            return credentials;
+        }
+    };
+
+    let mut user_active: user::ActiveModel = credentials.clone().into();
+    user_active.pass_word = Set(new_hash);
+    match user_active.update(db).await {
+        Ok(updated) => {
+            info!("用户 {} 的密码哈希已升级为 Argon2id", updated.uuid);
+            updated
+        }
+        Err(e) => {
+            error!("用户 {} 密码哈希升级失败: {}", credentials.uuid, e);
+            credentials
+        }
+    }
+}
+
+// 登录请求的客户端信息
+pub struct ClientInfo {
+    ip: String,
+    user_agent: Option<String>,
+}
+
+impl ClientInfo {
+    pub fn from_request(req: &HttpRequest) -> Self {
+        let ip = client_ip(req, &TRUSTED_PROXIES);
+        let user_agent = req
+            .headers()
+            .get(header::USER_AGENT)
+            .and_then(|v| v.to_str().ok())
+            .map(|v| v.chars().take(255).collect());
+        ClientInfo { ip, user_agent }
+    }
+}
+
+// 直连地址是受信任的代理时，从 X-Forwarded-For 末尾往前取第一个不属于代理的地址，
+// 更靠前的条目由客户端自行填写，不可信
+fn client_ip(req: &HttpRequest, trusted_proxies: &[IpAddr]) -> String {
+    let Some(peer) = req.peer_addr().map(|addr| addr.ip()) else {
+        return "unknown".to_string();
+    };
+    if !trusted_proxies.contains(&peer) {
+        return peer.to_string();
+    }
+    req.headers()
+        .get_all("x-forwarded-for")
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .collect::<Vec<_>>()
+        .into_iter()
+        .rev()
+        .filter_map(|ip| ip.trim().parse::<IpAddr>().ok())
+        .find(|ip| !trusted_proxies.contains(ip))
+        .unwrap_or(peer)
+        .to_string()
+}
+
+fn too_many_attempts(wait: Duration) -> AppError {
+    AppError::TooManyRequests(format!(
+        "登录失败次数过多，请在{}秒后重试",
+        wait.as_secs().max(1)
+    ))
+}
+
+// 写入登录记录，失败只记录日志，不影响登录流程
+async fn record_login(
+    db: &DatabaseConnection,
+    user_uuid: Option<&str>,
+    user_name: &str,
+    client: &ClientInfo,
+    success: bool,
+    reason: Option<&str>,
+) {
+    let record = login_history::ActiveModel {
+        user_uuid: Set(user_uuid.map(str::to_string)),
+        user_name: Set(user_name.chars().take(255).collect()),
+        ip_address: Set(client.ip.clone()),
+        user_agent: Set(client.user_agent.clone()),
+        success: Set(success as i8),
+        reason: Set(reason.map(str::to_string)),
+        created_at: Set(Utc::now()),
+        ..Default::default()
+    };
+    if let Err(e) = record.insert(db).await {
+        error!("写入登录记录失败: {}", e);
+    }
 }
 
 // 提取JWT生成逻辑
-fn generate_jwt(credentials: &Model, secret: &str, expires_in: u64) -> Result<String, AppError> {
+fn generate_jwt(
+    credentials: &Model,
+    permissions: Permission,
+    secret: &str,
+    expires_in: u64,
+) -> Result<String, AppError> {
     let exp = SystemTime::now()
         .duration_since(SystemTime::UNIX_EPOCH)
         .unwrap()
@@ -108,7 +431,8 @@ fn generate_jwt(credentials: &Model, secret: &str, expires_in: u64) -> Result<St
         user_uuid: credentials.uuid.clone(),
         user_name: credentials.user_name.clone(),
         exp: exp as usize,
-        permissions: credentials.permissions.clone(),
+        permissions: Some(permissions.bits().to_string()),
+        jti: Some(Uuid::new_v4().to_string()),
     };
 
     encode(
@@ -122,19 +446,11 @@ fn generate_jwt(credentials: &Model, secret: &str, expires_in: u64) -> Result<St
     })
 }
 
-fn build_login_response(credentials: Model, token: String) -> LoginData {
+fn build_login_response(credentials: Model, permissions: Permission, token: String) -> LoginData {
+    // 登录响应中的权限是角色合并后的有效权限
     let user_info = UserInfo {
-        id: credentials.id,
-        uuid: credentials.uuid,
-        user_name: credentials.user_name,
-        created_at: credentials.created_at.to_string(),
-        updated_at: credentials.updated_at.to_string(),
-        email: credentials.email,
-        phone: credentials.phone,
-        role: credentials.role,
-        permissions: credentials.permissions.clone(),
-        image: credentials.image,
-        binding: credentials.binding,
+        permissions: Some(permissions.bits().to_string()),
+        ..UserInfo::from(credentials)
     };
 
     LoginData {
@@ -156,20 +472,21 @@ pub struct SimpleRespData {
     tag = "鉴权模块",
     operation_id = "用户注册",
     responses(
-        (status = 200, description = "注册成功", body = CommonResponse<UserDto>),
-        (status = 400, description = "验证错误", body = CommonResponse<Option<UserDto>>),
-        (status = 409, description = "用户名已存在", body = CommonResponse<Option<UserDto>>),
-        (status = 500, description = "服务器内部错误", body = SimpleRespData),
+        (status = 200, description = "注册成功", body = CommonResponse<UserInfo>),
+        (status = 400, description = "验证错误", body = ErrorResponse),
+        (status = 409, description = "用户名、邮箱或手机号已存在", body = ErrorResponse),
+        (status = 500, description = "服务器内部错误", body = ErrorResponse),
     ),
 )]
 
 pub async fn register(
     db: web::Data<DatabaseConnection>,
+    notifier: web::Data<dyn Notifier>,
     user_data: web::Json<RegisterResponse>,
 ) -> SimpleResp {
     if let Err(e) = user_data.validate() {
         info!("注册:{:?}", e); // 打印验证错误
-        return Resp::err(AppError::DeserializeError(e.to_string())).to_json_result();
+        return Err(e.into());
     }
     let user_data = user_data.into_inner(); // 提取内部数据
 
@@ -181,25 +498,40 @@ pub async fn register(
     {
         Ok(count) if count > 0 => {
             warn!("用户名 '{}' 已存在", user_data.user_name);
-            return Resp::err(AppError::Conflict("用户名已存在".into())).to_json_result();
+            return Err(AppError::Conflict("用户名已存在".into()));
         }
         Ok(_) => {}
         Err(e) => {
             error!("检查用户名时发生错误: {}", e);
-            return Resp::err(AppError::InternalServerError("服务器内部错误".into()))
-                .to_json_result();
+            return Err(AppError::InternalServerError("服务器内部错误".into()));
         }
     }
 
-    // 密码加密
-    let hashed_password = match hash(&user_data.pass_word, DEFAULT_COST) {
-        Ok(hashed) => hashed,
-        Err(e) => {
-            log::error!("密码加密失败: {}", e); // 详细错误日志记录在服务器端
-            return Resp::err(AppError::InternalServerError("密码加密失败".into()))
-                .to_json_result();
+    let email = user_data.email.as_deref().map(normalize_email);
+    let phone = user_data.phone.as_deref().map(normalize_phone);
+    if let Some(email) = &email {
+        let exists = UserEntity::find()
+            .filter(user::Column::Email.eq(email))
+            .count(db.as_ref())
+            .await?
+            > 0;
+        if exists {
+            return Err(AppError::Conflict("邮箱已被使用".into()));
         }
-    };
+    }
+    if let Some(phone) = &phone {
+        let exists = UserEntity::find()
+            .filter(user::Column::Phone.eq(phone))
+            .count(db.as_ref())
+            .await?
+            > 0;
+        if exists {
+            return Err(AppError::Conflict("手机号已被使用".into()));
+        }
+    }
+
+    // 密码加密
+    let hashed_password = hash_password(&user_data.pass_word)?;
 
     // 创建新用户
     let new_user = user::ActiveModel {
@@ -207,16 +539,35 @@ pub async fn register(
         user_name: Set(user_data.user_name.clone()),
         created_at: Set(Utc::now()),
         updated_at: Set(Utc::now()),
-        permissions: Set(Some(Permission::ALL.bits().to_string())), // 设置默认权限
         pass_word: Set(hashed_password.clone()), // 注意：这里应该存储哈希后的密码
+        email: Set(email),
+        phone: Set(phone),
         ..Default::default()
     };
 
-    match new_user.insert(db.as_ref()).await {
-        Ok(created_user) => Resp::ok(created_user, "注册成功").to_json_result(),
+    // 用户和默认角色在同一个事务中创建
+    let created = async {
+        let txn = db.begin().await?;
+        let created_user = new_user.insert(&txn).await?;
+        assign_default_role(&txn, &created_user.uuid).await?;
+        txn.commit().await?;
+        Ok::<_, AppError>(created_user)
+    }
+    .await;
+
+    match created {
+        Ok(created_user) => {
+            // 验证邮件发送失败不影响注册，用户可以稍后重新发送
+            if created_user.email.is_some() {
+                if let Err(e) = send_verification_email(notifier.as_ref(), &created_user).await {
+                    error!("发送邮箱验证邮件失败: {}", e);
+                }
+            }
+            Resp::ok(UserInfo::from(created_user), "注册成功").to_json_result()
+        }
         Err(e) => {
             error!("创建用户失败: {}", e);
-            Resp::err(AppError::InternalServerError("服务器内部错误".into())).to_json_result()
+            Err(AppError::InternalServerError("服务器内部错误".into()))
         }
     }
 }
@@ -238,10 +589,11 @@ fn default_permissions() -> String {
     operation_id = "获取指定用户权限",
     responses(
         (status = 200, description = "获取权限成功", body = SimpleRespData),
-        (status = 400, description = "权限ID格式错误", body = SimpleRespData),
+        (status = 400, description = "权限ID格式错误", body = ErrorResponse),
+        (status = 403, description = "需要读取系统权限", body = ErrorResponse),
     ),
 )]
-// 解析权限ID并返回权限信息
+// 解析权限ID并返回权限信息，与权限路由列表一样需要读取系统权限
 pub async fn get_permissions_by_id(query: web::Query<PermissionDto>) -> SimpleResp {
     info!("permission_id: {}", query.permissions);
     match query.permissions.parse::<u64>() {
@@ -254,14 +606,14 @@ pub async fn get_permissions_by_id(query: web::Query<PermissionDto>) -> SimpleRe
                 .collect::<Vec<_>>();
             Resp::ok(permission_names, "获取权限成功").to_json_result()
         }
-        Err(_) => Resp::err(AppError::BadRequest("权限ID格式错误".into())).to_json_result(),
+        Err(_) => Err(AppError::BadRequest("权限ID格式错误".into())),
     }
 }
 
 #[derive(Debug, Serialize, ToSchema)]
 pub struct PermissionResponse {
-    name: String,
-    description: String,
+    pub name: String,
+    pub description: String,
 }
 
 #[utoipa::path(
@@ -294,3 +646,120 @@ pub async fn get_permissions() -> SimpleResp {
         .collect::<Vec<_>>();
     Resp::ok(permission_list, "获取权限列表成功").to_json_result()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{test, App};
+    use sea_orm::{DatabaseBackend, MockDatabase};
+    use serde_json::Value;
+
+    #[actix_web::test]
+    async fn login_returns_challenge_when_two_factor_is_bound() {
+        let mut user = Model::fixture("u-1", "alice01");
+        user.pass_word = hash_password("correct-horse").unwrap();
+        user.binding = Some("encrypted-secret".into());
+        // 只有按用户名查询一次，返回挑战令牌前不会签发令牌，也不会查询角色权限
+        let db = MockDatabase::new(DatabaseBackend::MySql)
+            .append_query_results([vec![user]])
+            .into_connection();
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(db))
+                .app_data(web::Data::new(LoginThrottle::default()))
+                .route("/login", web::post().to(login)),
+        )
+        .await;
+
+        let req = test::TestRequest::post()
+            .uri("/login")
+            .set_json(serde_json::json!({"identifier": "alice01", "pass_word": "correct-horse"}))
+            .to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), 200);
+        let body: Value = test::read_body_json(res).await;
+        let data = &body["data"];
+        assert_eq!(data["two_factor_required"], true);
+        assert!(data.get("access_token").is_none());
+        let claims = decode_challenge_token(data["challenge_token"].as_str().unwrap()).unwrap();
+        assert_eq!(claims.user_uuid, "u-1");
+    }
+
+    #[actix_web::test]
+    async fn forwarded_for_is_only_trusted_behind_a_known_proxy() {
+        let proxy: IpAddr = "10.0.0.1".parse().unwrap();
+        let request = |peer: &str| {
+            test::TestRequest::default()
+                .peer_addr(peer.parse().unwrap())
+                .insert_header(("X-Forwarded-For", "1.2.3.4, 203.0.113.7"))
+                .to_http_request()
+        };
+
+        // 未配置代理时忽略客户端可以随意填写的请求头
+        assert_eq!(
+            client_ip(&request("198.51.100.9:5000"), &[]),
+            "198.51.100.9"
+        );
+        assert_eq!(
+            client_ip(&request("198.51.100.9:5000"), &[proxy]),
+            "198.51.100.9"
+        );
+        // 经过代理时取代理追加的最后一个地址，而不是客户端伪造的第一个
+        assert_eq!(
+            client_ip(&request("10.0.0.1:5000"), &[proxy]),
+            "203.0.113.7"
+        );
+
+        let req = test::TestRequest::default()
+            .peer_addr("10.0.0.1:5000".parse().unwrap())
+            .to_http_request();
+        assert_eq!(client_ip(&req, &[proxy]), "10.0.0.1");
+    }
+
+    #[actix_web::test]
+    async fn login_accepts_user_name_verified_email_or_phone() {
+        let user = Model::fixture("u-1", "alice01");
+        let db = MockDatabase::new(DatabaseBackend::MySql)
+            .append_query_results([vec![user.clone()], vec![user.clone()], vec![user]])
+            .into_connection();
+
+        for identifier in [" Alice@Example.com", "+86 138-0013-8000", "alice01"] {
+            let found = find_login_user(&db, identifier).await.unwrap();
+            assert_eq!(found.unwrap().uuid, "u-1");
+        }
+
+        let log = db.into_transaction_log();
+        assert_eq!(log.len(), 3);
+        let by_email = log[0].statements()[0].to_string();
+        assert!(by_email.contains("`email` = 'alice@example.com'"));
+        assert!(by_email.contains("`email_verified_at` IS NOT NULL"));
+        let by_phone = log[1].statements()[0].to_string();
+        assert!(by_phone.contains("WHERE `users`.`phone` = '+8613800138000'"));
+        let by_name = log[2].statements()[0].to_string();
+        assert!(by_name.contains("WHERE `users`.`user_name` = 'alice01'"));
+    }
+
+    #[actix_web::test]
+    async fn user_name_equal_to_an_email_cannot_shadow_its_owner() {
+        // 历史数据中存在用户名等于 alice 邮箱的账户
+        let mut alice = Model::fixture("u-1", "alice01");
+        alice.email = Some("alice@example.com".into());
+        alice.email_verified_at = Some(Utc::now());
+        let squatter = Model::fixture("u-2", "alice@example.com");
+        let db = MockDatabase::new(DatabaseBackend::MySql)
+            .append_query_results([vec![alice]])
+            // 没有已验证的邮箱命中时才按用户名匹配
+            .append_query_results([Vec::<Model>::new(), vec![squatter]])
+            .into_connection();
+
+        let found = find_login_user(&db, "alice@example.com").await.unwrap();
+        assert_eq!(found.unwrap().uuid, "u-1");
+        let found = find_login_user(&db, "alice@example.com").await.unwrap();
+        assert_eq!(found.unwrap().uuid, "u-2");
+
+        let log = db.into_transaction_log();
+        assert_eq!(log.len(), 3);
+        assert!(log[0].statements()[0].to_string().contains("`email` ="));
+        assert!(log[2].statements()[0].to_string().contains("`user_name` ="));
+    }
+}
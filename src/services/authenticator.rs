@@ -1,68 +1,336 @@
+use crate::config::two_factor::TWO_FACTOR_CONFIG;
 use crate::error::error::AppError;
-use crate::models::user::{self, Entity as UserEntity};
-use crate::utils::jsonwebtoken::{extract_token, has_permission};
-use actix_web::HttpRequest;
+use crate::models::two_factor_recovery_codes::{self, Entity as RecoveryCodeEntity};
+use crate::models::user::{self, Entity as UserEntity, Model};
+use crate::utils::crypto::{decrypt_secret, encrypt_secret, is_encrypted, sha256_hex};
+use crate::utils::current_user::CurrentUser;
 use actix_web::{web, HttpResponse, Responder};
 use base64::engine::general_purpose;
 use base64::engine::Engine as _;
+use chrono::Utc;
 use google_authenticator::GoogleAuthenticator;
 use image::*;
 use log;
 use qrcode::QrCode;
-use sea_orm::ActiveModelTrait;
+use rand::distributions::Alphanumeric;
+use rand::Rng;
 use sea_orm::ActiveValue::Set;
-use sea_orm::DatabaseConnection;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DatabaseConnection, EntityTrait,
+    PaginatorTrait, QueryFilter, TransactionTrait,
+};
 use serde::Deserialize;
 use serde_json::json;
 use std::io::Cursor;
+use url::form_urlencoded::byte_serialize;
+
+// 每次生成的恢复码数量
+const RECOVERY_CODE_COUNT: usize = 10;
 // 添加用于处理2FA验证的端点
 pub async fn verify_2fa(
     web::Json(data): web::Json<Verify2FARequest>,
     db: web::Data<DatabaseConnection>,
-    req: HttpRequest,
+    current_user: CurrentUser,
 ) -> Result<impl Responder, AppError> {
-    let auth = GoogleAuthenticator::new();
-    if !auth.verify_code(&data.secret, &data.code, 1, 0) {
-        log::debug!("2FA验证失败: code={}", data.code);
+    let user = UserEntity::find_by_uuid(&current_user.uuid)
+        .one(db.as_ref())
+        .await?
+        .ok_or_else(|| AppError::NotFound("用户不存在".into()))?;
+
+    // 只能确认服务端为当前用户生成的待绑定密钥
+    let pending = match user.pending_binding.as_deref() {
+        Some(pending) if !pending.is_empty() => pending.to_string(),
+        _ => return Err(AppError::BadRequest("请先生成双重验证密钥".into())),
+    };
+    if !verify_totp(&decrypt_secret(&pending)?, &data.code) {
+        log::debug!("2FA验证失败: user={}", current_user.uuid);
         return Err(AppError::BadRequest("无效的验证码".into()));
     }
 
-    let token = extract_token(&req.headers())
-        .ok_or_else(|| AppError::Unauthorized("请求未包含认证Token".into()))?;
+    // 激活与生成恢复码放在同一事务中
+    let txn = db.begin().await?;
+    let mut user_active: user::ActiveModel = user.into();
+    user_active.binding = Set(Some(pending));
+    user_active.pending_binding = Set(None);
+    user_active.update(&txn).await?;
+    let recovery_codes = replace_recovery_codes(&txn, &current_user.uuid).await?;
+    txn.commit().await?;
+
+    log::info!("用户 {} 已激活2FA", current_user.uuid);
 
-    // 验证JWT是否具有权限
-    let token_claims = has_permission(&token)
-        .map_err(|e| AppError::Unauthorized(e.to_string()))?
-        .claims;
+    // 恢复码明文只在这里展示一次
+    Ok(HttpResponse::Ok().json(json!({
+        "status": "success",
+        "message": "双重验证已激活",
+        "data": {
+            "recovery_codes": recovery_codes
+        }
+    })))
+}
 
-    let user = UserEntity::find_by_uuid(&token_claims.user_uuid)
+// 关闭2FA，需要提供当前有效的验证码
+pub async fn disable_2fa(
+    web::Json(data): web::Json<Disable2FARequest>,
+    db: web::Data<DatabaseConnection>,
+    current_user: CurrentUser,
+) -> Result<impl Responder, AppError> {
+    let user = UserEntity::find_by_uuid(&current_user.uuid)
         .one(db.as_ref())
         .await?
         .ok_or_else(|| AppError::NotFound("用户不存在".into()))?;
 
+    let secret = user_totp_secret(&user)?
+        .ok_or_else(|| AppError::BadRequest("当前账户未开启双重验证".into()))?;
+
+    if !verify_totp(&secret, &data.code) {
+        log::debug!("关闭2FA验证失败: user={}", current_user.uuid);
+        return Err(AppError::BadRequest("无效的验证码".into()));
+    }
+
+    let txn = db.begin().await?;
     let mut user_active: user::ActiveModel = user.into();
-    user_active.binding = Set(Some(data.secret));
-    user_active.update(db.as_ref()).await?;
+    user_active.binding = Set(None);
+    user_active.update(&txn).await?;
+    RecoveryCodeEntity::delete_many()
+        .filter(two_factor_recovery_codes::Column::UserUuid.eq(&current_user.uuid))
+        .exec(&txn)
+        .await?;
+    txn.commit().await?;
 
-    log::info!("用户 {} 已激活2FA", token_claims.user_uuid);
+    log::info!("用户 {} 已关闭2FA", current_user.uuid);
 
     Ok(HttpResponse::Ok().json(json!({
         "status": "success",
-        "message": "双重验证已激活"
+        "message": "双重验证已关闭"
     })))
 }
 
-// 添加用于生成2FA密钥的端点
-pub async fn generate_2fa_secret() -> Result<impl Responder, AppError> {
+// 重新生成恢复码，需要提供当前有效的验证码，旧的恢复码全部作废
+pub async fn regenerate_recovery_codes(
+    web::Json(data): web::Json<RegenerateRecoveryCodesRequest>,
+    db: web::Data<DatabaseConnection>,
+    current_user: CurrentUser,
+) -> Result<impl Responder, AppError> {
+    let user = UserEntity::find_by_uuid(&current_user.uuid)
+        .one(db.as_ref())
+        .await?
+        .ok_or_else(|| AppError::NotFound("用户不存在".into()))?;
+
+    let secret = user_totp_secret(&user)?
+        .ok_or_else(|| AppError::BadRequest("当前账户未开启双重验证".into()))?;
+    if !verify_totp(&secret, &data.code) {
+        log::debug!("重新生成恢复码验证失败: user={}", current_user.uuid);
+        return Err(AppError::BadRequest("无效的验证码".into()));
+    }
+    reencrypt_legacy_binding(db.as_ref(), &user, &secret).await;
+
+    let txn = db.begin().await?;
+    let recovery_codes = replace_recovery_codes(&txn, &current_user.uuid).await?;
+    txn.commit().await?;
+
+    log::info!("用户 {} 重新生成了恢复码", current_user.uuid);
+
+    Ok(HttpResponse::Ok().json(json!({
+        "status": "success",
+        "message": "恢复码已重新生成",
+        "data": {
+            "recovery_codes": recovery_codes
+        }
+    })))
+}
+
+// 查询剩余可用的恢复码数量
+pub async fn recovery_codes_status(
+    db: web::Data<DatabaseConnection>,
+    current_user: CurrentUser,
+) -> Result<impl Responder, AppError> {
+    let remaining = RecoveryCodeEntity::find()
+        .filter(two_factor_recovery_codes::Column::UserUuid.eq(&current_user.uuid))
+        .filter(two_factor_recovery_codes::Column::UsedAt.is_null())
+        .count(db.as_ref())
+        .await?;
+
+    Ok(HttpResponse::Ok().json(json!({
+        "status": "success",
+        "data": {
+            "remaining": remaining
+        }
+    })))
+}
+
+// 删除用户已有的恢复码并生成新的一组，返回明文
+async fn replace_recovery_codes<C: ConnectionTrait>(
+    db: &C,
+    user_uuid: &str,
+) -> Result<Vec<String>, AppError> {
+    RecoveryCodeEntity::delete_many()
+        .filter(two_factor_recovery_codes::Column::UserUuid.eq(user_uuid))
+        .exec(db)
+        .await?;
+
+    let codes: Vec<String> = (0..RECOVERY_CODE_COUNT)
+        .map(|_| generate_recovery_code())
+        .collect();
+    // 保存SHA-256摘要而不是bcrypt/argon2哈希：恢复码是服务端生成的10位随机串（约51位熵），
+    // 不存在弱口令，慢哈希不会增加暴力破解的难度；按摘要可以直接查到对应记录，
+    // 不需要对用户的每个恢复码逐一做慢哈希校验，登录接口也不会因此被用来消耗CPU
+    let models = codes
+        .iter()
+        .map(|code| two_factor_recovery_codes::ActiveModel {
+            user_uuid: Set(user_uuid.to_string()),
+            code_hash: Set(sha256_hex(code)),
+            created_at: Set(Utc::now()),
+            ..Default::default()
+        });
+    RecoveryCodeEntity::insert_many(models).exec(db).await?;
+    Ok(codes)
+}
+
+// 校验恢复码，匹配成功后立即标记为已使用
+pub async fn consume_recovery_code(
+    db: &DatabaseConnection,
+    user_uuid: &str,
+    code: &str,
+) -> Result<bool, AppError> {
+    let code = normalize_recovery_code(code);
+    let matched = RecoveryCodeEntity::find()
+        .filter(two_factor_recovery_codes::Column::UserUuid.eq(user_uuid))
+        .filter(two_factor_recovery_codes::Column::UsedAt.is_null())
+        .filter(two_factor_recovery_codes::Column::CodeHash.eq(sha256_hex(&code)))
+        .one(db)
+        .await?;
+    let Some(matched) = matched else {
+        return Ok(false);
+    };
+
+    // 只有仍未使用的记录才会被更新，避免同一恢复码被并发使用两次
+    let result = RecoveryCodeEntity::update_many()
+        .col_expr(
+            two_factor_recovery_codes::Column::UsedAt,
+            sea_orm::sea_query::Expr::value(Utc::now()),
+        )
+        .filter(two_factor_recovery_codes::Column::Id.eq(matched.id))
+        .filter(two_factor_recovery_codes::Column::UsedAt.is_null())
+        .exec(db)
+        .await?;
+    Ok(result.rows_affected == 1)
+}
+
+// 生成形如 abcde-12345 的恢复码
+fn generate_recovery_code() -> String {
+    let raw: String = rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(10)
+        .map(|c| (c as char).to_ascii_lowercase())
+        .collect();
+    format!("{}-{}", &raw[..5], &raw[5..])
+}
+
+// 用户输入的恢复码忽略大小写和首尾空白
+fn normalize_recovery_code(code: &str) -> String {
+    code.trim().to_ascii_lowercase()
+}
+
+// 校验TOTP验证码，允许前后各一个时间窗口的偏差
+pub fn verify_totp(secret: &str, code: &str) -> bool {
+    GoogleAuthenticator::new().verify_code(secret, code, 1, 0)
+}
+
+// 读取并解密用户已绑定的TOTP密钥，未开启2FA时返回 None
+pub fn user_totp_secret(user: &Model) -> Result<Option<String>, AppError> {
+    match user.binding.as_deref() {
+        Some(stored) if !stored.is_empty() => decrypt_secret(stored).map(Some),
+        _ => Ok(None),
+    }
+}
+
+// 历史遗留的明文TOTP密钥在验证码校验通过后改为加密保存，失败时只记录日志
+pub async fn reencrypt_legacy_binding(db: &DatabaseConnection, user: &Model, secret: &str) {
+    let Some(stored) = user.binding.as_deref() else {
+        return;
+    };
+    if is_encrypted(stored) {
+        return;
+    }
+    let encrypted = match encrypt_secret(secret) {
+        Ok(encrypted) => encrypted,
+        Err(e) => {
+            log::error!("用户 {} TOTP密钥加密失败: {}", user.uuid, e);
+            return;
+        }
+    };
+    // 只替换仍是同一份明文的记录，期间关闭或重新绑定过的不受影响
+    let result = UserEntity::update_many()
+        .col_expr(
+            user::Column::Binding,
+            sea_orm::sea_query::Expr::value(encrypted),
+        )
+        .filter(user::Column::Uuid.eq(&user.uuid))
+        .filter(user::Column::Binding.eq(stored))
+        .exec(db)
+        .await;
+    match result {
+        Ok(_) => log::info!("用户 {} 的TOTP密钥已改为加密保存", user.uuid),
+        Err(e) => log::error!("用户 {} TOTP密钥加密保存失败: {}", user.uuid, e),
+    }
+}
+
+// 为当前登录用户生成待确认的2FA密钥
+pub async fn generate_2fa_secret(
+    db: web::Data<DatabaseConnection>,
+    current_user: CurrentUser,
+) -> Result<impl Responder, AppError> {
+    let user = UserEntity::find_by_uuid(&current_user.uuid)
+        .one(db.as_ref())
+        .await?
+        .ok_or_else(|| AppError::NotFound("用户不存在".into()))?;
+
+    if user_totp_secret(&user)?.is_some() {
+        return Err(AppError::Conflict("当前账户已开启双重验证".into()));
+    }
+
     let auth = GoogleAuthenticator::new();
     let secret = auth.create_secret(32); // 生成32字节的密钥
 
     // 生成OTP URI
-    let otp_uri = format!(
-        "otpauth://totp/MyApp:UserAccount?secret={}&issuer=MyApp",
-        secret
-    );
+    let otp_uri = build_otp_uri(&TWO_FACTOR_CONFIG.issuer, &user.user_name, &secret);
+    let data_url = render_qr_data_url(&otp_uri)?;
 
+    // 密钥加密后暂存，确认验证码之前不会生效
+    let user_name = user.user_name.clone();
+    let mut user_active: user::ActiveModel = user.into();
+    user_active.pending_binding = Set(Some(encrypt_secret(&secret)?));
+    user_active.update(db.as_ref()).await?;
+
+    log::info!("用户 {} 生成了待确认的2FA密钥", user_name);
+
+    Ok(HttpResponse::Ok().json(json!( {
+        "status": "success",
+        "data": {
+            "secret": secret,
+            "otp_uri": otp_uri,
+            "qr_code": data_url
+        }
+    })))
+}
+
+// 生成符合 Key Uri Format 的 otpauth 地址，空格按 %20 转义
+fn build_otp_uri(issuer: &str, account: &str, secret: &str) -> String {
+    let encode = |s: &str| {
+        byte_serialize(s.as_bytes())
+            .collect::<String>()
+            .replace('+', "%20")
+    };
+    let issuer = encode(issuer);
+    let account = encode(account);
+    format!(
+        "otpauth://totp/{}:{}?secret={}&issuer={}",
+        issuer, account, secret, issuer
+    )
+}
+
+// 将 otpauth 地址渲染为 base64 编码的PNG二维码
+fn render_qr_data_url(otp_uri: &str) -> Result<String, AppError> {
     // 生成二维码
     let code = QrCode::new(otp_uri.as_bytes())
         .map_err(|e| AppError::InternalServerError(e.to_string()))?;
@@ -74,15 +342,7 @@ pub async fn generate_2fa_secret() -> Result<impl Responder, AppError> {
 
     // 转换为Base64
     let base64_image = general_purpose::STANDARD.encode(&png_bytes);
-    let data_url = format!("data:image/png;base64,{}", base64_image);
-
-    Ok(HttpResponse::Ok().json(json!( {
-        "status": "success",
-        "data": {
-            "secret": secret,
-            "qr_code": data_url
-        }
-    })))
+    Ok(format!("data:image/png;base64,{}", base64_image))
 }
 
 fn write_image_to_png_bytes(
@@ -95,40 +355,135 @@ fn write_image_to_png_bytes(
 
 #[derive(Deserialize)]
 pub struct Verify2FARequest {
-    secret: String,
+    code: String,
+}
+
+#[derive(Deserialize)]
+pub struct Disable2FARequest {
+    code: String,
+}
+
+#[derive(Deserialize)]
+pub struct RegenerateRecoveryCodesRequest {
     code: String,
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use actix_web::{test, web, App};
-
-    #[actix_web::test]
-    async fn test_generate_2fa_secret() {
-        let mut app =
-            test::init_service(App::new().route("/", web::get().to(generate_2fa_secret))).await;
+    use sea_orm::{DatabaseBackend, MockDatabase, MockExecResult, Transaction};
 
-        let req = test::TestRequest::get().uri("/").to_request();
-        let resp = test::call_service(&mut app, req).await;
+    fn exec_result(rows_affected: u64) -> MockExecResult {
+        MockExecResult {
+            last_insert_id: 0,
+            rows_affected,
+        }
+    }
 
-        assert!(resp.status().is_success());
+    fn recovery_code(id: i32, code: &str) -> two_factor_recovery_codes::Model {
+        two_factor_recovery_codes::Model {
+            id,
+            user_uuid: "u-1".into(),
+            code_hash: sha256_hex(code),
+            used_at: None,
+            created_at: Utc::now(),
+        }
+    }
 
-        let body: serde_json::Value = test::read_body_json(resp).await;
+    #[test]
+    fn test_generate_2fa_secret() {
+        let secret = GoogleAuthenticator::new().create_secret(32);
 
         // 测试密钥长度
-        let secret = body["data"]["secret"].as_str().unwrap();
         assert_eq!(secret.len(), 32);
 
-        // 测试OTP URI格式
-        let otp_uri = body["data"]["qr_code"].as_str().unwrap();
-        assert!(otp_uri.starts_with("data:image/png;base64,"));
+        // 测试OTP URI格式，账户名和发行方需要转义
+        let otp_uri = build_otp_uri("Rust Web", "alice@example.com", &secret);
+        assert_eq!(
+            otp_uri,
+            format!(
+                "otpauth://totp/Rust%20Web:alice%40example.com?secret={}&issuer=Rust%20Web",
+                secret
+            )
+        );
 
         // 测试二维码生成和解析
-        let base64_data = otp_uri.split(",").nth(1).unwrap();
+        let data_url = render_qr_data_url(&otp_uri).unwrap();
+        assert!(data_url.starts_with("data:image/png;base64,"));
+        let base64_data = data_url.split(",").nth(1).unwrap();
         let png_bytes = general_purpose::STANDARD.decode(base64_data).unwrap();
         let img = image::load_from_memory(&png_bytes).unwrap();
-        assert_eq!(img.width(), 360); // 假设二维码的默认宽度为360
-        assert_eq!(img.height(), 360); // 假设二维码的默认高度为360
+        // 二维码版本随内容变化，只校验是正方形
+        assert!(img.width() > 0);
+        assert_eq!(img.width(), img.height());
+    }
+
+    #[actix_web::test]
+    async fn recovery_code_can_only_be_consumed_once() {
+        let db = MockDatabase::new(DatabaseBackend::MySql)
+            .append_query_results([vec![recovery_code(1, "abcde-12345")]])
+            .append_exec_results([exec_result(1)])
+            // 第二次使用：摘要查询不到未使用的记录
+            .append_query_results([Vec::<two_factor_recovery_codes::Model>::new()])
+            // 并发使用：查询时仍未使用，但更新时已被另一个请求标记
+            .append_query_results([vec![recovery_code(2, "fghij-67890")]])
+            .append_exec_results([exec_result(0)])
+            .into_connection();
+
+        // 输入忽略大小写和首尾空白
+        assert!(consume_recovery_code(&db, "u-1", " ABCDE-12345 ")
+            .await
+            .unwrap());
+        assert!(!consume_recovery_code(&db, "u-1", "abcde-12345")
+            .await
+            .unwrap());
+        assert!(!consume_recovery_code(&db, "u-1", "fghij-67890")
+            .await
+            .unwrap());
+
+        let log = db.into_transaction_log();
+        let lookup = log[0].statements()[0].to_string();
+        assert!(lookup.contains(&sha256_hex("abcde-12345")));
+        assert!(lookup.contains("`used_at` IS NULL"));
+        let mark_used = log[1].statements()[0].to_string();
+        assert!(mark_used.starts_with("UPDATE `two_factor_recovery_codes`"));
+        assert!(mark_used.contains("`used_at` IS NULL"));
+    }
+
+    #[actix_web::test]
+    async fn regenerating_recovery_codes_replaces_the_old_set() {
+        let db = MockDatabase::new(DatabaseBackend::MySql)
+            .append_exec_results([
+                exec_result(10),
+                MockExecResult {
+                    last_insert_id: 10,
+                    rows_affected: 10,
+                },
+            ])
+            .into_connection();
+
+        let codes = replace_recovery_codes(&db, "u-1").await.unwrap();
+        assert_eq!(codes.len(), RECOVERY_CODE_COUNT);
+        let unique: std::collections::HashSet<_> = codes.iter().collect();
+        assert_eq!(unique.len(), RECOVERY_CODE_COUNT);
+        assert!(codes
+            .iter()
+            .all(|code| code.len() == 11 && normalize_recovery_code(code) == *code));
+
+        // 先删除该用户的旧恢复码，再写入新恢复码的摘要，不保存明文
+        let log = db.into_transaction_log();
+        assert_eq!(
+            log[0],
+            Transaction::from_sql_and_values(
+                DatabaseBackend::MySql,
+                "DELETE FROM `two_factor_recovery_codes` WHERE `two_factor_recovery_codes`.`user_uuid` = ?",
+                ["u-1".into()],
+            )
+        );
+        let insert = log[1].statements()[0].to_string();
+        for code in &codes {
+            assert!(insert.contains(&sha256_hex(code)));
+            assert!(!insert.contains(code.as_str()));
+        }
     }
 }
@@ -0,0 +1,110 @@
+use crate::common::CommonResponse;
+use crate::config::mail::MAIL_CONFIG;
+use crate::dto::user::EmailVerifyQuery;
+use crate::error::error::{AppError, ErrorResponse};
+use crate::jsonwebtoken::{decode_email_verify_token, generate_email_verify_token};
+use crate::middleware::helpers::{Resp, SimpleResp};
+use crate::models::user::{self, Entity as UserEntity};
+use crate::utils::current_user::CurrentUser;
+use crate::utils::notifier::{Notification, Notifier};
+use actix_web::web;
+use chrono::Utc;
+use log::info;
+use sea_orm::{ActiveModelTrait, ActiveValue::Set, DatabaseConnection};
+
+#[utoipa::path(
+    post,
+    path = "/api/auth/email/verify/send",
+    tag = "鉴权模块",
+    operation_id = "发送邮箱验证邮件",
+    responses(
+        (status = 200, description = "验证邮件已发送", body = CommonResponse<String>),
+        (status = 400, description = "未设置邮箱", body = ErrorResponse),
+        (status = 409, description = "邮箱已验证", body = ErrorResponse),
+    ),
+)]
+// 给当前登录用户的邮箱重新发送验证链接
+pub async fn send_email_verification(
+    db: web::Data<DatabaseConnection>,
+    notifier: web::Data<dyn Notifier>,
+    current_user: CurrentUser,
+) -> SimpleResp {
+    let user = UserEntity::find_by_uuid(&current_user.uuid)
+        .one(db.as_ref())
+        .await?
+        .ok_or_else(|| AppError::NotFound("用户不存在".into()))?;
+
+    if user.email_verified_at.is_some() {
+        return Err(AppError::Conflict("邮箱已验证".into()));
+    }
+    send_verification_email(notifier.as_ref(), &user).await?;
+    Resp::ok("", "验证邮件已发送").to_json_result()
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/auth/email/verify",
+    tag = "鉴权模块",
+    operation_id = "验证邮箱",
+    params(
+        ("token" = String, Query, description = "验证邮件中的令牌")
+    ),
+    responses(
+        (status = 200, description = "邮箱验证成功", body = CommonResponse<String>),
+        (status = 400, description = "验证链接无效或已过期", body = ErrorResponse),
+    ),
+)]
+// 打开验证链接完成邮箱验证，重复打开同一个有效链接不会报错
+pub async fn verify_email(
+    db: web::Data<DatabaseConnection>,
+    query: web::Query<EmailVerifyQuery>,
+) -> SimpleResp {
+    let claims = decode_email_verify_token(&query.token)?;
+    let invalid = || AppError::BadRequest("验证链接无效或已过期".into());
+
+    let user = UserEntity::find_by_uuid(&claims.user_uuid)
+        .one(db.as_ref())
+        .await?
+        .ok_or_else(invalid)?;
+    // 链接签发后邮箱被修改过，旧链接不再有效
+    if user.email.as_deref() != Some(claims.email.as_str()) {
+        return Err(invalid());
+    }
+    if user.email_verified_at.is_some() {
+        return Resp::ok("", "邮箱已验证").to_json_result();
+    }
+
+    let user_uuid = user.uuid.clone();
+    let mut user_active: user::ActiveModel = user.into();
+    user_active.email_verified_at = Set(Some(Utc::now()));
+    user_active.update(db.as_ref()).await?;
+
+    info!("用户 {} 完成了邮箱验证", user_uuid);
+    Resp::ok("", "邮箱验证成功").to_json_result()
+}
+
+// 生成验证链接并通过通知渠道发送，用户未设置邮箱时返回错误
+pub async fn send_verification_email(
+    notifier: &dyn Notifier,
+    user: &user::Model,
+) -> Result<(), AppError> {
+    let email = user
+        .email
+        .as_deref()
+        .ok_or_else(|| AppError::BadRequest("当前账户未设置邮箱".into()))?;
+    let token =
+        generate_email_verify_token(&user.uuid, email, MAIL_CONFIG.verify_token_ttl.as_secs())?;
+
+    let notification = Notification {
+        recipient: email.to_string(),
+        subject: "验证邮箱".to_string(),
+        body: format!(
+            "您好 {}，请在{}小时内打开以下链接完成邮箱验证：\n{}?token={}\n如果不是您本人操作，请忽略本消息。",
+            user.user_name,
+            (MAIL_CONFIG.verify_token_ttl.as_secs() / 3600).max(1),
+            MAIL_CONFIG.verify_url,
+            token
+        ),
+    };
+    notifier.send(&notification).await
+}
@@ -1,5 +1,12 @@
+pub mod api_keys;
 pub mod auth;
 pub mod authenticator;
+pub mod email;
+pub mod oauth;
+pub mod password;
+pub mod permissions;
+pub mod profile;
+pub mod roles;
 pub mod routes;
 pub mod sse;
 pub mod user;
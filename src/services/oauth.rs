@@ -0,0 +1,663 @@
+use crate::common::CommonResponse;
+use crate::config::oauth::{OAuthProviderConfig, ProviderKind, OAUTH_CONFIG};
+use crate::dto::user::{is_phone_number, normalize_email};
+use crate::error::error::{AppError, ErrorResponse};
+use crate::jsonwebtoken::{claims_from_request, extract_token, AuthToken};
+use crate::middleware::helpers::{Resp, SimpleResp};
+use crate::models::user::{self, Entity as UserEntity};
+use crate::models::user_identities::{self, Entity as IdentityEntity};
+use crate::services::auth::{complete_login, ClientInfo, LoginData};
+use crate::services::roles::assign_default_role;
+use crate::utils::login_throttle::LoginThrottle;
+use crate::utils::oauth_state::OAuthStateStore;
+use crate::utils::password_hash::hash_password;
+use actix_web::cookie::{time, Cookie, SameSite};
+use actix_web::{web, HttpRequest, HttpResponse};
+use base64::engine::general_purpose;
+use base64::engine::Engine as _;
+use chrono::Utc;
+use log::{error, info, warn};
+use rand::RngCore;
+use sea_orm::{
+    ActiveModelTrait, ActiveValue::Set, ColumnTrait, ConnectionTrait, DatabaseConnection,
+    EntityTrait, PaginatorTrait, QueryFilter, TransactionTrait,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use sha2::{Digest, Sha256};
+use std::time::Duration;
+use url::Url;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+// 保存 state 的 Cookie，回调时必须与查询参数一致，防止把他人发起的授权结果用在当前浏览器上
+const OAUTH_STATE_COOKIE: &str = "oauth_state";
+const OAUTH_COOKIE_PATH: &str = "/api/auth/oauth";
+
+// 调用提供方接口使用的HTTP客户端，启动时创建，失败时拒绝启动
+pub fn oauth_http_client() -> Result<reqwest::Client, AppError> {
+    reqwest::Client::builder()
+        .timeout(Duration::from_secs(10))
+        .user_agent("rust-web-oauth")
+        .build()
+        .map_err(|e| AppError::InternalServerError(format!("创建HTTP客户端失败: {}", e)))
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct OAuthAuthorizeData {
+    pub authorization_url: String,
+    pub state: String,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct OAuthCallbackQuery {
+    pub code: Option<String>,
+    pub state: String,
+    // 用户拒绝授权时提供方会带上 error 参数
+    pub error: Option<String>,
+}
+
+// 从提供方获取到的用户信息
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExternalProfile {
+    pub subject: String,
+    pub user_name: Option<String>,
+    pub email: Option<String>,
+    // 只有提供方确认过的邮箱才会用于关联已有账户
+    pub email_verified: bool,
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/auth/oauth/{provider}/authorize",
+    tag = "鉴权模块",
+    operation_id = "第三方登录授权地址",
+    params(
+        ("provider" = String, Path, description = "第三方登录提供方，例如 github")
+    ),
+    responses(
+        (status = 200, description = "获取授权地址成功", body = CommonResponse<OAuthAuthorizeData>),
+        (status = 404, description = "未配置该提供方", body = ErrorResponse),
+    ),
+)]
+// 生成带 state 和 PKCE 校验的授权地址，携带访问令牌调用时回调会绑定到当前用户
+pub async fn oauth_authorize(
+    provider: web::Path<String>,
+    store: web::Data<OAuthStateStore>,
+    req: HttpRequest,
+) -> SimpleResp {
+    let provider = provider_config(&provider)?;
+    let link_user_uuid = match extract_token(req.headers()) {
+        Some(AuthToken::Jwt(_)) => Some(claims_from_request(&req)?.user_uuid),
+        // 公开接口不经过认证中间件，API密钥无法在这里解析
+        Some(AuthToken::ApiKey(_)) => {
+            return Err(AppError::BadRequest(
+                "绑定第三方账户需要使用登录令牌".into(),
+            ))
+        }
+        None => None,
+    };
+
+    let state = random_token();
+    let code_verifier = random_token();
+    let authorization_url = Url::parse_with_params(
+        &provider.authorize_url,
+        &[
+            ("response_type", "code"),
+            ("client_id", provider.client_id.as_str()),
+            ("redirect_uri", provider.redirect_uri.as_str()),
+            ("scope", provider.scopes.as_str()),
+            ("state", state.as_str()),
+            ("code_challenge", pkce_challenge(&code_verifier).as_str()),
+            ("code_challenge_method", "S256"),
+        ],
+    )
+    .map_err(|e| {
+        error!("第三方登录 {} 授权地址配置错误: {}", provider.name, e);
+        AppError::InternalServerError("第三方登录配置错误".into())
+    })?;
+    store.insert(state.clone(), &provider.name, code_verifier, link_user_uuid);
+
+    let cookie = state_cookie(&state, provider.redirect_uri.starts_with("https://"));
+    let data = OAuthAuthorizeData {
+        authorization_url: authorization_url.to_string(),
+        state,
+    };
+    Ok(HttpResponse::Ok()
+        .cookie(cookie)
+        .json(Resp::ok(data, "获取授权地址成功")))
+}
+
+// 只允许本站发起的导航携带，前端脚本无法读取
+fn state_cookie(state: &str, secure: bool) -> Cookie<'static> {
+    Cookie::build(OAUTH_STATE_COOKIE, state.to_string())
+        .path(OAUTH_COOKIE_PATH)
+        .http_only(true)
+        .secure(secure)
+        .same_site(SameSite::Lax)
+        .max_age(time::Duration::seconds(
+            OAUTH_CONFIG.state_ttl.as_secs() as i64
+        ))
+        .finish()
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/auth/oauth/{provider}/callback",
+    tag = "鉴权模块",
+    operation_id = "第三方登录回调",
+    params(
+        ("provider" = String, Path, description = "第三方登录提供方，例如 github"),
+        ("code" = String, Query, description = "提供方返回的授权码"),
+        ("state" = String, Query, description = "发起授权时生成的 state"),
+    ),
+    responses(
+        (status = 200, description = "登录成功，已开启双重验证时返回挑战令牌", body = CommonResponse<LoginData>),
+        (status = 400, description = "授权请求无效或已过期", body = ErrorResponse),
+        (status = 409, description = "该第三方账户已绑定其他用户", body = ErrorResponse),
+        (status = 500, description = "提供方接口调用失败", body = ErrorResponse),
+    ),
+)]
+// 用授权码换取用户信息，登录或绑定到对应的用户
+pub async fn oauth_callback(
+    db: web::Data<DatabaseConnection>,
+    store: web::Data<OAuthStateStore>,
+    throttle: web::Data<LoginThrottle>,
+    http_client: web::Data<reqwest::Client>,
+    provider: web::Path<String>,
+    query: web::Query<OAuthCallbackQuery>,
+    req: HttpRequest,
+) -> SimpleResp {
+    if let Some(err) = &query.error {
+        warn!("第三方登录 {} 授权失败: {}", provider, err);
+        return Err(AppError::BadRequest("第三方授权未完成".into()));
+    }
+    // state 必须来自当前浏览器发起的授权，不一致时不消耗已保存的授权请求
+    if req
+        .cookie(OAUTH_STATE_COOKIE)
+        .map(|c| c.value().to_string())
+        != Some(query.state.clone())
+    {
+        warn!("第三方登录 {} 回调的 state 与 Cookie 不一致", provider);
+        return Err(AppError::BadRequest("授权请求无效或已过期".into()));
+    }
+    let provider = provider_config(&provider)?;
+    let pending = store
+        .take(&query.state)
+        .filter(|pending| pending.provider == provider.name)
+        .ok_or_else(|| AppError::BadRequest("授权请求无效或已过期".into()))?;
+    let code = query
+        .code
+        .as_deref()
+        .ok_or_else(|| AppError::BadRequest("缺少授权码".into()))?;
+
+    let access_token = exchange_code(&http_client, &provider, code, &pending.code_verifier).await?;
+    let profile = fetch_profile(&http_client, &provider, &access_token).await?;
+
+    let mut res = match pending.link_user_uuid {
+        Some(user_uuid) => {
+            link_identity(db.as_ref(), &user_uuid, &provider.name, &profile).await?;
+            info!("用户 {} 绑定了 {} 账户", user_uuid, provider.name);
+            Resp::ok("", "第三方账户绑定成功").to_json_result()?
+        }
+        None => {
+            let credentials = find_or_create_user(db.as_ref(), &provider.name, &profile).await?;
+            let client = ClientInfo::from_request(&req);
+            complete_login(db.as_ref(), &throttle, credentials, &client).await?
+        }
+    };
+    // state 已经用过，清除 Cookie
+    let mut removal = Cookie::named(OAUTH_STATE_COOKIE);
+    removal.set_path(OAUTH_COOKIE_PATH);
+    res.add_removal_cookie(&removal)
+        .map_err(|e| AppError::InternalServerError(e.to_string()))?;
+    Ok(res)
+}
+
+fn provider_config(name: &str) -> Result<OAuthProviderConfig, AppError> {
+    OAUTH_CONFIG
+        .providers
+        .get(&name.to_lowercase())
+        .cloned()
+        .ok_or_else(|| AppError::NotFound(format!("未配置第三方登录: {}", name)))
+}
+
+fn upstream_error(provider: &OAuthProviderConfig, e: impl std::fmt::Display) -> AppError {
+    error!("第三方登录 {} 接口调用失败: {}", provider.name, e);
+    AppError::InternalServerError("第三方登录失败，请稍后重试".into())
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: Option<String>,
+    error: Option<String>,
+    error_description: Option<String>,
+}
+
+// 使用授权码和 PKCE 校验码换取访问令牌
+pub async fn exchange_code(
+    client: &reqwest::Client,
+    provider: &OAuthProviderConfig,
+    code: &str,
+    code_verifier: &str,
+) -> Result<String, AppError> {
+    let response: TokenResponse = client
+        .post(&provider.token_url)
+        .header(reqwest::header::ACCEPT, "application/json")
+        .form(&[
+            ("grant_type", "authorization_code"),
+            ("code", code),
+            ("redirect_uri", provider.redirect_uri.as_str()),
+            ("client_id", provider.client_id.as_str()),
+            ("client_secret", provider.client_secret.as_str()),
+            ("code_verifier", code_verifier),
+        ])
+        .send()
+        .await
+        .map_err(|e| upstream_error(provider, e))?
+        .json()
+        .await
+        .map_err(|e| upstream_error(provider, e))?;
+
+    // GitHub 换取失败时同样返回200，只能通过 error 字段判断
+    match (response.access_token, response.error) {
+        (Some(token), None) => Ok(token),
+        (_, error) => {
+            warn!(
+                "第三方登录 {} 授权码无效: {:?} {:?}",
+                provider.name, error, response.error_description
+            );
+            Err(AppError::BadRequest("授权码无效或已过期".into()))
+        }
+    }
+}
+
+// 获取提供方的用户信息
+pub async fn fetch_profile(
+    client: &reqwest::Client,
+    provider: &OAuthProviderConfig,
+    access_token: &str,
+) -> Result<ExternalProfile, AppError> {
+    let userinfo = get_json(client, provider, &provider.userinfo_url, access_token).await?;
+
+    let profile = match provider.kind {
+        ProviderKind::Github => {
+            let subject = userinfo["id"].as_i64().map(|id| id.to_string());
+            // /user 只返回公开邮箱，已验证的主邮箱需要单独查询
+            let emails_url = format!("{}/emails", provider.userinfo_url.trim_end_matches('/'));
+            let email = match get_json(client, provider, &emails_url, access_token).await {
+                Ok(JsonValue::Array(emails)) => emails
+                    .iter()
+                    .find(|e| e["primary"].as_bool() == Some(true))
+                    .filter(|e| e["verified"].as_bool() == Some(true))
+                    .and_then(|e| e["email"].as_str())
+                    .map(str::to_string),
+                _ => None,
+            };
+            ExternalProfile {
+                subject: subject.unwrap_or_default(),
+                user_name: userinfo["login"].as_str().map(str::to_string),
+                email_verified: email.is_some(),
+                email,
+            }
+        }
+        ProviderKind::Oidc => ExternalProfile {
+            subject: userinfo["sub"].as_str().unwrap_or_default().to_string(),
+            user_name: userinfo["preferred_username"]
+                .as_str()
+                .or_else(|| userinfo["name"].as_str())
+                .map(str::to_string),
+            email: userinfo["email"].as_str().map(str::to_string),
+            email_verified: userinfo["email_verified"].as_bool().unwrap_or(false),
+        },
+    };
+
+    if profile.subject.is_empty() {
+        return Err(upstream_error(provider, "用户信息缺少唯一标识"));
+    }
+    Ok(ExternalProfile {
+        email: profile.email.as_deref().map(normalize_email),
+        ..profile
+    })
+}
+
+async fn get_json(
+    client: &reqwest::Client,
+    provider: &OAuthProviderConfig,
+    url: &str,
+    access_token: &str,
+) -> Result<JsonValue, AppError> {
+    client
+        .get(url)
+        .bearer_auth(access_token)
+        .header(reqwest::header::ACCEPT, "application/json")
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(|e| upstream_error(provider, e))?
+        .json()
+        .await
+        .map_err(|e| upstream_error(provider, e))
+}
+
+// 查找第三方身份对应的用户，没有时依次尝试按已验证邮箱关联、创建新用户
+async fn find_or_create_user(
+    db: &DatabaseConnection,
+    provider: &str,
+    profile: &ExternalProfile,
+) -> Result<user::Model, AppError> {
+    if let Some(identity) = find_identity(db, provider, &profile.subject).await? {
+        let existing = UserEntity::find_by_uuid(&identity.user_uuid)
+            .one(db)
+            .await?;
+        let mut identity_active: user_identities::ActiveModel = identity.into();
+        match existing {
+            Some(existing) => {
+                identity_active.last_login_at = Set(Some(Utc::now()));
+                identity_active.update(db).await?;
+                return Ok(existing);
+            }
+            // 用户已被删除，清理残留的绑定后按新用户处理
+            None => {
+                identity_active.delete(db).await?;
+            }
+        }
+    }
+
+    let txn = db.begin().await?;
+    // 双方都确认过的邮箱才能关联已有账户，避免通过未验证邮箱接管他人账户
+    let linked = match (&profile.email, profile.email_verified) {
+        (Some(email), true) => {
+            UserEntity::find()
+                .filter(user::Column::Email.eq(email))
+                .filter(user::Column::EmailVerifiedAt.is_not_null())
+                .one(&txn)
+                .await?
+        }
+        _ => None,
+    };
+    let credentials = match linked {
+        Some(existing) => existing,
+        None => create_user(&txn, provider, profile).await?,
+    };
+    insert_identity(&txn, &credentials.uuid, provider, profile).await?;
+    txn.commit().await?;
+
+    info!("用户 {} 通过 {} 首次登录", credentials.uuid, provider);
+    Ok(credentials)
+}
+
+async fn create_user<C: ConnectionTrait>(
+    db: &C,
+    provider: &str,
+    profile: &ExternalProfile,
+) -> Result<user::Model, AppError> {
+    let base = profile
+        .user_name
+        .clone()
+        .unwrap_or_else(|| format!("{}_{}", provider, profile.subject));
+    let user_name = unique_user_name(db, &base).await?;
+
+    // 邮箱已被其他账户占用时不再写入，用户可以稍后自行设置
+    let email = match (&profile.email, profile.email_verified) {
+        (Some(email), true) => {
+            let taken = UserEntity::find()
+                .filter(user::Column::Email.eq(email))
+                .count(db)
+                .await?
+                > 0;
+            (!taken).then(|| email.clone())
+        }
+        _ => None,
+    };
+
+    let new_user = user::ActiveModel {
+        uuid: Set(Uuid::new_v4().to_string()),
+        user_name: Set(user_name),
+        // 第三方登录的用户没有本地密码，写入一个随机密码的哈希，之后可通过重置密码设置
+        pass_word: Set(hash_password(&random_token())?),
+        email_verified_at: Set(email.as_ref().map(|_| Utc::now())),
+        email: Set(email),
+        created_at: Set(Utc::now()),
+        updated_at: Set(Utc::now()),
+        ..Default::default()
+    };
+    let created_user = new_user.insert(db).await?;
+    assign_default_role(db, &created_user.uuid).await?;
+    Ok(created_user)
+}
+
+// 用户名已存在时追加随机后缀
+async fn unique_user_name<C: ConnectionTrait>(db: &C, base: &str) -> Result<String, AppError> {
+    let mut base: String = base
+        .chars()
+        .filter(|c| c.is_alphanumeric() || matches!(c, '_' | '-' | '.'))
+        .take(80)
+        .collect();
+    while base.chars().count() < 5 {
+        base.push('_');
+    }
+    // 与注册时的用户名规则一致，不能是手机号格式
+    if is_phone_number(&base) {
+        base = format!("user_{}", base);
+    }
+
+    let mut candidate = base.clone();
+    for _ in 0..5 {
+        let exists = UserEntity::find()
+            .filter(user::Column::UserName.eq(&candidate))
+            .count(db)
+            .await?
+            > 0;
+        if !exists {
+            return Ok(candidate);
+        }
+        candidate = format!("{}_{}", base, &Uuid::new_v4().simple().to_string()[..6]);
+    }
+    Err(AppError::Conflict("无法生成可用的用户名".into()))
+}
+
+async fn find_identity<C: ConnectionTrait>(
+    db: &C,
+    provider: &str,
+    subject: &str,
+) -> Result<Option<user_identities::Model>, AppError> {
+    Ok(IdentityEntity::find()
+        .filter(user_identities::Column::Provider.eq(provider))
+        .filter(user_identities::Column::Subject.eq(subject))
+        .one(db)
+        .await?)
+}
+
+async fn insert_identity<C: ConnectionTrait>(
+    db: &C,
+    user_uuid: &str,
+    provider: &str,
+    profile: &ExternalProfile,
+) -> Result<(), AppError> {
+    let identity = user_identities::ActiveModel {
+        user_uuid: Set(user_uuid.to_string()),
+        provider: Set(provider.to_string()),
+        subject: Set(profile.subject.clone()),
+        email: Set(profile.email.clone()),
+        created_at: Set(Utc::now()),
+        last_login_at: Set(Some(Utc::now())),
+        ..Default::default()
+    };
+    identity.insert(db).await?;
+    Ok(())
+}
+
+// 把第三方身份绑定到已登录的用户
+async fn link_identity(
+    db: &DatabaseConnection,
+    user_uuid: &str,
+    provider: &str,
+    profile: &ExternalProfile,
+) -> Result<(), AppError> {
+    match find_identity(db, provider, &profile.subject).await? {
+        Some(identity) if identity.user_uuid == user_uuid => Ok(()),
+        Some(_) => Err(AppError::Conflict("该第三方账户已绑定其他用户".into())),
+        None => insert_identity(db, user_uuid, provider, profile).await,
+    }
+}
+
+// 32字节随机值，用作 state 和 PKCE 校验码
+fn random_token() -> String {
+    let mut bytes = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    general_purpose::URL_SAFE_NO_PAD.encode(bytes)
+}
+
+// PKCE S256: BASE64URL(SHA256(code_verifier))
+fn pkce_challenge(code_verifier: &str) -> String {
+    general_purpose::URL_SAFE_NO_PAD.encode(Sha256::digest(code_verifier.as_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::http::header;
+    use actix_web::{App, HttpResponse, HttpServer};
+    use std::collections::HashMap;
+
+    const VERIFIER: &str = "dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXk";
+
+    // 本地模拟的身份提供方，只接受固定的授权码和 PKCE 校验码
+    async fn mock_token(form: web::Form<HashMap<String, String>>) -> HttpResponse {
+        if form.get("code").map(String::as_str) == Some("good-code")
+            && form.get("code_verifier").map(String::as_str) == Some(VERIFIER)
+        {
+            HttpResponse::Ok().json(serde_json::json!({ "access_token": "mock-token" }))
+        } else {
+            HttpResponse::Ok().json(serde_json::json!({ "error": "invalid_grant" }))
+        }
+    }
+
+    async fn mock_userinfo(req: HttpRequest) -> HttpResponse {
+        match req.headers().get(header::AUTHORIZATION) {
+            Some(value) if value == "Bearer mock-token" => {
+                HttpResponse::Ok().json(serde_json::json!({
+                    "sub": "user-42",
+                    "preferred_username": "mock_user",
+                    "email": " Mock.User@Example.com ",
+                    "email_verified": true,
+                }))
+            }
+            _ => HttpResponse::Unauthorized().finish(),
+        }
+    }
+
+    fn mock_provider(base: &str) -> OAuthProviderConfig {
+        OAuthProviderConfig {
+            name: "mock".to_string(),
+            kind: ProviderKind::Oidc,
+            client_id: "client".to_string(),
+            client_secret: "secret".to_string(),
+            authorize_url: format!("{}/authorize", base),
+            token_url: format!("{}/token", base),
+            userinfo_url: format!("{}/userinfo", base),
+            scopes: "openid profile email".to_string(),
+            redirect_uri: "http://127.0.0.1:5502/oauth/callback".to_string(),
+        }
+    }
+
+    #[test]
+    fn state_cookie_is_http_only_and_lax() {
+        let cookie = state_cookie("abc", true);
+        assert_eq!(cookie.name(), OAUTH_STATE_COOKIE);
+        assert_eq!(cookie.value(), "abc");
+        assert_eq!(cookie.http_only(), Some(true));
+        assert_eq!(cookie.secure(), Some(true));
+        assert_eq!(cookie.same_site(), Some(SameSite::Lax));
+        assert_eq!(cookie.path(), Some(OAUTH_COOKIE_PATH));
+        assert_eq!(state_cookie("abc", false).secure(), Some(false));
+    }
+
+    #[actix_web::test]
+    async fn callback_requires_state_cookie_from_same_browser() {
+        let store = OAuthStateStore::new(Duration::from_secs(60));
+        store.insert(
+            "victim-state".into(),
+            "mock",
+            VERIFIER.into(),
+            Some("u-1".into()),
+        );
+        let app = actix_web::test::init_service(
+            App::new()
+                .app_data(web::Data::new(
+                    sea_orm::MockDatabase::new(sea_orm::DatabaseBackend::MySql).into_connection(),
+                ))
+                .app_data(web::Data::new(store.clone()))
+                .app_data(web::Data::new(LoginThrottle::default()))
+                .app_data(web::Data::new(reqwest::Client::new()))
+                .route("/callback/{provider}", web::get().to(oauth_callback)),
+        )
+        .await;
+        let uri = "/callback/mock?code=good-code&state=victim-state";
+
+        // 攻击者把自己的回调地址发给受害者，受害者浏览器中没有对应的 Cookie
+        let req = actix_web::test::TestRequest::get().uri(uri).to_request();
+        let res = actix_web::test::call_service(&app, req).await;
+        assert_eq!(res.status(), 400);
+
+        let req = actix_web::test::TestRequest::get()
+            .uri(uri)
+            .cookie(Cookie::new(OAUTH_STATE_COOKIE, "other-state"))
+            .to_request();
+        let res = actix_web::test::call_service(&app, req).await;
+        assert_eq!(res.status(), 400);
+
+        // 被拒绝的回调不会消耗授权请求
+        assert!(store.take("victim-state").is_some());
+    }
+
+    #[test]
+    fn test_pkce_challenge_matches_rfc7636() {
+        assert_eq!(
+            pkce_challenge(VERIFIER),
+            "E9Melhoa2OwvFrEMTJguCHaoeK1t8URWbuGJSstw-cM"
+        );
+    }
+
+    #[actix_web::test]
+    async fn test_code_exchange_against_mock_provider() {
+        let server = HttpServer::new(|| {
+            App::new()
+                .route("/token", web::post().to(mock_token))
+                .route("/userinfo", web::get().to(mock_userinfo))
+        })
+        .bind("127.0.0.1:0")
+        .unwrap();
+        let addr = server.addrs()[0];
+        let server = server.workers(1).run();
+        let handle = server.handle();
+        actix_web::rt::spawn(server);
+
+        let provider = mock_provider(&format!("http://{}", addr));
+        let client = reqwest::Client::new();
+
+        let token = exchange_code(&client, &provider, "good-code", VERIFIER)
+            .await
+            .unwrap();
+        assert_eq!(token, "mock-token");
+        assert!(
+            exchange_code(&client, &provider, "good-code", "wrong-verifier")
+                .await
+                .is_err()
+        );
+
+        let profile = fetch_profile(&client, &provider, &token).await.unwrap();
+        assert_eq!(
+            profile,
+            ExternalProfile {
+                subject: "user-42".to_string(),
+                user_name: Some("mock_user".to_string()),
+                email: Some("mock.user@example.com".to_string()),
+                email_verified: true,
+            }
+        );
+
+        handle.stop(true).await;
+    }
+}
@@ -0,0 +1,382 @@
+use crate::common::CommonResponse;
+use crate::config::password::PASSWORD_CONFIG;
+use crate::dto::user::{
+    password_policy_error, ChangePasswordRequest, PasswordResetConfirmRequest, PasswordResetRequest,
+};
+use crate::error::error::{AppError, ErrorResponse};
+use crate::middleware::helpers::{Resp, SimpleResp};
+use crate::models::password_reset_tokens::{self, Entity as ResetTokenEntity};
+use crate::models::user::{self, Entity as UserEntity};
+use crate::utils::crypto::sha256_hex;
+use crate::utils::current_user::CurrentUser;
+use crate::utils::notifier::{Notification, Notifier};
+use crate::utils::password_hash::{hash_password, verify_password};
+use actix_web::web;
+use base64::engine::general_purpose;
+use base64::engine::Engine as _;
+use chrono::Utc;
+use log::{error, info, warn};
+use rand::RngCore;
+use sea_orm::sea_query::Expr;
+use sea_orm::{
+    ActiveModelTrait, ActiveValue::Set, ColumnTrait, ConnectionTrait, DatabaseConnection,
+    EntityTrait, QueryFilter, TransactionTrait,
+};
+use validator::Validate;
+
+#[utoipa::path(
+    post,
+    path = "/api/auth/password/change",
+    request_body = ChangePasswordRequest,
+    tag = "鉴权模块",
+    operation_id = "修改密码",
+    responses(
+        (status = 200, description = "密码修改成功", body = CommonResponse<String>),
+        (status = 400, description = "新密码不符合要求", body = ErrorResponse),
+        (status = 401, description = "旧密码错误", body = ErrorResponse),
+    ),
+)]
+// 已登录用户修改自己的密码
+pub async fn change_password(
+    db: web::Data<DatabaseConnection>,
+    current_user: CurrentUser,
+    payload: web::Json<ChangePasswordRequest>,
+) -> SimpleResp {
+    if let Err(e) = payload.validate() {
+        info!("修改密码:{:?}", e);
+        return Err(e.into());
+    }
+    let payload = payload.into_inner();
+
+    let user = UserEntity::find_by_uuid(&current_user.uuid)
+        .one(db.as_ref())
+        .await?
+        .ok_or_else(|| AppError::NotFound("用户不存在".into()))?;
+
+    if !verify_password(&payload.old_password, &user.pass_word) {
+        warn!("用户 {} 修改密码时旧密码错误", user.uuid);
+        return Err(AppError::Unauthorized("旧密码错误".into()));
+    }
+    password_policy_error(&payload.new_password, &user.user_name)
+        .map_err(|e| AppError::BadRequest(e.to_string()))?;
+
+    let user_uuid = user.uuid.clone();
+    let txn = db.begin().await?;
+    update_password(&txn, user, &payload.new_password).await?;
+    invalidate_reset_tokens(&txn, &user_uuid).await?;
+    txn.commit().await?;
+
+    info!("用户 {} 修改了密码", user_uuid);
+    Resp::ok("", "密码修改成功").to_json_result()
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/auth/password/reset",
+    request_body = PasswordResetRequest,
+    tag = "鉴权模块",
+    operation_id = "申请重置密码",
+    responses(
+        (status = 200, description = "如果账户存在，重置链接已发送", body = CommonResponse<String>),
+        (status = 400, description = "验证错误", body = ErrorResponse),
+    ),
+)]
+// 申请重置密码，无论用户是否存在、是否有已验证的邮箱都返回相同结果
+pub async fn request_password_reset(
+    db: web::Data<DatabaseConnection>,
+    notifier: web::Data<dyn Notifier>,
+    payload: web::Json<PasswordResetRequest>,
+) -> SimpleResp {
+    if let Err(e) = payload.validate() {
+        info!("申请重置密码:{:?}", e);
+        return Err(e.into());
+    }
+    let payload = payload.into_inner();
+    let message = "如果账户存在，重置链接已发送";
+
+    let Some(user) = UserEntity::find()
+        .filter(user::Column::UserName.eq(&payload.user_name))
+        .one(db.as_ref())
+        .await?
+    else {
+        info!("申请重置密码的用户 '{}' 不存在", payload.user_name);
+        return Resp::ok("", message).to_json_result();
+    };
+    // 重置链接只发往已验证的邮箱，未验证的邮箱可能是他人随意填写的
+    let recipient = match (&user.email, user.email_verified_at) {
+        (Some(email), Some(_)) => email.clone(),
+        _ => {
+            info!("用户 {} 没有已验证的邮箱，不发送重置链接", user.uuid);
+            return Resp::ok("", message).to_json_result();
+        }
+    };
+
+    let token = generate_reset_token();
+    let expires_at = Utc::now()
+        + chrono::Duration::from_std(PASSWORD_CONFIG.reset_token_ttl)
+            .unwrap_or_else(|_| chrono::Duration::minutes(30));
+    let record = password_reset_tokens::ActiveModel {
+        user_uuid: Set(user.uuid.clone()),
+        token_hash: Set(hash_reset_token(&token)),
+        expires_at: Set(expires_at),
+        created_at: Set(Utc::now()),
+        ..Default::default()
+    };
+    record.insert(db.as_ref()).await?;
+
+    let notification = Notification {
+        recipient,
+        subject: "重置密码".to_string(),
+        body: format!(
+            "您好 {}，请在{}分钟内打开以下链接重置密码：\n{}?token={}\n如果不是您本人操作，请忽略本消息。",
+            user.user_name,
+            PASSWORD_CONFIG.reset_token_ttl.as_secs() / 60,
+            PASSWORD_CONFIG.reset_url,
+            token
+        ),
+    };
+    // 投递失败不暴露给客户端，避免借此判断用户是否存在
+    if let Err(e) = notifier.send(&notification).await {
+        error!("发送重置密码通知失败: {}", e);
+    }
+
+    Resp::ok("", message).to_json_result()
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/auth/password/reset/confirm",
+    request_body = PasswordResetConfirmRequest,
+    tag = "鉴权模块",
+    operation_id = "确认重置密码",
+    responses(
+        (status = 200, description = "密码重置成功", body = CommonResponse<String>),
+        (status = 400, description = "令牌无效或密码不符合要求", body = ErrorResponse),
+    ),
+)]
+// 使用重置令牌设置新密码，令牌只能使用一次
+pub async fn confirm_password_reset(
+    db: web::Data<DatabaseConnection>,
+    payload: web::Json<PasswordResetConfirmRequest>,
+) -> SimpleResp {
+    if let Err(e) = payload.validate() {
+        info!("确认重置密码:{:?}", e);
+        return Err(e.into());
+    }
+    let payload = payload.into_inner();
+    let invalid = || AppError::BadRequest("重置令牌无效或已过期".into());
+
+    let record = ResetTokenEntity::find()
+        .filter(password_reset_tokens::Column::TokenHash.eq(hash_reset_token(&payload.token)))
+        .filter(password_reset_tokens::Column::UsedAt.is_null())
+        .filter(password_reset_tokens::Column::ExpiresAt.gt(Utc::now()))
+        .one(db.as_ref())
+        .await?
+        .ok_or_else(invalid)?;
+
+    let user = UserEntity::find_by_uuid(&record.user_uuid)
+        .one(db.as_ref())
+        .await?
+        .ok_or_else(invalid)?;
+    password_policy_error(&payload.new_password, &user.user_name)
+        .map_err(|e| AppError::BadRequest(e.to_string()))?;
+
+    let user_uuid = user.uuid.clone();
+    let txn = db.begin().await?;
+    // 只有仍未使用的令牌才会被标记，并发提交时只有一个请求能成功
+    let claimed = ResetTokenEntity::update_many()
+        .col_expr(
+            password_reset_tokens::Column::UsedAt,
+            Expr::value(Utc::now()),
+        )
+        .filter(password_reset_tokens::Column::Id.eq(record.id))
+        .filter(password_reset_tokens::Column::UsedAt.is_null())
+        .exec(&txn)
+        .await?;
+    if claimed.rows_affected != 1 {
+        return Err(invalid());
+    }
+    update_password(&txn, user, &payload.new_password).await?;
+    invalidate_reset_tokens(&txn, &user_uuid).await?;
+    txn.commit().await?;
+
+    info!("用户 {} 通过重置令牌修改了密码", user_uuid);
+    Resp::ok("", "密码重置成功").to_json_result()
+}
+
+async fn update_password<C: ConnectionTrait>(
+    db: &C,
+    user: user::Model,
+    new_password: &str,
+) -> Result<(), AppError> {
+    let mut user_active: user::ActiveModel = user.into();
+    user_active.pass_word = Set(hash_password(new_password)?);
+    user_active.updated_at = Set(Utc::now());
+    user_active.update(db).await?;
+    Ok(())
+}
+
+// 作废用户所有尚未使用的重置令牌
+async fn invalidate_reset_tokens<C: ConnectionTrait>(
+    db: &C,
+    user_uuid: &str,
+) -> Result<(), AppError> {
+    ResetTokenEntity::update_many()
+        .col_expr(
+            password_reset_tokens::Column::UsedAt,
+            Expr::value(Utc::now()),
+        )
+        .filter(password_reset_tokens::Column::UserUuid.eq(user_uuid))
+        .filter(password_reset_tokens::Column::UsedAt.is_null())
+        .exec(db)
+        .await?;
+    Ok(())
+}
+
+// 生成32字节随机令牌，使用URL安全的base64编码
+fn generate_reset_token() -> String {
+    let mut bytes = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    general_purpose::URL_SAFE_NO_PAD.encode(bytes)
+}
+
+// 数据库中只保存令牌的SHA-256摘要
+fn hash_reset_token(token: &str) -> String {
+    sha256_hex(token)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{test, App};
+    use async_trait::async_trait;
+    use sea_orm::{DatabaseBackend, MockDatabase, MockExecResult, Transaction};
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Default)]
+    struct RecordingNotifier(Mutex<Vec<Notification>>);
+
+    #[async_trait]
+    impl Notifier for RecordingNotifier {
+        async fn send(&self, notification: &Notification) -> Result<(), AppError> {
+            self.0.lock().unwrap().push(notification.clone());
+            Ok(())
+        }
+    }
+
+    fn exec_result(rows_affected: u64) -> MockExecResult {
+        MockExecResult {
+            last_insert_id: 1,
+            rows_affected,
+        }
+    }
+
+    fn reset_token(token: &str) -> password_reset_tokens::Model {
+        password_reset_tokens::Model {
+            id: 1,
+            user_uuid: "u-1".into(),
+            token_hash: hash_reset_token(token),
+            expires_at: Utc::now() + chrono::Duration::minutes(10),
+            used_at: None,
+            created_at: Utc::now(),
+        }
+    }
+
+    // 申请重置密码，返回实际发出的通知
+    async fn request_reset(user: user::Model) -> Vec<Notification> {
+        let db = MockDatabase::new(DatabaseBackend::MySql)
+            .append_query_results([vec![user]])
+            .append_exec_results([exec_result(1)])
+            .append_query_results([vec![reset_token("unused")]])
+            .into_connection();
+        let notifier = Arc::new(RecordingNotifier::default());
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(db))
+                .app_data(web::Data::<dyn Notifier>::from(
+                    notifier.clone() as Arc<dyn Notifier>
+                ))
+                .route("/reset", web::post().to(request_password_reset)),
+        )
+        .await;
+        let req = test::TestRequest::post()
+            .uri("/reset")
+            .set_json(serde_json::json!({"user_name": "alice01"}))
+            .to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), 200);
+        let sent = notifier.0.lock().unwrap().clone();
+        sent
+    }
+
+    // 提交重置令牌，返回状态码和执行过的SQL
+    async fn confirm(db: MockDatabase, token: &str) -> (u16, Vec<Transaction>) {
+        let db = web::Data::new(db.into_connection());
+        let app = test::init_service(
+            App::new()
+                .app_data(db.clone())
+                .route("/confirm", web::post().to(confirm_password_reset)),
+        )
+        .await;
+        let req = test::TestRequest::post()
+            .uri("/confirm")
+            .set_json(serde_json::json!({"token": token, "new_password": "n3w-secret"}))
+            .to_request();
+        let status = test::call_service(&app, req).await.status().as_u16();
+        drop(app);
+        let db = Arc::try_unwrap(db.into_inner()).unwrap();
+        (status, db.into_transaction_log())
+    }
+
+    #[actix_web::test]
+    async fn reset_link_is_only_sent_to_a_verified_email() {
+        let mut user = user::Model::fixture("u-1", "alice01");
+        user.email = Some("alice@example.com".into());
+        assert!(request_reset(user.clone()).await.is_empty());
+
+        user.email_verified_at = Some(Utc::now());
+        let sent = request_reset(user).await;
+        assert_eq!(sent.len(), 1);
+        assert_eq!(sent[0].recipient, "alice@example.com");
+
+        // 没有邮箱时不会退回到用户名
+        assert!(request_reset(user::Model::fixture("u-1", "alice01"))
+            .await
+            .is_empty());
+    }
+
+    #[actix_web::test]
+    async fn reset_token_is_single_use_and_expires() {
+        let user = user::Model::fixture("u-1", "alice01");
+
+        // 已使用或已过期的令牌查询不到
+        let db = MockDatabase::new(DatabaseBackend::MySql)
+            .append_query_results([Vec::<password_reset_tokens::Model>::new()]);
+        let (status, log) = confirm(db, "expired").await;
+        assert_eq!(status, 400);
+        let lookup = log[0].statements()[0].to_string();
+        assert!(lookup.contains(&hash_reset_token("expired")));
+        assert!(lookup.contains("`used_at` IS NULL"));
+        assert!(lookup.contains("`expires_at` >"));
+
+        // 并发提交时令牌已被另一个请求标记为已使用
+        let db = MockDatabase::new(DatabaseBackend::MySql)
+            .append_query_results([vec![reset_token("raced")]])
+            .append_query_results([vec![user.clone()]])
+            .append_exec_results([exec_result(0)]);
+        assert_eq!(confirm(db, "raced").await.0, 400);
+
+        let db = MockDatabase::new(DatabaseBackend::MySql)
+            .append_query_results([vec![reset_token("valid")]])
+            .append_query_results([vec![user.clone()]])
+            .append_exec_results([exec_result(1), exec_result(1)])
+            .append_query_results([vec![user]])
+            .append_exec_results([exec_result(0)]);
+        let (status, log) = confirm(db, "valid").await;
+        assert_eq!(status, 200);
+        // 标记令牌已使用、更新密码、作废其余令牌在同一事务中完成
+        let statements = log[2].statements();
+        assert!(statements[1].to_string().contains("`used_at` IS NULL"));
+        assert_eq!(statements.last().unwrap().to_string(), "COMMIT");
+    }
+}
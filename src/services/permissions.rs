@@ -0,0 +1,244 @@
+use crate::common::CommonResponse;
+use crate::dto::permission::PermissionNamesRequest;
+use crate::error::error::{AppError, ErrorResponse};
+use crate::middleware::helpers::{Resp, SimpleResp};
+use crate::models::user;
+use crate::permission::Permission;
+use crate::services::auth::PermissionResponse;
+use crate::services::roles::{effective_permissions, user_roles};
+use crate::services::routes::{route_table, Access, RouteSpec};
+use crate::services::user::find_user;
+use actix_web::web;
+use chrono::Utc;
+use log::info;
+use sea_orm::{ActiveModelTrait, ActiveValue::Set, ConnectionTrait, DatabaseConnection};
+use serde::Serialize;
+use utoipa::ToSchema;
+use validator::Validate;
+
+#[derive(Serialize, ToSchema)]
+pub struct UserPermissionsData {
+    pub roles: Vec<String>,
+    // 在角色之外额外授予的权限
+    pub granted: Vec<String>,
+    // 被禁止的权限，即使角色包含也不生效
+    pub denied: Vec<String>,
+    pub effective: Vec<PermissionResponse>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct RouteInfo {
+    pub method: String,
+    pub path: String,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct PermissionRoutes {
+    pub name: String,
+    pub description: String,
+    pub routes: Vec<RouteInfo>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/users/{uuid}/permissions",
+    tag = "权限模块",
+    operation_id = "获取用户有效权限",
+    params(
+        ("uuid" = String, Path, description = "用户的 UUID")
+    ),
+    responses(
+        (status = 200, description = "获取成功", body = CommonResponse<UserPermissionsData>),
+        (status = 404, description = "用户不存在", body = ErrorResponse),
+    ),
+)]
+// 查看用户的角色、额外授予/禁止的权限以及最终生效的权限
+pub async fn get_user_permissions(
+    db: web::Data<DatabaseConnection>,
+    uuid: web::Path<String>,
+) -> SimpleResp {
+    let user = find_user(db.as_ref(), &uuid).await?;
+    let data = user_permissions_data(db.as_ref(), &user).await?;
+    Resp::ok(data, "获取用户权限成功").to_json_result()
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/users/{uuid}/permissions/grant",
+    request_body = PermissionNamesRequest,
+    tag = "权限模块",
+    operation_id = "授予用户权限",
+    params(
+        ("uuid" = String, Path, description = "用户的 UUID")
+    ),
+    responses(
+        (status = 200, description = "授予成功", body = CommonResponse<UserPermissionsData>),
+        (status = 400, description = "权限名称无效", body = ErrorResponse),
+        (status = 404, description = "用户不存在", body = ErrorResponse),
+    ),
+)]
+// 额外授予权限，同时解除对这些权限的禁止
+pub async fn grant_user_permissions(
+    db: web::Data<DatabaseConnection>,
+    uuid: web::Path<String>,
+    payload: web::Json<PermissionNamesRequest>,
+) -> SimpleResp {
+    let requested = parse_permissions(&payload)?;
+    let user = find_user(db.as_ref(), &uuid).await?;
+
+    let granted = Permission::from_stored(user.permissions.as_deref()) | requested;
+    let denied = Permission::from_stored(user.denied_permissions.as_deref()) - requested;
+    let user = save_permissions(db.as_ref(), user, granted, denied).await?;
+
+    info!("用户 {} 被授予权限 {:?}", user.uuid, requested.names());
+    let data = user_permissions_data(db.as_ref(), &user).await?;
+    Resp::ok(data, "授予权限成功").to_json_result()
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/users/{uuid}/permissions/revoke",
+    request_body = PermissionNamesRequest,
+    tag = "权限模块",
+    operation_id = "撤销用户权限",
+    params(
+        ("uuid" = String, Path, description = "用户的 UUID")
+    ),
+    responses(
+        (status = 200, description = "撤销成功", body = CommonResponse<UserPermissionsData>),
+        (status = 400, description = "权限名称无效", body = ErrorResponse),
+        (status = 404, description = "用户不存在", body = ErrorResponse),
+    ),
+)]
+// 撤销权限：移除额外授予的部分，仍由角色提供的部分加入禁止列表
+pub async fn revoke_user_permissions(
+    db: web::Data<DatabaseConnection>,
+    uuid: web::Path<String>,
+    payload: web::Json<PermissionNamesRequest>,
+) -> SimpleResp {
+    let requested = parse_permissions(&payload)?;
+    let user = find_user(db.as_ref(), &uuid).await?;
+
+    let role_permissions = user_roles(db.as_ref(), &user.uuid)
+        .await?
+        .iter()
+        .fold(Permission::NONE, |acc, role| {
+            acc | Permission::from_stored(Some(&role.permissions))
+        });
+    let granted = Permission::from_stored(user.permissions.as_deref()) - requested;
+    let denied = Permission::from_stored(user.denied_permissions.as_deref())
+        | (requested & role_permissions);
+    let user = save_permissions(db.as_ref(), user, granted, denied).await?;
+
+    info!("用户 {} 被撤销权限 {:?}", user.uuid, requested.names());
+    let data = user_permissions_data(db.as_ref(), &user).await?;
+    Resp::ok(data, "撤销权限成功").to_json_result()
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/auth/permissions/routes",
+    tag = "权限模块",
+    operation_id = "获取权限对应的接口",
+    responses(
+        (status = 200, description = "获取成功", body = CommonResponse<Vec<PermissionRoutes>>),
+    ),
+)]
+// 根据路由表列出每个单一权限涉及的接口
+pub async fn get_permission_routes() -> SimpleResp {
+    let data = permission_routes(&route_table());
+    Resp::ok(data, "获取权限对应的接口成功").to_json_result()
+}
+
+fn permission_routes(routes: &[RouteSpec]) -> Vec<PermissionRoutes> {
+    Permission::ALL
+        .descriptions()
+        .into_iter()
+        .map(|(name, description)| {
+            let flag = Permission::from_names(&[name]).unwrap_or(Permission::NONE);
+            PermissionRoutes {
+                name: name.to_string(),
+                description: description.to_string(),
+                routes: routes
+                    .iter()
+                    .filter(|route| match &route.access {
+                        Access::Require(rule) => rule.permissions().contains(flag),
+                        Access::Public | Access::SessionOnly => false,
+                    })
+                    .map(|route| RouteInfo {
+                        method: route.method.to_string(),
+                        path: route.path.to_string(),
+                    })
+                    .collect(),
+            }
+        })
+        .collect()
+}
+
+fn parse_permissions(payload: &PermissionNamesRequest) -> Result<Permission, AppError> {
+    payload.validate().map_err(AppError::from)?;
+    Permission::from_names(&payload.permissions)
+        .map_err(|name| AppError::BadRequest(format!("无效权限: {}", name)))
+}
+
+async fn save_permissions<C: ConnectionTrait>(
+    db: &C,
+    user: user::Model,
+    granted: Permission,
+    denied: Permission,
+) -> Result<user::Model, AppError> {
+    let mut user_active: user::ActiveModel = user.into();
+    user_active.permissions = Set(Some(granted.bits().to_string()));
+    user_active.denied_permissions = Set(Some(denied.bits().to_string()));
+    user_active.updated_at = Set(Utc::now());
+    Ok(user_active.update(db).await?)
+}
+
+async fn user_permissions_data<C: ConnectionTrait>(
+    db: &C,
+    user: &user::Model,
+) -> Result<UserPermissionsData, AppError> {
+    let roles = user_roles(db, &user.uuid)
+        .await?
+        .into_iter()
+        .map(|role| role.name)
+        .collect();
+    let effective = effective_permissions(db, user)
+        .await?
+        .descriptions()
+        .into_iter()
+        .map(|(name, description)| PermissionResponse {
+            name: name.to_string(),
+            description: description.to_string(),
+        })
+        .collect();
+    Ok(UserPermissionsData {
+        roles,
+        granted: Permission::from_stored(user.permissions.as_deref()).names(),
+        denied: Permission::from_stored(user.denied_permissions.as_deref()).names(),
+        effective,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn permission_routes_follow_route_table() {
+        let data = permission_routes(&route_table());
+        let read_user = data.iter().find(|p| p.name == "READ_USER").unwrap();
+        assert_eq!(read_user.description, "读取用户权限");
+        assert!(read_user
+            .routes
+            .iter()
+            .any(|r| r.method == "GET" && r.path == "/api/users"));
+        // 公开接口不属于任何权限
+        assert!(data
+            .iter()
+            .flat_map(|p| &p.routes)
+            .all(|r| r.path != "/api/auth/login"));
+        // 每个单一权限都出现一次，组合权限不单独列出
+        assert!(data.iter().all(|p| p.name != "READ_WRITE_USER"));
+    }
+}
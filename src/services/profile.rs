@@ -0,0 +1,315 @@
+use crate::common::CommonResponse;
+use crate::dto::user::{
+    normalize_email, normalize_phone, DeleteAccountRequest, UpdateProfileRequest, UserInfo,
+};
+use crate::error::error::{AppError, ErrorResponse};
+use crate::middleware::helpers::{Resp, SimpleResp};
+use crate::models::user::{self, Entity as UserEntity};
+use crate::services::email::send_verification_email;
+use crate::services::user::{soft_delete_user, update_user_if_unchanged};
+use crate::utils::current_user::CurrentUser;
+use crate::utils::etag::{check_if_match, conditional_ok, etag_of, tagged_ok};
+use crate::utils::notifier::Notifier;
+use crate::utils::password_hash::verify_password;
+use actix_web::{web, HttpRequest};
+use chrono::Utc;
+use log::{error, info, warn};
+use sea_orm::{
+    ActiveValue::Set, ColumnTrait, DatabaseConnection, EntityTrait, PaginatorTrait, QueryFilter,
+};
+use validator::Validate;
+
+#[utoipa::path(
+    get,
+    path = "/api/me",
+    tag = "用户模块",
+    operation_id = "获取个人资料",
+    responses(
+        (status = 200, description = "获取成功，响应头 ETag 为当前版本", body = CommonResponse<UserInfo>),
+        (status = 304, description = "If-None-Match 与当前版本一致，资料未修改"),
+        (status = 401, description = "未登录", body = ErrorResponse),
+    ),
+)]
+// 获取当前登录用户的资料
+pub async fn get_profile(
+    req: HttpRequest,
+    db: web::Data<DatabaseConnection>,
+    current_user: CurrentUser,
+) -> SimpleResp {
+    let user = find_current_user(db.as_ref(), &current_user).await?;
+    conditional_ok(&req, UserInfo::from(user), "获取个人资料成功")
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/me",
+    request_body = UpdateProfileRequest,
+    tag = "用户模块",
+    operation_id = "修改个人资料",
+    responses(
+        (status = 200, description = "修改成功", body = CommonResponse<UserInfo>),
+        (status = 400, description = "参数错误或包含不允许修改的字段", body = ErrorResponse),
+        (status = 409, description = "用户名、邮箱或手机号已被使用", body = ErrorResponse),
+        (status = 412, description = "If-Match 与当前版本不一致，或读取后资料已在其他地方被修改", body = ErrorResponse),
+    ),
+)]
+// 修改自己的用户名、邮箱、手机号和头像，权限只能由管理员修改
+pub async fn update_profile(
+    req: HttpRequest,
+    db: web::Data<DatabaseConnection>,
+    notifier: web::Data<dyn Notifier>,
+    current_user: CurrentUser,
+    payload: web::Json<UpdateProfileRequest>,
+) -> SimpleResp {
+    if let Err(e) = payload.validate() {
+        info!("修改个人资料:{:?}", e);
+        return Err(e.into());
+    }
+    let payload = payload.into_inner();
+    let user = find_current_user(db.as_ref(), &current_user).await?;
+    check_if_match(&req, &etag_of(&UserInfo::from(user.clone())))?;
+    let old_email = user.email.clone();
+    let mut user_active: user::ActiveModel = user.clone().into();
+
+    if let Some(user_name) = payload.user_name {
+        if taken(
+            db.as_ref(),
+            user::Column::UserName,
+            &user_name,
+            &current_user.uuid,
+        )
+        .await?
+        {
+            return Err(AppError::Conflict(format!("用户名'{}'已存在", user_name)));
+        }
+        user_active.user_name = Set(user_name);
+    }
+    let email = payload.email.as_deref().map(normalize_email);
+    let email_changed = email.is_some() && email != old_email;
+    if let Some(email) = email.filter(|_| email_changed) {
+        if taken(db.as_ref(), user::Column::Email, &email, &current_user.uuid).await? {
+            return Err(AppError::Conflict("邮箱已被使用".into()));
+        }
+        // 新邮箱需要重新验证
+        user_active.email = Set(Some(email));
+        user_active.email_verified_at = Set(None);
+    }
+    if let Some(phone) = payload.phone.as_deref().map(normalize_phone) {
+        if taken(db.as_ref(), user::Column::Phone, &phone, &current_user.uuid).await? {
+            return Err(AppError::Conflict("手机号已被使用".into()));
+        }
+        user_active.phone = Set(Some(phone));
+    }
+    if let Some(image) = payload.image {
+        user_active.image = Set(Some(image));
+    }
+    user_active.updated_at = Set(Utc::now());
+    // 校验之后资料被其他请求修改过则返回 412，不覆盖对方的修改
+    let updated = update_user_if_unchanged(db.as_ref(), &user, user_active).await?;
+
+    if email_changed {
+        if let Err(e) = send_verification_email(notifier.as_ref(), &updated).await {
+            error!("发送邮箱验证邮件失败: {}", e);
+        }
+    }
+    info!("用户 {} 修改了个人资料", updated.uuid);
+    tagged_ok(UserInfo::from(updated), "修改个人资料成功")
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/me",
+    request_body = DeleteAccountRequest,
+    tag = "用户模块",
+    operation_id = "注销账户",
+    responses(
+        (status = 200, description = "账户已注销", body = CommonResponse<String>),
+        (status = 401, description = "密码错误", body = ErrorResponse),
+        (status = 412, description = "确认密码之后账户已在其他地方被修改", body = ErrorResponse),
+    ),
+)]
+// 注销自己的账户（软删除），需要确认密码
+pub async fn delete_account(
+    db: web::Data<DatabaseConnection>,
+    current_user: CurrentUser,
+    payload: web::Json<DeleteAccountRequest>,
+) -> SimpleResp {
+    if let Err(e) = payload.validate() {
+        info!("注销账户:{:?}", e);
+        return Err(e.into());
+    }
+    let user = find_current_user(db.as_ref(), &current_user).await?;
+    if !verify_password(&payload.pass_word, &user.pass_word) {
+        warn!("用户 {} 注销账户时密码错误", user.uuid);
+        return Err(AppError::Unauthorized("密码错误".into()));
+    }
+
+    let user = soft_delete_user(db.as_ref(), user).await?;
+    info!("用户 {} 注销了账户", user.uuid);
+    Resp::ok("", "账户已注销").to_json_result()
+}
+
+async fn find_current_user(
+    db: &DatabaseConnection,
+    current_user: &CurrentUser,
+) -> Result<user::Model, AppError> {
+    UserEntity::find_by_uuid(&current_user.uuid)
+        .one(db)
+        .await?
+        .ok_or_else(|| AppError::NotFound("用户不存在".into()))
+}
+
+// 检查字段值是否已被其他用户使用
+async fn taken(
+    db: &DatabaseConnection,
+    column: user::Column,
+    value: &str,
+    user_uuid: &str,
+) -> Result<bool, AppError> {
+    Ok(UserEntity::find()
+        .filter(column.eq(value))
+        .filter(user::Column::Uuid.ne(user_uuid))
+        .count(db)
+        .await?
+        > 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::permission::Permission;
+    use crate::utils::notifier::LogNotifier;
+    use actix_web::{dev::Service as _, test, App, HttpMessage};
+    use sea_orm::{DatabaseBackend, DatabaseConnection, MockDatabase, MockExecResult, Value};
+    use std::collections::BTreeMap;
+    use std::sync::Arc;
+
+    // PaginatorTrait::count 在 MySQL 下按 i32 读取 num_items
+    fn count(n: i32) -> Vec<BTreeMap<String, Value>> {
+        vec![BTreeMap::from([(
+            "num_items".to_string(),
+            Value::Int(Some(n)),
+        )])]
+    }
+
+    fn alice() -> user::Model {
+        let mut user = user::Model::fixture("u-1", "alice01");
+        user.email = Some("alice@example.com".into());
+        user.email_verified_at = Some(Utc::now());
+        user
+    }
+
+    // 以 alice 的身份调用修改资料接口，返回状态码和执行过的SQL
+    async fn put_profile(db: MockDatabase, body: serde_json::Value) -> (u16, Vec<String>) {
+        let db = web::Data::new(db.into_connection());
+        let app = test::init_service(
+            App::new()
+                .app_data(db.clone())
+                .app_data(web::Data::<dyn Notifier>::from(
+                    Arc::new(LogNotifier) as Arc<dyn Notifier>
+                ))
+                .wrap_fn(|req, srv| {
+                    req.extensions_mut().insert(CurrentUser {
+                        uuid: "u-1".into(),
+                        name: "alice01".into(),
+                        permissions: Permission::READ_ARTICLE,
+                        token_id: None,
+                    });
+                    srv.call(req)
+                })
+                .route("/api/me", web::put().to(update_profile)),
+        )
+        .await;
+        let req = test::TestRequest::put()
+            .uri("/api/me")
+            .set_json(body)
+            .to_request();
+        let status = test::call_service(&app, req).await.status().as_u16();
+        drop(app);
+        let log = Arc::try_unwrap(db.into_inner())
+            .map(DatabaseConnection::into_transaction_log)
+            .unwrap()
+            .iter()
+            .flat_map(|t| {
+                t.statements()
+                    .iter()
+                    .map(|s| s.to_string())
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+        (status, log)
+    }
+
+    #[actix_web::test]
+    async fn changing_email_requires_verification_again() {
+        let mut updated = alice();
+        updated.email = Some("new@example.com".into());
+        updated.email_verified_at = None;
+        let db = MockDatabase::new(DatabaseBackend::MySql)
+            .append_query_results([vec![alice()]])
+            .append_query_results([count(0)])
+            .append_exec_results([MockExecResult {
+                last_insert_id: 0,
+                rows_affected: 1,
+            }])
+            .append_query_results([vec![updated]]);
+
+        let (status, log) = put_profile(db, serde_json::json!({"email": "New@Example.com"})).await;
+        assert_eq!(status, 200);
+        let update = log.iter().find(|sql| sql.starts_with("UPDATE")).unwrap();
+        assert!(update.contains("`email` = 'new@example.com'"));
+        assert!(update.contains("`email_verified_at` = NULL"));
+    }
+
+    #[actix_web::test]
+    async fn unchanged_email_stays_verified() {
+        let db = MockDatabase::new(DatabaseBackend::MySql)
+            .append_query_results([vec![alice()]])
+            .append_exec_results([MockExecResult {
+                last_insert_id: 0,
+                rows_affected: 1,
+            }])
+            .append_query_results([vec![alice()]]);
+
+        let (status, log) =
+            put_profile(db, serde_json::json!({"email": "ALICE@example.com"})).await;
+        assert_eq!(status, 200);
+        let update = log.iter().find(|sql| sql.starts_with("UPDATE")).unwrap();
+        assert!(!update.contains("`email_verified_at` = NULL"));
+    }
+
+    #[actix_web::test]
+    async fn taken_name_email_or_phone_conflicts() {
+        for body in [
+            serde_json::json!({"user_name": "bob_smith"}),
+            serde_json::json!({"email": "bob@example.com"}),
+            serde_json::json!({"phone": "+86 138-0013-8000"}),
+        ] {
+            let db = MockDatabase::new(DatabaseBackend::MySql)
+                .append_query_results([vec![alice()]])
+                .append_query_results([count(1)]);
+            let (status, log) = put_profile(db, body.clone()).await;
+            assert_eq!(status, 409, "{}", body);
+            // 冲突时不会写入
+            assert!(!log.iter().any(|sql| sql.starts_with("UPDATE")));
+            assert!(log[1].contains("`uuid` <> 'u-1'"));
+        }
+    }
+
+    #[actix_web::test]
+    async fn concurrent_change_fails_precondition() {
+        // 写入时行的更新时间已经变化，条件更新没有命中任何行
+        let db = MockDatabase::new(DatabaseBackend::MySql)
+            .append_query_results([vec![alice()]])
+            .append_exec_results([MockExecResult {
+                last_insert_id: 0,
+                rows_affected: 0,
+            }]);
+
+        let (status, log) = put_profile(db, serde_json::json!({"image": "/avatar.png"})).await;
+        assert_eq!(status, 412);
+        assert_eq!(log.len(), 2);
+        assert!(log[1].starts_with("UPDATE `users` SET"));
+        assert!(log[1].contains("WHERE `users`.`id` = 1 AND `users`.`updated_at` = '"));
+    }
+}
@@ -0,0 +1,405 @@
+use crate::common::CommonResponse;
+use crate::dto::role::{AssignRolesRequest, CreateRoleRequest, UpdateRoleRequest};
+use crate::error::error::{AppError, ErrorResponse};
+use crate::middleware::helpers::{Resp, SimpleResp};
+use crate::models::roles::{self, Entity as RoleEntity};
+use crate::models::user;
+use crate::models::user_roles::{self, Entity as UserRoleEntity};
+use crate::permission::{Permission, BUILTIN_ROLES, DEFAULT_ROLE};
+use crate::services::user::find_user;
+use actix_web::web;
+use chrono::Utc;
+use log::info;
+use sea_orm::{
+    ActiveModelTrait, ActiveValue::Set, ColumnTrait, ConnectionTrait, DatabaseConnection,
+    EntityTrait, PaginatorTrait, QueryFilter, QueryOrder, TransactionTrait,
+};
+use serde::Serialize;
+use utoipa::ToSchema;
+use validator::Validate;
+
+#[derive(Serialize, ToSchema)]
+pub struct RoleInfo {
+    pub id: i32,
+    pub name: String,
+    pub description: Option<String>,
+    pub permissions: Vec<String>,
+    pub is_builtin: bool,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+impl From<roles::Model> for RoleInfo {
+    fn from(model: roles::Model) -> Self {
+        RoleInfo {
+            id: model.id,
+            name: model.name,
+            description: model.description,
+            permissions: Permission::from_stored(Some(&model.permissions)).names(),
+            is_builtin: model.is_builtin != 0,
+            created_at: model.created_at.to_string(),
+            updated_at: model.updated_at.to_string(),
+        }
+    }
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct UserRolesData {
+    pub roles: Vec<String>,
+    // 角色与额外授予权限合并、再去掉禁止权限后的结果
+    pub effective_permissions: Vec<String>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/roles",
+    tag = "角色模块",
+    operation_id = "获取角色列表",
+    responses(
+        (status = 200, description = "获取成功", body = CommonResponse<Vec<RoleInfo>>),
+        (status = 403, description = "权限不足", body = ErrorResponse),
+    ),
+)]
+pub async fn get_roles(db: web::Data<DatabaseConnection>) -> SimpleResp {
+    let roles = RoleEntity::find()
+        .order_by_asc(roles::Column::Id)
+        .all(db.as_ref())
+        .await?
+        .into_iter()
+        .map(RoleInfo::from)
+        .collect::<Vec<_>>();
+    Resp::ok(roles, "获取角色列表成功").to_json_result()
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/roles",
+    request_body = CreateRoleRequest,
+    tag = "角色模块",
+    operation_id = "创建角色",
+    responses(
+        (status = 200, description = "创建成功", body = CommonResponse<RoleInfo>),
+        (status = 400, description = "权限名称无效", body = ErrorResponse),
+        (status = 409, description = "角色名称已存在", body = ErrorResponse),
+    ),
+)]
+pub async fn create_role(
+    db: web::Data<DatabaseConnection>,
+    payload: web::Json<CreateRoleRequest>,
+) -> SimpleResp {
+    if let Err(e) = payload.validate() {
+        info!("创建角色:{:?}", e);
+        return Err(e.into());
+    }
+    let payload = payload.into_inner();
+    let permissions = parse_permission_names(&payload.permissions)?;
+
+    let exists = RoleEntity::find()
+        .filter(roles::Column::Name.eq(&payload.name))
+        .count(db.as_ref())
+        .await?
+        > 0;
+    if exists {
+        return Err(AppError::Conflict(format!("角色'{}'已存在", payload.name)));
+    }
+
+    let role = roles::ActiveModel {
+        name: Set(payload.name),
+        description: Set(payload.description),
+        permissions: Set(permissions.bits().to_string()),
+        is_builtin: Set(0),
+        created_at: Set(Utc::now()),
+        updated_at: Set(Utc::now()),
+        ..Default::default()
+    }
+    .insert(db.as_ref())
+    .await?;
+
+    info!("创建了角色 {}", role.name);
+    Resp::ok(RoleInfo::from(role), "创建角色成功").to_json_result()
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/roles/{id}",
+    request_body = UpdateRoleRequest,
+    tag = "角色模块",
+    operation_id = "更新角色",
+    params(
+        ("id" = i32, Path, description = "角色ID")
+    ),
+    responses(
+        (status = 200, description = "更新成功", body = CommonResponse<RoleInfo>),
+        (status = 400, description = "权限名称无效", body = ErrorResponse),
+        (status = 404, description = "角色不存在", body = ErrorResponse),
+    ),
+)]
+// 修改角色的说明和权限，每次请求都按当前角色计算权限，已签发的令牌立即生效
+pub async fn update_role(
+    db: web::Data<DatabaseConnection>,
+    id: web::Path<i32>,
+    payload: web::Json<UpdateRoleRequest>,
+) -> SimpleResp {
+    if let Err(e) = payload.validate() {
+        info!("更新角色:{:?}", e);
+        return Err(e.into());
+    }
+    let payload = payload.into_inner();
+    let role = find_role(db.as_ref(), *id).await?;
+
+    let mut role_active: roles::ActiveModel = role.into();
+    if let Some(description) = payload.description {
+        role_active.description = Set(Some(description));
+    }
+    if let Some(names) = &payload.permissions {
+        role_active.permissions = Set(parse_permission_names(names)?.bits().to_string());
+    }
+    role_active.updated_at = Set(Utc::now());
+    let role = role_active.update(db.as_ref()).await?;
+
+    info!("更新了角色 {}", role.name);
+    Resp::ok(RoleInfo::from(role), "更新角色成功").to_json_result()
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/roles/{id}",
+    tag = "角色模块",
+    operation_id = "删除角色",
+    params(
+        ("id" = i32, Path, description = "角色ID")
+    ),
+    responses(
+        (status = 200, description = "删除成功", body = CommonResponse<String>),
+        (status = 403, description = "内置角色不能删除", body = ErrorResponse),
+        (status = 404, description = "角色不存在", body = ErrorResponse),
+    ),
+)]
+// 删除自定义角色，同时移除所有用户的该角色
+pub async fn delete_role(db: web::Data<DatabaseConnection>, id: web::Path<i32>) -> SimpleResp {
+    let role = find_role(db.as_ref(), *id).await?;
+    if role.is_builtin != 0 {
+        return Err(AppError::Forbidden(format!(
+            "内置角色'{}'不能删除",
+            role.name
+        )));
+    }
+
+    let txn = db.begin().await?;
+    UserRoleEntity::delete_many()
+        .filter(user_roles::Column::RoleId.eq(role.id))
+        .exec(&txn)
+        .await?;
+    RoleEntity::delete_by_id(role.id).exec(&txn).await?;
+    txn.commit().await?;
+
+    info!("删除了角色 {}", role.name);
+    Resp::ok("", &format!("角色 {} 已删除", role.name)).to_json_result()
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/users/{uuid}/roles",
+    tag = "角色模块",
+    operation_id = "获取用户角色",
+    params(
+        ("uuid" = String, Path, description = "用户的 UUID")
+    ),
+    responses(
+        (status = 200, description = "获取成功", body = CommonResponse<UserRolesData>),
+        (status = 404, description = "用户不存在", body = ErrorResponse),
+    ),
+)]
+pub async fn get_user_roles(
+    db: web::Data<DatabaseConnection>,
+    uuid: web::Path<String>,
+) -> SimpleResp {
+    let user = find_user(db.as_ref(), &uuid).await?;
+    let data = user_roles_data(db.as_ref(), &user).await?;
+    Resp::ok(data, "获取用户角色成功").to_json_result()
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/users/{uuid}/roles",
+    request_body = AssignRolesRequest,
+    tag = "角色模块",
+    operation_id = "设置用户角色",
+    params(
+        ("uuid" = String, Path, description = "用户的 UUID")
+    ),
+    responses(
+        (status = 200, description = "设置成功", body = CommonResponse<UserRolesData>),
+        (status = 400, description = "角色不存在", body = ErrorResponse),
+        (status = 404, description = "用户不存在", body = ErrorResponse),
+    ),
+)]
+// 用请求中的角色列表替换用户现有的角色
+pub async fn assign_user_roles(
+    db: web::Data<DatabaseConnection>,
+    uuid: web::Path<String>,
+    payload: web::Json<AssignRolesRequest>,
+) -> SimpleResp {
+    let user = find_user(db.as_ref(), &uuid).await?;
+    let payload = payload.into_inner();
+
+    let roles = RoleEntity::find()
+        .filter(roles::Column::Name.is_in(payload.roles.clone()))
+        .all(db.as_ref())
+        .await?;
+    if let Some(unknown) = payload
+        .roles
+        .iter()
+        .find(|name| !roles.iter().any(|role| &role.name == *name))
+    {
+        return Err(AppError::BadRequest(format!("角色不存在: {}", unknown)));
+    }
+
+    let txn = db.begin().await?;
+    UserRoleEntity::delete_many()
+        .filter(user_roles::Column::UserUuid.eq(&user.uuid))
+        .exec(&txn)
+        .await?;
+    for role in &roles {
+        insert_user_role(&txn, &user.uuid, role.id).await?;
+    }
+    txn.commit().await?;
+
+    info!("用户 {} 的角色已设置为 {:?}", user.uuid, payload.roles);
+    let data = user_roles_data(db.as_ref(), &user).await?;
+    Resp::ok(data, "设置用户角色成功").to_json_result()
+}
+
+// 启动时补齐缺失的内置角色，已存在的角色保持管理员修改后的权限
+pub async fn seed_builtin_roles(db: &DatabaseConnection) -> Result<(), AppError> {
+    for (name, description, permissions) in BUILTIN_ROLES {
+        let exists = RoleEntity::find()
+            .filter(roles::Column::Name.eq(*name))
+            .count(db)
+            .await?
+            > 0;
+        if exists {
+            continue;
+        }
+        roles::ActiveModel {
+            name: Set(name.to_string()),
+            description: Set(Some(description.to_string())),
+            permissions: Set(permissions.bits().to_string()),
+            is_builtin: Set(1),
+            created_at: Set(Utc::now()),
+            updated_at: Set(Utc::now()),
+            ..Default::default()
+        }
+        .insert(db)
+        .await?;
+        info!("已创建内置角色 {}", name);
+    }
+    Ok(())
+}
+
+// 给新用户分配默认角色
+pub async fn assign_default_role<C: ConnectionTrait>(
+    db: &C,
+    user_uuid: &str,
+) -> Result<(), AppError> {
+    let role = RoleEntity::find()
+        .filter(roles::Column::Name.eq(DEFAULT_ROLE))
+        .one(db)
+        .await?
+        .ok_or_else(|| {
+            AppError::InternalServerError(format!("默认角色'{}'不存在", DEFAULT_ROLE))
+        })?;
+    insert_user_role(db, user_uuid, role.id).await
+}
+
+// 查询用户拥有的角色
+pub async fn user_roles<C: ConnectionTrait>(
+    db: &C,
+    user_uuid: &str,
+) -> Result<Vec<roles::Model>, AppError> {
+    let role_ids = UserRoleEntity::find()
+        .filter(user_roles::Column::UserUuid.eq(user_uuid))
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|user_role| user_role.role_id)
+        .collect::<Vec<_>>();
+    if role_ids.is_empty() {
+        return Ok(Vec::new());
+    }
+    Ok(RoleEntity::find()
+        .filter(roles::Column::Id.is_in(role_ids))
+        .order_by_asc(roles::Column::Id)
+        .all(db)
+        .await?)
+}
+
+// 有效权限 = (所有角色权限 ∪ 额外授予的权限) - 禁止的权限
+pub async fn effective_permissions<C: ConnectionTrait>(
+    db: &C,
+    user: &user::Model,
+) -> Result<Permission, AppError> {
+    let roles = user_roles(db, &user.uuid).await?;
+    Ok(combine_permissions(
+        roles
+            .iter()
+            .map(|role| Permission::from_stored(Some(&role.permissions))),
+        user,
+    ))
+}
+
+fn combine_permissions(
+    role_permissions: impl Iterator<Item = Permission>,
+    user: &user::Model,
+) -> Permission {
+    let granted = role_permissions.fold(
+        Permission::from_stored(user.permissions.as_deref()),
+        |acc, permissions| acc | permissions,
+    );
+    granted - Permission::from_stored(user.denied_permissions.as_deref())
+}
+
+async fn user_roles_data<C: ConnectionTrait>(
+    db: &C,
+    user: &user::Model,
+) -> Result<UserRolesData, AppError> {
+    let roles = user_roles(db, &user.uuid).await?;
+    let effective = combine_permissions(
+        roles
+            .iter()
+            .map(|role| Permission::from_stored(Some(&role.permissions))),
+        user,
+    );
+    Ok(UserRolesData {
+        roles: roles.into_iter().map(|role| role.name).collect(),
+        effective_permissions: effective.names(),
+    })
+}
+
+pub(crate) async fn insert_user_role<C: ConnectionTrait>(
+    db: &C,
+    user_uuid: &str,
+    role_id: i32,
+) -> Result<(), AppError> {
+    user_roles::ActiveModel {
+        user_uuid: Set(user_uuid.to_string()),
+        role_id: Set(role_id),
+        created_at: Set(Utc::now()),
+        ..Default::default()
+    }
+    .insert(db)
+    .await?;
+    Ok(())
+}
+
+fn parse_permission_names(names: &[String]) -> Result<Permission, AppError> {
+    Permission::from_names(names)
+        .map_err(|name| AppError::BadRequest(format!("无效权限: {}", name)))
+}
+
+async fn find_role(db: &DatabaseConnection, id: i32) -> Result<roles::Model, AppError> {
+    RoleEntity::find_by_id(id)
+        .one(db)
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("ID为{}的角色不存在", id)))
+}
@@ -1,63 +1,238 @@
+use super::api_keys;
 use super::articles;
 use super::auth;
 use super::authenticator;
 use super::categories;
+use super::email;
+use super::oauth;
+use super::password;
+use super::permissions;
+use super::profile;
+use super::roles;
 use super::sse;
 use super::tags;
 use super::user;
 use crate::config::permission::Permission;
-use crate::utils::permission_guard::PermissionGuard;
-use actix_web::web;
+use crate::utils::permission_guard::{PermissionGuard, PermissionRule};
+use actix_web::http::Method;
+use actix_web::{web, Route};
+use std::collections::HashSet;
+
+// 路由的访问控制标记，每个路由都必须声明：公开、仅限登录令牌的账户接口，或者具体的权限规则。
+// 不提供“登录即可”的标记，避免路由绕过权限声明
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Access {
+    // 无需登录
+    Public,
+    // 账户管理类接口，只接受登录令牌，API密钥泄露时不能借此修改邮箱、密码或双重验证
+    SessionOnly,
+    // 登录并且满足权限规则
+    Require(PermissionRule),
+}
+
+impl Access {
+    pub fn all(permission: Permission) -> Self {
+        Access::Require(PermissionRule::All(permission))
+    }
+
+    pub fn any(permission: Permission) -> Self {
+        Access::Require(PermissionRule::Any(permission))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RouteSpec {
+    pub method: Method,
+    pub path: &'static str,
+    pub access: Access,
+}
+
+// 路由表：同时生成 route_table() 和路由注册函数，认证和授权都由这一张表推导
+macro_rules! route_table {
+    ($($method:ident $path:literal => $handler:path, $access:expr;)*) => {
+        pub fn route_table() -> Vec<RouteSpec> {
+            vec![$(RouteSpec {
+                method: route_table!(@method $method),
+                path: $path,
+                access: $access,
+            }),*]
+        }
+
+        fn register_routes(cfg: &mut web::ServiceConfig) {
+            $(cfg.route($path, with_access(web::$method(), &$access).to($handler));)*
+        }
+    };
+    (@method get) => { Method::GET };
+    (@method post) => { Method::POST };
+    (@method put) => { Method::PUT };
+    (@method patch) => { Method::PATCH };
+    (@method delete) => { Method::DELETE };
+}
+
+// 同一路径的路由按声明顺序匹配，带 {uuid:.*} 的通配路由要放在具体路径之后
+route_table! {
+    get "/api/sse/stream" => sse::sse_stream, Access::Public;
+
+    get "/api/me" => profile::get_profile, Access::SessionOnly;
+    put "/api/me" => profile::update_profile, Access::SessionOnly;
+    delete "/api/me" => profile::delete_account, Access::SessionOnly;
+
+    get "/api/users" => user::get_all_users, Access::all(Permission::READ_USER);
+    post "/api/users/batch" => user::batch_users, Access::all(Permission::WRITE_USER);
+    post "/api/users/{uuid}/unlock" => user::unlock_user, Access::all(Permission::WRITE_USER);
+    post "/api/users/{uuid}/disable" => user::disable_user, Access::all(Permission::WRITE_USER);
+    post "/api/users/{uuid}/restore" => user::restore_user, Access::all(Permission::WRITE_USER);
+    get "/api/users/{uuid}/permissions" => permissions::get_user_permissions, Access::all(Permission::READ_USER);
+    post "/api/users/{uuid}/permissions/grant" => permissions::grant_user_permissions, Access::all(Permission::WRITE_SYSTEM);
+    post "/api/users/{uuid}/permissions/revoke" => permissions::revoke_user_permissions, Access::all(Permission::WRITE_SYSTEM);
+    get "/api/users/{uuid}/roles" => roles::get_user_roles, Access::all(Permission::READ_USER);
+    put "/api/users/{uuid}/roles" => roles::assign_user_roles, Access::all(Permission::WRITE_SYSTEM);
+    put "/api/users/{uuid:.*}" => user::update_user, Access::all(Permission::WRITE_USER);
+    get "/api/users/{uuid:.*}" => user::get_user_by_uuid, Access::all(Permission::READ_USER);
+    delete "/api/users/{uuid:.*}" => user::delete_user, Access::all(Permission::WRITE_USER);
+
+    post "/api/auth/login" => auth::login, Access::Public;
+    post "/api/auth/login/2fa" => auth::login_2fa, Access::Public;
+    post "/api/auth/register" => auth::register, Access::Public;
+    post "/api/auth/email/verify/send" => email::send_email_verification, Access::SessionOnly;
+    get "/api/auth/email/verify" => email::verify_email, Access::Public;
+    get "/api/auth/oauth/{provider}/authorize" => oauth::oauth_authorize, Access::Public;
+    get "/api/auth/oauth/{provider}/callback" => oauth::oauth_callback, Access::Public;
+    post "/api/auth/password/change" => password::change_password, Access::SessionOnly;
+    post "/api/auth/password/reset" => password::request_password_reset, Access::Public;
+    post "/api/auth/password/reset/confirm" => password::confirm_password_reset, Access::Public;
+    get "/api/auth/permissions" => auth::get_permissions, Access::Public;
+    get "/api/auth/permissions/routes" => permissions::get_permission_routes, Access::all(Permission::READ_SYSTEM);
+    get "/api/auth/permission" => auth::get_permissions_by_id, Access::all(Permission::READ_SYSTEM);
+
+    get "/api/articles" => articles::get_article, Access::all(Permission::READ_ARTICLE);
+    post "/api/articles" => articles::create_article, Access::all(Permission::WRITE_ARTICLE);
+    post "/api/articles/batch" => articles::batch_articles, Access::all(Permission::WRITE_ARTICLE);
+    get "/api/articles/{uuid}" => articles::get_article_by_uuid, Access::all(Permission::READ_ARTICLE);
+    put "/api/articles/{uuid}" => articles::update_article, Access::all(Permission::WRITE_ARTICLE);
+    delete "/api/articles/{uuid}" => articles::delete_article, Access::all(Permission::WRITE_ARTICLE);
+
+    post "/api/2fa/verify" => authenticator::verify_2fa, Access::SessionOnly;
+    post "/api/2fa/disable" => authenticator::disable_2fa, Access::SessionOnly;
+    post "/api/2fa/recovery-codes" => authenticator::regenerate_recovery_codes, Access::SessionOnly;
+    get "/api/2fa/recovery-codes" => authenticator::recovery_codes_status, Access::SessionOnly;
+    get "/api/2fa/generate" => authenticator::generate_2fa_secret, Access::SessionOnly;
+
+    get "/api/roles" => roles::get_roles, Access::all(Permission::READ_SYSTEM);
+    post "/api/roles" => roles::create_role, Access::all(Permission::WRITE_SYSTEM);
+    put "/api/roles/{id}" => roles::update_role, Access::all(Permission::WRITE_SYSTEM);
+    delete "/api/roles/{id}" => roles::delete_role, Access::all(Permission::WRITE_SYSTEM);
+
+    post "/api/api-keys" => api_keys::create_api_key, Access::SessionOnly;
+    get "/api/api-keys" => api_keys::list_api_keys, Access::SessionOnly;
+    delete "/api/api-keys/{id}" => api_keys::delete_api_key, Access::SessionOnly;
+
+    post "/api/categories" => categories::create_category, Access::all(Permission::WRITE_CATEGORY);
+    get "/api/categories" => categories::get_all_categories, Access::all(Permission::READ_CATEGORY);
+    delete "/api/categories/{id:.*}" => categories::delete_category, Access::all(Permission::WRITE_CATEGORY);
+
+    post "/api/tags" => tags::create_tag, Access::all(Permission::WRITE_TAG);
+    get "/api/tags" => tags::get_all_tags, Access::all(Permission::READ_TAG);
+    post "/api/tags/batch" => tags::batch_tags, Access::all(Permission::WRITE_TAG);
+}
+
+fn with_access(route: Route, access: &Access) -> Route {
+    match access {
+        Access::Require(rule) => route.guard(PermissionGuard::from(rule.clone())),
+        Access::Public | Access::SessionOnly => route,
+    }
+}
 
 pub fn config_routes(cfg: &mut web::ServiceConfig) {
-    cfg.service(
-        web::scope("/api")
-            .service(web::scope("/sse").route("/stream", web::get().to(sse::sse_stream)))
-            .service(
-                web::scope("/users")
-                    .route("", web::get().to(user::get_all_users))
-                    .route("/{uuid:.*}", web::put().to(user::update_user))
-                    .route("/{uuid:.*}", web::get().to(user::get_user_by_uuid))
-                    .route("/{uuid:.*}", web::delete().to(user::delete_user)),
-            )
-            .service(
-                web::scope("/auth")
-                    .route("/login", web::post().to(auth::login))
-                    .route("/register", web::post().to(auth::register))
-                    .route("/permissions", web::get().to(auth::get_permissions))
-                    .route("/permission", web::get().to(auth::get_permissions_by_id)),
-            )
-            .service(
-                web::resource("/articles")
-                    .route(
-                        web::get()
-                            .guard(PermissionGuard::new(Permission::READ_ARTICLE))
-                            .to(articles::get_article),
-                    )
-                    .route(
-                        web::post()
-                            .guard(PermissionGuard::new(Permission::WRITE_ARTICLE))
-                            .to(articles::create_article),
-                    ),
-            )
-            .service(
-                web::scope("/2fa")
-                    .route("/verify", web::post().to(authenticator::verify_2fa))
-                    .route(
-                        "/generate",
-                        web::get().to(authenticator::generate_2fa_secret),
-                    ),
-            )
-            .service(
-                web::scope("/categories")
-                    .route("", web::post().to(categories::create_category))
-                    .route("", web::get().to(categories::get_all_categories))
-                    .route("/{id:.*}", web::delete().to(categories::delete_category)),
-            )
-            .service(
-                web::scope("/tags")
-                    .route("", web::post().to(tags::create_tag))
-                    .route("", web::get().to(tags::get_all_tags)),
-            ),
-    );
+    register_routes(cfg);
+}
+
+// 启动时校验路由表：权限规则不能为空，同一方法和路径不能重复声明
+pub fn check_route_table(routes: &[RouteSpec]) -> Result<(), String> {
+    let mut seen = HashSet::new();
+    for route in routes {
+        if !seen.insert((route.method.clone(), route.path)) {
+            return Err(format!("路由 {} {} 重复声明", route.method, route.path));
+        }
+        if let Access::Require(rule) = &route.access {
+            if !rule_has_requirement(rule) {
+                return Err(format!(
+                    "路由 {} {} 未声明所需权限，公开接口请使用 Access::Public",
+                    route.method, route.path
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+fn rule_has_requirement(rule: &PermissionRule) -> bool {
+    match rule {
+        PermissionRule::All(p) | PermissionRule::Any(p) => !p.is_empty(),
+        PermissionRule::And(l, r) | PermissionRule::Or(l, r) => {
+            rule_has_requirement(l) && rule_has_requirement(r)
+        }
+        PermissionRule::Not(inner) => rule_has_requirement(inner),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn route_table_passes_startup_check() {
+        assert_eq!(check_route_table(&route_table()), Ok(()));
+    }
+
+    #[test]
+    fn account_management_routes_reject_api_keys() {
+        let session_only: Vec<_> = route_table()
+            .into_iter()
+            .filter(|route| route.access == Access::SessionOnly)
+            .map(|route| route.path)
+            .collect();
+        for path in [
+            "/api/me",
+            "/api/auth/password/change",
+            "/api/2fa/generate",
+            "/api/2fa/verify",
+            "/api/2fa/disable",
+            "/api/api-keys",
+        ] {
+            assert!(session_only.contains(&path), "{} 应只接受登录令牌", path);
+        }
+    }
+
+    #[test]
+    fn account_changes_by_uuid_require_user_management() {
+        // 用户修改或注销自己的账户走 /api/me，按 UUID 操作只对管理员开放
+        for method in [Method::PUT, Method::DELETE] {
+            let route = route_table()
+                .into_iter()
+                .find(|route| route.method == method && route.path == "/api/users/{uuid:.*}")
+                .unwrap();
+            assert_eq!(route.access, Access::all(Permission::WRITE_USER));
+        }
+    }
+
+    #[test]
+    fn empty_permission_is_treated_as_missing_marker() {
+        let routes = vec![RouteSpec {
+            method: Method::GET,
+            path: "/api/example",
+            access: Access::all(Permission::empty()),
+        }];
+        assert!(check_route_table(&routes).is_err());
+    }
+
+    #[test]
+    fn duplicate_routes_are_rejected() {
+        let route = RouteSpec {
+            method: Method::GET,
+            path: "/api/example",
+            access: Access::Public,
+        };
+        assert!(check_route_table(&[route.clone(), route]).is_err());
+    }
 }
@@ -18,7 +18,7 @@ pub async fn sse_stream(notifier: web::Data<SseNotifier>) -> impl Responder {
             }
         })
         // 将错误转换为 Infallible
-        .map(|event| Ok::<Event, Infallible>(event));
+        .map(Ok::<Event, Infallible>);
 
     // 创建SSE响应
     Sse::from_stream(sse_stream).with_keep_alive(Duration::from_secs(5))
@@ -1,22 +1,21 @@
 use crate::common::{
-    CommonResponse, PaginatedResponse, PaginationInfo, PaginationQuery, TagsQuery,
-    DEFAULT_PAGE_SIZE, MAX_PAGE_SIZE,
+    dedup_ids, BatchResult, CommonResponse, ListPage, PaginationQuery, Paginator, TagsQuery,
 };
-use crate::data_processing::deep_filter_data;
+use crate::dto::tag::{TagBatchAction, TagBatchRequest, TagInfo};
+use crate::error::error::ErrorResponse;
 use crate::middleware::helpers::{Resp, SimpleResp};
+use crate::models::article_tags::{self, Entity as ArticleTagEntity};
 use crate::models::sea_orm_active_enums::Type;
 use crate::models::tags::{self, Entity as TagsEntity};
 use crate::serde::deserialize_enum;
-use crate::services::categories::SimpleRespData;
-use crate::services::user::UserInfo;
 use crate::utils::query_parameter::Query;
 use crate::AppError;
-use actix_web::{web, Responder};
+use actix_web::web;
 use chrono::Utc;
-use log::*;
+use log::info;
 use sea_orm::{
-    ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, PaginatorTrait, QueryFilter,
-    QueryOrder, QuerySelect, Set,
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, ModelTrait, PaginatorTrait,
+    QueryFilter, QuerySelect, Set, TransactionTrait,
 };
 use serde::{Deserialize, Serialize};
 use utoipa::ToSchema;
@@ -30,25 +29,27 @@ pub struct CreateTagRequest {
 
 #[utoipa::path(
     post,
-    path = "/tags",
+    path = "/api/tags",
     request_body = CreateTagRequest,
+    tag = "标签",
+    operation_id = "创建标签",
     responses(
-        (status = 201, description = "Tag created successfully", body = SimpleRespData),
-        (status = 400, description = "Invalid input"),
-        (status = 500, description = "Internal server error")
+        (status = 200, description = "创建标签成功", body = CommonResponse<TagInfo>),
+        (status = 409, description = "标签名称已存在", body = ErrorResponse),
+        (status = 500, description = "创建标签失败", body = ErrorResponse),
     )
 )]
 pub async fn create_tag(
     db: web::Data<DatabaseConnection>,
     payload: web::Json<CreateTagRequest>,
-) -> impl Responder {
+) -> SimpleResp {
     log::info!("create_category payload: {:?}", payload);
     let category = TagsEntity::find()
         .filter(tags::Column::Name.eq(payload.name.clone()))
         .one(db.get_ref())
         .await;
     if let Ok(Some(_)) = category {
-        return Resp::ok("", "标签名称已存在").to_json_result();
+        return Err(AppError::Conflict("标签名称已存在".into()));
     }
     let tags = tags::ActiveModel {
         name: Set(payload.name.clone()),
@@ -59,10 +60,10 @@ pub async fn create_tag(
     };
 
     match tags.insert(db.get_ref()).await {
-        Ok(data) => Resp::ok(data, "创建标签成功").to_json_result(),
+        Ok(data) => Resp::ok(TagInfo::from(data), "创建标签成功").to_json_result(),
         Err(e) => {
-            log::error!("create_category error: {}", e);
-            Resp::ok("", "创建标签失败").to_json_result()
+            log::error!("create_tag error: {}", e);
+            Err(AppError::InternalServerError("创建标签失败".into()))
         }
     }
 }
@@ -74,68 +75,81 @@ pub async fn create_tag(
     operation_id = "获取标签列表",
     request_body = PaginationQuery,
     responses(
-        (status = 200, description = "获取标签列表成功", body = CommonResponse<UserInfo>),
-        (status = 500, description = "获取标签列表失败", body = SimpleRespData),
+        (status = 200, description = "获取标签列表成功，传入 cursor 时为游标分页", body = CommonResponse<ListPage<TagInfo>>),
+        (status = 400, description = "分页参数错误", body = ErrorResponse),
+        (status = 500, description = "获取标签列表失败", body = ErrorResponse),
     ),
 )]
 pub async fn get_all_tags(
     db: web::Data<DatabaseConnection>,
     query: Query<TagsQuery>,
 ) -> SimpleResp {
-    // 验证分页参数
-    let validated_query = match query.validate() {
-        Ok(_) => query.into_inner(),
-        Err(e) => {
-            error!("分页参数验证失败: {:?}", e);
-
-            return Resp::err(AppError::DeserializeError("分页参数验证失败".to_string()))
-                .to_json_result();
-        }
-    };
-    info!("validated_query: {:?}", validated_query);
-    let page = validated_query.page.unwrap_or(1);
-    let limit = validated_query.limit.unwrap_or(DEFAULT_PAGE_SIZE);
+    let page = Paginator::new(TagsEntity::find())
+        .fetch(db.as_ref(), &query.into_inner())
+        .await?
+        .map(TagInfo::from);
+    Resp::ok(page, "获取标签列表成功").to_json_result()
+}
 
-    // 限制每页数量的范围
-    let limit = if limit == 0 {
-        DEFAULT_PAGE_SIZE
-    } else if limit > MAX_PAGE_SIZE {
-        MAX_PAGE_SIZE
-    } else {
-        limit
-    };
-    let offset = (page - 1) * limit;
+#[utoipa::path(
+    post,
+    path = "/api/tags/batch",
+    request_body = TagBatchRequest,
+    tag = "标签",
+    operation_id = "批量操作标签",
+    responses(
+        (status = 200, description = "批量操作完成，返回每个标签的处理结果", body = CommonResponse<BatchResult>),
+        (status = 400, description = "参数错误", body = ErrorResponse),
+        (status = 500, description = "数据库错误，整批修改回滚，没有任何标签被删除", body = ErrorResponse),
+    ),
+    description = "整批提交：不存在或仍被使用的标签在结果中标记为失败并跳过，数据库错误会回滚整批修改",
+)]
+// 批量删除标签，仍被文章使用的标签不会删除。
+// 所有修改在同一个事务中提交，数据库出错时整批回滚并返回错误
+pub async fn batch_tags(
+    db: web::Data<DatabaseConnection>,
+    payload: web::Json<TagBatchRequest>,
+) -> SimpleResp {
+    if let Err(e) = payload.validate() {
+        info!("批量操作标签:{:?}", e);
+        return Err(e.into());
+    }
+    let payload = payload.into_inner();
+    let ids = dedup_ids(&payload.ids);
+    let txn = db.begin().await?;
+    // 在事务内加锁读取，避免并发的文章关联在检查和删除之间发生变化
+    let tags = TagsEntity::find()
+        .filter(tags::Column::Id.is_in(ids.clone()))
+        .lock_exclusive()
+        .all(&txn)
+        .await?;
 
-    // 获取总数和分页数据
-    let (total, tags) = match tokio::try_join!(
-        TagsEntity::find().count(db.as_ref()),
-        TagsEntity::find()
-            .order_by_desc(tags::Column::Id)
-            .offset(Some(offset))
-            .limit(Some(limit))
-            .all(db.as_ref())
-    ) {
-        Ok((total, tags)) => (total, tags),
-        Err(e) => {
-            error!("数据库操作失败: {}", e);
-            return Resp::err(AppError::InternalServerError("数据库操作失败".to_string()))
-                .to_json_result();
+    let mut result = BatchResult::default();
+    for id in ids {
+        let Some(tag) = tags.iter().find(|t| t.id == id) else {
+            result.fail(id, "标签不存在");
+            continue;
+        };
+        match payload.action {
+            TagBatchAction::Delete => {
+                let used = ArticleTagEntity::find()
+                    .filter(article_tags::Column::TagsId.eq(id))
+                    .count(&txn)
+                    .await?;
+                if used > 0 {
+                    result.fail(id, format!("标签仍被 {} 篇文章使用", used));
+                    continue;
+                }
+                tag.clone().delete(&txn).await?;
+            }
         }
-    };
-    let total_pages = (total + limit - 1) / limit; // 整数除法避免浮点误差
-    let data = deep_filter_data(tags, vec!["id"]);
-
-    let response = PaginatedResponse {
-        data,
-        pagination: PaginationInfo {
-            total,
-            total_pages,
-            current_page: page,
-            limit,
-            has_next: page < total_pages,
-            has_previous: page > 1,
-        },
-    };
+        result.ok(id);
+    }
+    txn.commit().await?;
 
-    Resp::ok(response, "获取标签列表成功").to_json_result()
+    info!(
+        "批量操作标签 {:?}: 成功 {} 个，失败 {} 个",
+        payload.action, result.succeeded, result.failed
+    );
+    Resp::ok(result, "批量操作标签完成").to_json_result()
 }
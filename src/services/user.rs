@@ -1,123 +1,187 @@
 use crate::common::{
-    CommonResponse, PaginatedResponse, PaginationInfo, PaginationQuery, DEFAULT_PAGE_SIZE,
-    MAX_PAGE_SIZE,
+    dedup_ids, BatchResult, CommonResponse, ListPage, Paginator, UserListQuery, UserSortField,
 };
+use crate::config::account::ACCOUNT_CONFIG;
 use crate::config::permission::{Permission, PERMISSION_MAP};
-use crate::data_processing::{deep_filter_data, filter_value};
-use crate::dto::user::{UpdateUserRequest, UserDto};
-use crate::error::error::AppError;
+use crate::dto::user::{UpdateUserRequest, UserBatchAction, UserBatchRequest, UserInfo};
+use crate::error::error::{AppError, ErrorResponse};
 use crate::middleware::helpers::{Resp, SimpleResp};
+use crate::models::api_keys::{self, Entity as ApiKeyEntity};
+use crate::models::roles::{self, Entity as RoleEntity};
+use crate::models::sea_orm_active_enums::UserStatus;
 use crate::models::user::{self, Entity as UserEntity};
+use crate::models::user_identities::{self, Entity as UserIdentityEntity};
+use crate::models::user_roles::{self, Entity as UserRoleEntity};
+use crate::services::roles::insert_user_role;
+use crate::utils::current_user::CurrentUser;
+use crate::utils::etag::{check_if_match, conditional_ok, etag_of, tagged_ok, update_if_unchanged};
+use crate::utils::login_throttle::LoginThrottle;
 use crate::utils::query_parameter::Query;
 use crate::utils::sse::SseNotifier;
-use actix_web::web;
-use chrono::Utc;
+use actix_web::{web, HttpRequest};
+use chrono::{DateTime, Utc};
 use log::{error, info};
+use sea_orm::sea_query::{Expr, SelectStatement};
 use sea_orm::ActiveValue::Set;
 use sea_orm::{
-    ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, PaginatorTrait, QueryFilter,
-    QueryOrder, QuerySelect,
+    ActiveModelTrait, ColumnTrait, Condition, ConnectionTrait, DatabaseConnection, EntityTrait,
+    PaginatorTrait, QueryFilter, QuerySelect, QueryTrait, TransactionTrait,
 };
-use serde::Serialize;
-use utoipa::ToSchema;
 use uuid::Uuid; // 添加uuid crate依赖
 use validator::Validate;
 
-#[derive(Serialize)]
-struct ErrorResponse {
-    error: String,
-}
-#[derive(Serialize, ToSchema)]
-pub struct UserInfo {
-    pub id: i32,
-    pub uuid: String,
-    pub user_name: String,
-    pub email: Option<String>,
-    pub image: Option<String>,
-    pub phone: Option<String>,
-    pub role: Option<String>,
-    pub permissions: Option<String>,
-    pub binding: Option<String>,
-    pub created_at: String,
-    pub updated_at: String,
-}
-
-#[derive(Serialize, ToSchema)]
-pub struct UserResponse {
-    data: Vec<UserInfo>,
-    pagination: PaginationInfo,
-}
-
 #[utoipa::path(
     get,
     path = "/api/users",
-    request_body = PaginationQuery,
+    request_body = UserListQuery,
     tag = "用户模块",
     operation_id = "获取用户列表",
     responses(
-        (status = 200, description = "获取用户列表成功", body = CommonResponse<UserResponse>),
+        (status = 200, description = "获取用户列表成功，传入 cursor 时为游标分页", body = CommonResponse<ListPage<UserInfo>>),
+        (status = 400, description = "筛选条件错误", body = ErrorResponse),
     ),
 )]
-// 获取用户列表（带分页）
+// 获取用户列表（带分页），支持关键字搜索、按角色/权限/双重验证/状态/创建时间筛选和排序
 pub async fn get_all_users(
     db: web::Data<DatabaseConnection>,
-    query: Query<PaginationQuery>,
+    query: Query<UserListQuery>,
 ) -> SimpleResp {
-    // 验证分页参数
-    let validated_query = match query.validate() {
-        Ok(_) => query.into_inner(),
-        Err(e) => {
-            log::error!("分页参数验证失败: {:?}", e);
-            let error_response = ErrorResponse {
-                error: e.to_string(),
-            };
-            return Resp::ok("分页参数验证失败", &error_response.error).to_json_result();
-        }
-    };
-    let page = validated_query.page.unwrap_or(1);
-    let limit = validated_query.limit.unwrap_or(DEFAULT_PAGE_SIZE);
-
-    // 限制每页数量的范围
-    let limit = if limit == 0 {
-        DEFAULT_PAGE_SIZE
-    } else if limit > MAX_PAGE_SIZE {
-        MAX_PAGE_SIZE
-    } else {
-        limit
+    let query = query.into_inner();
+    let condition = user_list_condition(db.as_ref(), &query).await?;
+    let sort_column = match query.sort_by.unwrap_or_default() {
+        UserSortField::Id => user::Column::Id,
+        UserSortField::CreatedAt => user::Column::CreatedAt,
+        UserSortField::UpdatedAt => user::Column::UpdatedAt,
     };
-    let offset = (page - 1) * limit;
 
-    // 获取总数和分页数据
-    let (total, users) = tokio::try_join!(
-        UserEntity::find().count(db.as_ref()),
-        UserEntity::find()
-            .order_by_desc(user::Column::Id)
-            .offset(Some(offset))
-            .limit(Some(limit))
-            .all(db.as_ref())
-    )
-    .map_err(|e| {
-        error!("数据库操作获取用户列表失败: {}", e);
-        AppError::DatabaseError("服务器异常，请联系管理员".to_string())
-    })?;
-    let total_pages = (total + limit - 1) / limit; // 整数除法避免浮点误差
-
-    info!("total1: {}, users1: {:?}, ", total, users);
-    let data = deep_filter_data(users, vec!["pass_word"]);
-    // 获取分页用户数据
-    let response = PaginatedResponse {
-        data,
-        pagination: PaginationInfo {
-            total,
-            total_pages,
-            current_page: page,
-            limit,
-            has_next: page < total_pages,
-            has_previous: page > 1,
-        },
+    let page = Paginator::new(UserEntity::find())
+        .filter(condition)
+        .sort_by(sort_column, query.order.unwrap_or_default())
+        .fetch(db.as_ref(), &query)
+        .await?
+        .map(UserInfo::from);
+    Resp::ok(page, "获取用户列表成功").to_json_result()
+}
+
+// 查询角色相关的筛选条件，再与其他条件组合
+async fn user_list_condition(
+    db: &DatabaseConnection,
+    query: &UserListQuery,
+) -> Result<Condition, AppError> {
+    let role_id = match query.role.as_deref().filter(|r| !r.is_empty()) {
+        Some(name) => Some(
+            RoleEntity::find()
+                .filter(roles::Column::Name.eq(name))
+                .one(db)
+                .await?
+                .ok_or_else(|| AppError::BadRequest(format!("角色不存在: {}", name)))?
+                .id,
+        ),
+        None => None,
+    };
+    let permission = match query.permission.as_deref().filter(|p| !p.is_empty()) {
+        Some(name) => {
+            let flag = Permission::from_names(&[name])
+                .map_err(|name| AppError::BadRequest(format!("无效权限: {}", name)))?;
+            if flag.bits().count_ones() != 1 {
+                return Err(AppError::BadRequest("只能按单一权限筛选".into()));
+            }
+            let role_ids = RoleEntity::find()
+                .all(db)
+                .await?
+                .into_iter()
+                .filter(|role| Permission::from_stored(Some(&role.permissions)).contains(flag))
+                .map(|role| role.id)
+                .collect();
+            Some((flag, role_ids))
+        }
+        None => None,
     };
+    build_user_condition(query, role_id, permission)
+}
 
-    Resp::ok(response, "获取用户列表成功").to_json_result()
+// 组合筛选条件；权限按有效权限判断：角色或额外授予包含该权限，且没有被禁止
+fn build_user_condition(
+    query: &UserListQuery,
+    role_id: Option<i32>,
+    permission: Option<(Permission, Vec<i32>)>,
+) -> Result<Condition, AppError> {
+    let mut condition = Condition::all();
+
+    if let Some(keyword) = query
+        .keyword
+        .as_deref()
+        .map(str::trim)
+        .filter(|k| !k.is_empty())
+    {
+        condition = condition.add(
+            Condition::any()
+                .add(user::Column::UserName.contains(keyword))
+                .add(user::Column::Email.contains(keyword))
+                .add(user::Column::Phone.contains(keyword)),
+        );
+    }
+    if let Some(role_id) = role_id {
+        condition = condition.add(user::Column::Uuid.in_subquery(users_with_roles(vec![role_id])));
+    }
+    if let Some((flag, role_ids)) = permission {
+        let bits = flag.bits();
+        let mut granted = Condition::any().add(Expr::cust_with_values(
+            "CAST(COALESCE(`permissions`, '0') AS UNSIGNED) & ? <> 0",
+            [bits],
+        ));
+        if !role_ids.is_empty() {
+            granted = granted.add(user::Column::Uuid.in_subquery(users_with_roles(role_ids)));
+        }
+        condition = condition.add(granted).add(Expr::cust_with_values(
+            "CAST(COALESCE(`denied_permissions`, '0') AS UNSIGNED) & ? = 0",
+            [bits],
+        ));
+    }
+    match query.two_factor {
+        Some(true) => {
+            condition = condition
+                .add(user::Column::Binding.is_not_null())
+                .add(user::Column::Binding.ne(""));
+        }
+        Some(false) => {
+            condition = condition.add(
+                Condition::any()
+                    .add(user::Column::Binding.is_null())
+                    .add(user::Column::Binding.eq("")),
+            );
+        }
+        None => {}
+    }
+    if let Some(status) = query.status {
+        condition = condition.add(user::Column::Status.eq(status));
+    }
+    if let Some(from) = parse_time("created_from", query.created_from.as_deref())? {
+        condition = condition.add(user::Column::CreatedAt.gte(from));
+    }
+    if let Some(to) = parse_time("created_to", query.created_to.as_deref())? {
+        condition = condition.add(user::Column::CreatedAt.lte(to));
+    }
+    Ok(condition)
+}
+
+fn users_with_roles(role_ids: Vec<i32>) -> SelectStatement {
+    UserRoleEntity::find()
+        .select_only()
+        .column(user_roles::Column::UserUuid)
+        .filter(user_roles::Column::RoleId.is_in(role_ids))
+        .into_query()
+}
+
+fn parse_time(field: &str, value: Option<&str>) -> Result<Option<DateTime<Utc>>, AppError> {
+    value
+        .filter(|v| !v.is_empty())
+        .map(|v| {
+            DateTime::parse_from_rfc3339(v)
+                .map(|t| t.with_timezone(&Utc))
+                .map_err(|_| AppError::BadRequest(format!("{} 时间格式错误，应为 RFC 3339", field)))
+        })
+        .transpose()
 }
 
 #[utoipa::path(
@@ -127,12 +191,14 @@ pub async fn get_all_users(
     tag = "用户模块",
     operation_id = "获取指定用户信息",
     responses(
-        (status = 200, description = "获取用户信息成功", body = CommonResponse<UserInfo>),
-        (status = 404, description = "用户不存在", body = CommonResponse<Option<UserInfo>>)
+        (status = 200, description = "获取用户信息成功，响应头 ETag 为当前版本", body = CommonResponse<UserInfo>),
+        (status = 304, description = "If-None-Match 与当前版本一致，资源未修改"),
+        (status = 404, description = "用户不存在", body = ErrorResponse)
     ),
 )]
 // 通过uuID获取用户
 pub async fn get_user_by_uuid(
+    req: HttpRequest,
     db: web::Data<DatabaseConnection>,
     uuid: web::Path<String>,
 ) -> SimpleResp {
@@ -140,8 +206,7 @@ pub async fn get_user_by_uuid(
     let uuid = match uuid_result {
         Ok(u) => u,
         Err(_) => {
-            return Resp::err(AppError::BadRequest("无效的 UUID 格式".to_string()))
-                .to_json_result();
+            return Err(AppError::BadRequest("无效的 UUID 格式".to_string()));
         }
     };
 
@@ -152,86 +217,68 @@ pub async fn get_user_by_uuid(
         Ok(u) => u,
         Err(e) => {
             error!("获取用户信息失败: {}", e); // 记录错误日志
-            return Resp::err(AppError::InternalServerError(
+            return Err(AppError::InternalServerError(
                 "获取用户信息失败".to_string(),
-            ))
-            .to_json_result();
+            ));
         }
     };
 
     match user {
-        Some(user) => {
-            let data = filter_value(user.into(), vec!["pass_word"]);
-            Resp::ok(data, "获取用户信息成功").to_json_result()
-        }
-        None => {
-            Resp::err(AppError::NotFound(format!("UUID为{}的用户不存在", uuid))).to_json_result()
-        }
+        Some(user) => conditional_ok(&req, UserInfo::from(user), "获取用户信息成功"),
+        None => Err(AppError::NotFound(format!("UUID为{}的用户不存在", uuid))),
     }
 }
 
 #[utoipa::path(
     put,
     path = "/api/users/{uuid}",
-    request_body = UserDto,
+    request_body = UpdateUserRequest,
     operation_id = "更新用户信息",
     params(
         ("uuid" = String, Path, description = "用户的 UUID")
     ),
     responses(
         (status = 200, description = "用户信息更新成功", body = CommonResponse<UserInfo>),
-        (status = 401, description = "未授权", body = AppError),
-        (status = 400, description = "请求参数错误", body = AppError),
-        (status = 404, description = "用户不存在", body = AppError),
-        (status = 409, description = "用户名已存在", body = AppError),
-        (status = 500, description = "服务器内部错误", body = AppError)
+        (status = 401, description = "未授权", body = ErrorResponse),
+        (status = 400, description = "请求参数错误", body = ErrorResponse),
+        (status = 403, description = "需要用户管理权限，修改权限需要系统管理员", body = ErrorResponse),
+        (status = 404, description = "用户不存在", body = ErrorResponse),
+        (status = 409, description = "用户名已存在", body = ErrorResponse),
+        (status = 412, description = "If-Match 与当前版本不一致，或读取后用户已被他人修改", body = ErrorResponse),
+        (status = 500, description = "服务器内部错误", body = ErrorResponse)
     ),
     security(),
     tag = "用户模块"
 )]
-// 更新用户信息
+// 管理员更新用户信息，用户修改自己的资料走 PUT /api/me；携带 If-Match 时只在版本一致时修改
 pub async fn update_user(
+    req: HttpRequest,
     db: web::Data<DatabaseConnection>,
+    current_user: CurrentUser,
     uuid: web::Path<String>,
     user_data: web::Json<UpdateUserRequest>,
     notifier: web::Data<SseNotifier>,
 ) -> SimpleResp {
-    // 验证UUID格式
-    let uuid_result = Uuid::parse_str(&uuid);
-    let uuid = match uuid_result {
-        Ok(u) => u,
-        Err(_) => {
-            return Resp::err(AppError::BadRequest("无效的 UUID 格式".to_string()))
-                .to_json_result();
-        }
-    };
+    let existing_user = find_user(db.as_ref(), &uuid).await?;
+    let uuid = existing_user.uuid.clone();
+    check_if_match(&req, &etag_of(&UserInfo::from(existing_user.clone())))?;
 
-    let existing_user = match UserEntity::find_by_uuid(&uuid.to_string())
-        .one(db.as_ref())
-        .await
+    // 修改权限等同于授权，只有系统管理员可以操作，避免用户给自己提权
+    if (user_data.permissions.is_some() || user_data.denied_permissions.is_some())
+        && !current_user.has(Permission::WRITE_SYSTEM)
     {
-        Ok(u) => u,
-        Err(e) => {
-            error!("获取用户信息失败: {}", e); // 记录错误日志
-            return Resp::err(AppError::InternalServerError(
-                "获取用户信息失败".to_string(),
-            ))
-            .to_json_result();
-        }
-    };
-
-    let existing_user =
-        existing_user.ok_or_else(|| AppError::NotFound(format!("ID为{}的用户不存在", uuid)))?;
+        return Err(AppError::Forbidden("只有系统管理员可以修改用户权限".into()));
+    }
 
     // 3. 准备更新模型
-    let mut user_active: user::ActiveModel = existing_user.into();
+    let mut user_active: user::ActiveModel = existing_user.clone().into();
 
     // 4. 用户名更新逻辑
 
     if user_active.user_name != Set(user_data.user_name.clone()) {
         let exists = UserEntity::find()
             .filter(user::Column::UserName.eq(&user_data.user_name))
-            .filter(user::Column::Uuid.ne(&*uuid.to_string()))
+            .filter(user::Column::Uuid.ne(&uuid))
             .count(db.as_ref())
             .await
             .map_err(|e| AppError::InternalServerError(format!("用户名检查失败: {}", e)))?
@@ -265,35 +312,37 @@ pub async fn update_user(
         // 将权限位转换为数值存储
         user_active.permissions = Set(Some(permission_bits.bits().to_string()));
     }
+    if let Some(denied) = &user_data.denied_permissions {
+        let denied_bits = Permission::from_names(denied)
+            .map_err(|perm| AppError::BadRequest(format!("无效权限: {}", perm)))?;
+        user_active.denied_permissions = Set(Some(denied_bits.bits().to_string()));
+    }
 
     // 6. 其他字段更新
-    // if let Some(image) = &user_data.image {
-    //     user_active.avatar = Set(image.clone());
-    // }
+    if let Some(image) = &user_data.image {
+        user_active.image = Set(Some(image.clone()));
+    }
 
     // 7. 更新时间戳
     user_active.updated_at = Set(Utc::now());
 
-    // 8. 执行更新
-    let updated_user = user_active
-        .update(db.as_ref())
-        .await
-        .map_err(|e| AppError::InternalServerError(format!("更新失败: {}", e)))?;
+    // 8. 执行更新，读取之后被他人修改过则返回 412
+    let updated_user = update_user_if_unchanged(db.as_ref(), &existing_user, user_active).await?;
     let notification = serde_json::json!({
         "event": "user_updated",
         "data": {
             "user_id": updated_user.id,
             "updated_fields": {
                 "username": &user_data.user_name,
-                "permissions": &user_data.permissions
+                "permissions": &user_data.permissions,
+                "denied_permissions": &user_data.denied_permissions
             }
         }
     });
 
     notifier.notify(&notification.to_string());
 
-    let data = filter_value(updated_user.into(), vec!["pass_word"]);
-    Resp::ok(data, "修改用户信息成功").to_json_result()
+    tagged_ok(UserInfo::from(updated_user), "修改用户信息成功")
 }
 
 #[utoipa::path(
@@ -305,42 +354,555 @@ pub async fn update_user(
     responses(
 
         (status = 200, description = "用户删除成功", body = CommonResponse<UserInfo>),
-        (status = 400, description = "请求参数错误", body = AppError),
-        (status = 404, description = "用户不存在", body = AppError),
-        (status = 500, description = "服务器内部错误", body = AppError)
+        (status = 400, description = "请求参数错误", body = ErrorResponse),
+        (status = 403, description = "需要用户管理权限", body = ErrorResponse),
+        (status = 404, description = "用户不存在", body = ErrorResponse),
+        (status = 412, description = "If-Match 与当前版本不一致，或读取后用户已被他人修改", body = ErrorResponse),
+        (status = 500, description = "服务器内部错误", body = ErrorResponse)
     ),
     security(),
     tag = "用户模块",
     operation_id = "删除用户",
 )]
 
-// 删除用户
-pub async fn delete_user(db: web::Data<DatabaseConnection>, uuid: web::Path<String>) -> SimpleResp {
-    info!("删除用户请求: {}", uuid); // 改为info级别
+// 管理员删除用户（软删除），用户注销自己的账户走 DELETE /api/me 并确认密码；
+// 个人信息在保留期后匿名化
+pub async fn delete_user(
+    req: HttpRequest,
+    db: web::Data<DatabaseConnection>,
+    uuid: web::Path<String>,
+) -> SimpleResp {
+    let existing_user = find_user(db.as_ref(), &uuid).await?;
+    let uuid = existing_user.uuid.clone();
+    check_if_match(&req, &etag_of(&UserInfo::from(existing_user.clone())))?;
+    info!("删除用户请求: {}", uuid);
 
-    // 使用uuid库验证UUID格式
-    let uuid_result = Uuid::parse_str(&uuid);
-    let uuid = match uuid_result {
-        Ok(u) => u,
-        Err(_) => {
-            return Resp::err(AppError::BadRequest("无效的 UUID 格式".to_string()))
-                .to_json_result();
+    soft_delete_user(db.as_ref(), existing_user).await?;
+    info!("成功删除用户: {}", uuid); // 记录成功操作
+    Resp::ok("", &format!("用户 {} 已删除", uuid)).to_json_result()
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/users/{uuid}/unlock",
+    params(
+        ("uuid" = String, Path, description = "用户的 UUID")
+    ),
+    responses(
+        (status = 200, description = "账户已解锁", body = CommonResponse<String>),
+        (status = 400, description = "请求参数错误", body = ErrorResponse),
+        (status = 403, description = "权限不足", body = ErrorResponse),
+        (status = 404, description = "用户不存在", body = ErrorResponse)
+    ),
+    security(),
+    tag = "用户模块",
+    operation_id = "解锁用户",
+)]
+// 解除因登录失败过多导致的临时锁定
+pub async fn unlock_user(
+    db: web::Data<DatabaseConnection>,
+    throttle: web::Data<LoginThrottle>,
+    uuid: web::Path<String>,
+) -> SimpleResp {
+    let uuid =
+        Uuid::parse_str(&uuid).map_err(|_| AppError::BadRequest("无效的 UUID 格式".to_string()))?;
+
+    let user = UserEntity::find_by_uuid(&uuid.to_string())
+        .one(db.as_ref())
+        .await
+        .map_err(|e| {
+            error!("获取用户信息失败: {}", e);
+            AppError::InternalServerError("获取用户信息失败".to_string())
+        })?
+        .ok_or_else(|| AppError::NotFound(format!("UUID为{}的用户不存在", uuid)))?;
+
+    if throttle.unlock(&user.user_name) {
+        info!("账户 {} 已被管理员解锁", user.user_name);
+    }
+    Resp::ok("", &format!("用户 {} 已解锁", user.user_name)).to_json_result()
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/users/{uuid}/disable",
+    params(
+        ("uuid" = String, Path, description = "用户的 UUID")
+    ),
+    responses(
+        (status = 200, description = "用户已禁用", body = CommonResponse<String>),
+        (status = 404, description = "用户不存在", body = ErrorResponse),
+    ),
+    tag = "用户模块",
+    operation_id = "禁用用户",
+)]
+// 禁用账户，禁用后无法登录，已签发的令牌也会被拒绝
+pub async fn disable_user(
+    db: web::Data<DatabaseConnection>,
+    uuid: web::Path<String>,
+) -> SimpleResp {
+    let user = find_user(db.as_ref(), &uuid).await?;
+    if user.status == UserStatus::Deleted {
+        return Err(AppError::Conflict("账户已注销".into()));
+    }
+    let mut user_active: user::ActiveModel = user.into();
+    user_active.status = Set(UserStatus::Disabled);
+    user_active.updated_at = Set(Utc::now());
+    let user = user_active.update(db.as_ref()).await?;
+
+    info!("账户 {} 已被禁用", user.uuid);
+    Resp::ok("", &format!("用户 {} 已禁用", user.user_name)).to_json_result()
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/users/{uuid}/restore",
+    params(
+        ("uuid" = String, Path, description = "用户的 UUID")
+    ),
+    responses(
+        (status = 200, description = "用户已恢复", body = CommonResponse<String>),
+        (status = 404, description = "用户不存在", body = ErrorResponse),
+        (status = 409, description = "账户已匿名化，无法恢复", body = ErrorResponse),
+    ),
+    tag = "用户模块",
+    operation_id = "恢复用户",
+)]
+// 恢复被禁用或已注销（尚未匿名化）的账户
+pub async fn restore_user(
+    db: web::Data<DatabaseConnection>,
+    uuid: web::Path<String>,
+) -> SimpleResp {
+    let user = find_user(db.as_ref(), &uuid).await?;
+    if user.anonymized_at.is_some() {
+        return Err(AppError::Conflict("账户已匿名化，无法恢复".into()));
+    }
+    let mut user_active: user::ActiveModel = user.into();
+    user_active.status = Set(UserStatus::Active);
+    user_active.deleted_at = Set(None);
+    user_active.updated_at = Set(Utc::now());
+    let user = user_active.update(db.as_ref()).await?;
+
+    info!("账户 {} 已恢复", user.uuid);
+    Resp::ok("", &format!("用户 {} 已恢复", user.user_name)).to_json_result()
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/users/batch",
+    request_body = UserBatchRequest,
+    responses(
+        (status = 200, description = "批量操作完成，返回每个用户的处理结果", body = CommonResponse<BatchResult>),
+        (status = 400, description = "参数错误或角色不存在", body = ErrorResponse),
+        (status = 403, description = "分配角色需要系统管理权限", body = ErrorResponse),
+        (status = 500, description = "数据库错误，整批修改回滚，没有任何用户被修改", body = ErrorResponse),
+    ),
+    tag = "用户模块",
+    operation_id = "批量操作用户",
+    description = "整批提交：不存在或已注销的用户在结果中标记为失败并跳过，数据库错误会回滚整批修改",
+)]
+// 批量禁用用户或追加角色，规则与单个用户的接口相同。
+// 所有修改在同一个事务中提交，数据库出错时整批回滚并返回错误，而不是逐项报告
+pub async fn batch_users(
+    db: web::Data<DatabaseConnection>,
+    current_user: CurrentUser,
+    payload: web::Json<UserBatchRequest>,
+) -> SimpleResp {
+    if let Err(e) = payload.validate() {
+        info!("批量操作用户:{:?}", e);
+        return Err(e.into());
+    }
+    let payload = payload.into_inner();
+    // 与 PUT /api/users/{uuid}/roles 一样，分配角色需要系统管理权限
+    let role = match &payload.action {
+        UserBatchAction::AssignRole { role } => {
+            if !current_user.has(Permission::WRITE_SYSTEM) {
+                return Err(AppError::Forbidden("只有系统管理员可以分配角色".into()));
+            }
+            Some(
+                RoleEntity::find()
+                    .filter(roles::Column::Name.eq(role))
+                    .one(db.as_ref())
+                    .await?
+                    .ok_or_else(|| AppError::BadRequest(format!("角色不存在: {}", role)))?,
+            )
         }
+        UserBatchAction::Disable => None,
     };
 
-    let delete_result = match UserEntity::delete_by_uuid(db.as_ref(), &uuid.to_string()).await {
-        Ok(u) => u,
-        Err(e) => {
-            error!("删除用户失败: {}", e); // 记录错误日志
-            return Resp::err(AppError::InternalServerError("删除用户失败".to_string()))
-                .to_json_result();
+    let uuids = dedup_ids(&payload.uuids);
+    let txn = db.begin().await?;
+    // 在事务内加锁读取，避免并发修改在检查和写入之间改变用户状态
+    let users = UserEntity::find()
+        .filter(user::Column::Uuid.is_in(uuids.clone()))
+        .lock_exclusive()
+        .all(&txn)
+        .await?;
+
+    let mut result = BatchResult::default();
+    for uuid in &uuids {
+        let Some(user) = users.iter().find(|u| &u.uuid == uuid) else {
+            result.fail(uuid, "用户不存在");
+            continue;
+        };
+        if user.status == UserStatus::Deleted {
+            result.fail(uuid, "账户已注销");
+            continue;
         }
-    };
+        match &role {
+            Some(role) => {
+                let assigned = UserRoleEntity::find()
+                    .filter(user_roles::Column::UserUuid.eq(&user.uuid))
+                    .filter(user_roles::Column::RoleId.eq(role.id))
+                    .count(&txn)
+                    .await?
+                    > 0;
+                if !assigned {
+                    insert_user_role(&txn, &user.uuid, role.id).await?;
+                }
+            }
+            None => {
+                let mut user_active: user::ActiveModel = user.clone().into();
+                user_active.status = Set(UserStatus::Disabled);
+                user_active.updated_at = Set(Utc::now());
+                user_active.update(&txn).await?;
+            }
+        }
+        result.ok(uuid);
+    }
+    txn.commit().await?;
+
+    info!(
+        "用户 {} 批量操作用户 {:?}: 成功 {} 个，失败 {} 个",
+        current_user.uuid, payload.action, result.succeeded, result.failed
+    );
+    Resp::ok(result, "批量操作用户完成").to_json_result()
+}
+
+// 禁用或已注销的账户不能登录，也不能继续使用已签发的令牌
+pub fn ensure_active(user: &user::Model) -> Result<(), AppError> {
+    match user.status {
+        UserStatus::Active => Ok(()),
+        UserStatus::Disabled => Err(AppError::Forbidden("账户已被禁用".into())),
+        UserStatus::Deleted => Err(AppError::Forbidden("账户已注销".into())),
+    }
+}
+
+// 标记为已注销，保留数据以便在保留期内恢复；读取之后被他人修改过则返回 412
+pub async fn soft_delete_user<C: ConnectionTrait>(
+    db: &C,
+    user: user::Model,
+) -> Result<user::Model, AppError> {
+    let mut user_active: user::ActiveModel = user.clone().into();
+    user_active.status = Set(UserStatus::Deleted);
+    user_active.deleted_at = Set(Some(Utc::now()));
+    user_active.updated_at = Set(Utc::now());
+    update_user_if_unchanged(db, &user, user_active).await
+}
+
+// 只在用户的更新时间仍是读取时的值时写入，返回写入后的用户
+pub async fn update_user_if_unchanged<C: ConnectionTrait>(
+    db: &C,
+    read: &user::Model,
+    user_active: user::ActiveModel,
+) -> Result<user::Model, AppError> {
+    let unchanged = Condition::all()
+        .add(user::Column::Id.eq(read.id))
+        .add(user::Column::UpdatedAt.eq(read.updated_at));
+    update_if_unchanged(db, user_active, unchanged).await?;
+    UserEntity::find_by_id(read.id)
+        .one(db)
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("UUID为{}的用户不存在", read.uuid)))
+}
+
+// 匿名化超过保留期的已注销账户，返回处理的数量
+pub async fn purge_deleted_users(
+    db: &DatabaseConnection,
+    retention: std::time::Duration,
+) -> Result<u64, AppError> {
+    let cutoff = Utc::now()
+        - chrono::Duration::from_std(retention).unwrap_or_else(|_| chrono::Duration::days(30));
+    let users = UserEntity::find()
+        .filter(user::Column::Status.eq(UserStatus::Deleted))
+        .filter(user::Column::DeletedAt.lt(cutoff))
+        .filter(user::Column::AnonymizedAt.is_null())
+        .all(db)
+        .await?;
+
+    let mut purged = 0;
+    for user in users {
+        let user_uuid = user.uuid.clone();
+        let txn = db.begin().await?;
+        // 第三方登录和API密钥都是登录凭证，一并删除
+        UserIdentityEntity::delete_many()
+            .filter(user_identities::Column::UserUuid.eq(&user_uuid))
+            .exec(&txn)
+            .await?;
+        ApiKeyEntity::delete_many()
+            .filter(api_keys::Column::UserUuid.eq(&user_uuid))
+            .exec(&txn)
+            .await?;
+        let mut user_active: user::ActiveModel = user.into();
+        user_active.user_name = Set(format!("deleted_{}", user_uuid));
+        // 清空密码哈希，任何密码都无法通过校验
+        user_active.pass_word = Set(String::new());
+        user_active.email = Set(None);
+        user_active.email_verified_at = Set(None);
+        user_active.phone = Set(None);
+        user_active.image = Set(None);
+        user_active.binding = Set(None);
+        user_active.pending_binding = Set(None);
+        user_active.anonymized_at = Set(Some(Utc::now()));
+        user_active.updated_at = Set(Utc::now());
+        user_active.update(&txn).await?;
+        txn.commit().await?;
+        purged += 1;
+    }
+    Ok(purged)
+}
+
+// 定时执行匿名化任务
+pub fn spawn_purge_task(db: web::Data<DatabaseConnection>) {
+    actix_web::rt::spawn(async move {
+        let mut interval = actix_web::rt::time::interval(ACCOUNT_CONFIG.purge_interval);
+        loop {
+            interval.tick().await;
+            match purge_deleted_users(db.as_ref(), ACCOUNT_CONFIG.deleted_retention).await {
+                Ok(0) => {}
+                Ok(count) => info!("已匿名化 {} 个注销账户", count),
+                Err(e) => error!("匿名化注销账户失败: {}", e),
+            }
+        }
+    });
+}
+
+pub(crate) async fn find_user(
+    db: &DatabaseConnection,
+    uuid: &str,
+) -> Result<user::Model, AppError> {
+    let uuid =
+        Uuid::parse_str(uuid).map_err(|_| AppError::BadRequest("无效的 UUID 格式".to_string()))?;
+    UserEntity::find_by_uuid(&uuid.to_string())
+        .one(db)
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("UUID为{}的用户不存在", uuid)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::SortOrder;
+    use sea_orm::{DbBackend, MockDatabase, MockExecResult};
+    use std::future::Future;
+    use std::sync::Arc;
+
+    const UUID: &str = "6f1c1c2e-3b8a-4c1e-9f7a-2d5e8b9a0c11";
+
+    fn list_query() -> UserListQuery {
+        serde_urlencoded::from_str("").unwrap()
+    }
+
+    fn sql(condition: Condition) -> String {
+        UserEntity::find()
+            .filter(condition)
+            .build(DbBackend::MySql)
+            .to_string()
+    }
+
+    #[test]
+    fn empty_query_has_no_filters() {
+        let condition = build_user_condition(&list_query(), None, None).unwrap();
+        assert!(sql(condition).ends_with("FROM `users` WHERE TRUE"));
+    }
+
+    #[test]
+    fn filters_are_combined() {
+        let query: UserListQuery = serde_urlencoded::from_str(
+            "keyword=ali&two_factor=true&status=active&created_from=2024-01-01T00:00:00Z&sort_by=created_at&order=asc",
+        )
+        .unwrap();
+        assert_eq!(query.sort_by, Some(UserSortField::CreatedAt));
+        assert_eq!(query.order, Some(SortOrder::Asc));
+
+        let sql = sql(build_user_condition(&query, Some(3), None).unwrap());
+        assert!(sql.contains("`users`.`user_name` LIKE '%ali%'"));
+        assert!(sql.contains("`users`.`email` LIKE '%ali%'"));
+        assert!(sql.contains("`users`.`phone` LIKE '%ali%'"));
+        assert!(sql.contains("`users`.`binding` IS NOT NULL"));
+        assert!(sql.contains("`users`.`status` = ('active')"));
+        assert!(sql.contains("`users`.`created_at` >= '2024-01-01 00:00:00"));
+        assert!(sql.contains("`user_roles`.`role_id` IN (3)"));
+    }
+
+    #[test]
+    fn permission_filter_checks_roles_grants_and_denies() {
+        let sql = sql(build_user_condition(
+            &list_query(),
+            None,
+            Some((Permission::READ_USER, vec![1, 2])),
+        )
+        .unwrap());
+        assert!(sql.contains("CAST(COALESCE(`permissions`, '0') AS UNSIGNED) & 16 <> 0"));
+        assert!(sql.contains("`user_roles`.`role_id` IN (1, 2)"));
+        assert!(sql.contains("CAST(COALESCE(`denied_permissions`, '0') AS UNSIGNED) & 16 = 0"));
+    }
+
+    #[test]
+    fn invalid_time_is_rejected() {
+        let query: UserListQuery = serde_urlencoded::from_str("created_to=yesterday").unwrap();
+        assert!(matches!(
+            build_user_condition(&query, None, None),
+            Err(AppError::BadRequest(_))
+        ));
+    }
+
+    fn exec() -> MockExecResult {
+        MockExecResult {
+            last_insert_id: 0,
+            rows_affected: 1,
+        }
+    }
+
+    fn with_status(status: UserStatus) -> user::Model {
+        let mut user = user::Model::fixture(UUID, "alice01");
+        user.status = status;
+        user
+    }
+
+    // 调用处理函数，返回结果和执行过的SQL
+    async fn run<F, Fut, T>(db: MockDatabase, f: F) -> (T, Vec<String>)
+    where
+        F: FnOnce(web::Data<DatabaseConnection>) -> Fut,
+        Fut: Future<Output = T>,
+    {
+        let db = web::Data::new(db.into_connection());
+        let out = f(db.clone()).await;
+        let log = Arc::try_unwrap(db.into_inner())
+            .map(DatabaseConnection::into_transaction_log)
+            .unwrap()
+            .iter()
+            .flat_map(|t| {
+                t.statements()
+                    .iter()
+                    .map(|s| s.to_string())
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+        (out, log)
+    }
+
+    #[test]
+    fn only_active_users_pass() {
+        assert!(ensure_active(&with_status(UserStatus::Active)).is_ok());
+        assert!(matches!(
+            ensure_active(&with_status(UserStatus::Disabled)),
+            Err(AppError::Forbidden(_))
+        ));
+        assert!(matches!(
+            ensure_active(&with_status(UserStatus::Deleted)),
+            Err(AppError::Forbidden(_))
+        ));
+    }
+
+    #[actix_web::test]
+    async fn disable_sets_status() {
+        let db = MockDatabase::new(DbBackend::MySql)
+            .append_query_results([vec![with_status(UserStatus::Active)]])
+            .append_exec_results([exec()])
+            .append_query_results([vec![with_status(UserStatus::Disabled)]]);
+        let (resp, log) = run(db, |db| disable_user(db, web::Path::from(UUID.to_string()))).await;
+        assert!(resp.is_ok());
+        assert!(log[1].starts_with("UPDATE `users` SET `status` = 'disabled'"));
+    }
+
+    #[actix_web::test]
+    async fn deleted_account_cannot_be_disabled() {
+        let db = MockDatabase::new(DbBackend::MySql)
+            .append_query_results([vec![with_status(UserStatus::Deleted)]]);
+        let (resp, log) = run(db, |db| disable_user(db, web::Path::from(UUID.to_string()))).await;
+        assert!(matches!(resp, Err(AppError::Conflict(_))));
+        assert_eq!(log.len(), 1);
+    }
+
+    #[actix_web::test]
+    async fn restore_clears_deletion() {
+        let mut deleted = with_status(UserStatus::Deleted);
+        deleted.deleted_at = Some(Utc::now());
+        let db = MockDatabase::new(DbBackend::MySql)
+            .append_query_results([vec![deleted]])
+            .append_exec_results([exec()])
+            .append_query_results([vec![with_status(UserStatus::Active)]]);
+        let (resp, log) = run(db, |db| restore_user(db, web::Path::from(UUID.to_string()))).await;
+        assert!(resp.is_ok());
+        assert!(log[1].contains("`status` = 'active'"));
+        assert!(log[1].contains("`deleted_at` = NULL"));
+    }
+
+    #[actix_web::test]
+    async fn anonymized_account_cannot_be_restored() {
+        let mut anonymized = with_status(UserStatus::Deleted);
+        anonymized.deleted_at = Some(Utc::now());
+        anonymized.anonymized_at = Some(Utc::now());
+        let db = MockDatabase::new(DbBackend::MySql).append_query_results([vec![anonymized]]);
+        let (resp, log) = run(db, |db| restore_user(db, web::Path::from(UUID.to_string()))).await;
+        assert!(matches!(resp, Err(AppError::Conflict(_))));
+        assert_eq!(log.len(), 1);
+    }
+
+    #[actix_web::test]
+    async fn purge_anonymizes_expired_accounts() {
+        let mut deleted = with_status(UserStatus::Deleted);
+        deleted.deleted_at = Some(Utc::now() - chrono::Duration::days(40));
+        deleted.email = Some("alice@example.com".into());
+        deleted.pass_word = "$argon2id$hash".into();
+        let db = MockDatabase::new(DbBackend::MySql)
+            .append_query_results([vec![deleted.clone()]])
+            .append_exec_results([exec(), exec(), exec()])
+            .append_query_results([vec![deleted]]);
+        let (purged, log) = run(db, |db| async move {
+            purge_deleted_users(db.as_ref(), std::time::Duration::from_secs(30 * 86400)).await
+        })
+        .await;
+        assert_eq!(purged.unwrap(), 1);
+        // 以匿名化时间而不是密码字段判断是否已处理
+        assert!(log[0].contains("`users`.`anonymized_at` IS NULL"));
+        assert!(!log[0]
+            .split(" WHERE ")
+            .nth(1)
+            .unwrap()
+            .contains("pass_word"));
+        assert!(log[2].starts_with("DELETE FROM `user_identities`"));
+        assert!(log[3].starts_with("DELETE FROM `api_keys`"));
+        let update = &log[4];
+        assert!(update.contains(&format!("`user_name` = 'deleted_{}'", UUID)));
+        assert!(update.contains("`email` = NULL"));
+        assert!(update.contains("`pass_word` = ''"));
+        assert!(update.contains("`anonymized_at` = '"));
+    }
 
-    if delete_result.rows_affected == 0 {
-        Resp::err(AppError::NotFound(format!("UUID为{}的用户不存在", uuid))).to_json_result()
-    } else {
-        info!("成功删除用户: {}", uuid); // 记录成功操作
-        Resp::ok("", &format!("用户 {} 已删除", uuid).to_string()).to_json_result()
+    #[actix_web::test]
+    async fn batch_reads_users_with_lock_inside_transaction() {
+        let missing = "0b6c9d1e-7f2a-4d3b-8c5e-1a2b3c4d5e6f";
+        let db = MockDatabase::new(DbBackend::MySql)
+            .append_query_results([vec![with_status(UserStatus::Active)]])
+            .append_exec_results([exec()])
+            .append_query_results([vec![with_status(UserStatus::Disabled)]]);
+        let admin = CurrentUser {
+            uuid: "admin".into(),
+            name: "admin".into(),
+            permissions: Permission::WRITE_USER,
+            token_id: None,
+        };
+        let payload: UserBatchRequest = serde_json::from_value(serde_json::json!({
+            "uuids": [UUID, missing],
+            "action": "disable",
+        }))
+        .unwrap();
+        let (resp, log) = run(db, |db| batch_users(db, admin, web::Json(payload))).await;
+        assert!(resp.is_ok());
+        assert_eq!(log[0], "BEGIN");
+        assert!(log[1].ends_with("FOR UPDATE"));
+        assert!(log[2].starts_with("UPDATE `users` SET `status` = 'disabled'"));
+        assert_eq!(log.last().unwrap(), "COMMIT");
     }
 }
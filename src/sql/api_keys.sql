@@ -0,0 +1,15 @@
+DROP TABLE IF EXISTS `api_keys`;
+CREATE TABLE `api_keys`  (
+  `id` int NOT NULL AUTO_INCREMENT COMMENT '唯一标识符，主键',
+  `user_uuid` char(36) CHARACTER SET utf8mb4 COLLATE utf8mb4_0900_ai_ci NOT NULL COMMENT '所属用户UUID',
+  `name` varchar(100) CHARACTER SET utf8mb4 COLLATE utf8mb4_0900_ai_ci NOT NULL COMMENT '密钥名称，便于用户区分用途',
+  `key_prefix` varchar(16) CHARACTER SET utf8mb4 COLLATE utf8mb4_0900_ai_ci NOT NULL COMMENT '密钥前几位，用于在列表中识别',
+  `key_hash` char(64) CHARACTER SET utf8mb4 COLLATE utf8mb4_0900_ai_ci NOT NULL COMMENT '密钥的SHA-256摘要（十六进制）',
+  `permissions` varchar(32) CHARACTER SET utf8mb4 COLLATE utf8mb4_0900_ai_ci NOT NULL COMMENT '授予密钥的权限位',
+  `expires_at` datetime NULL DEFAULT NULL COMMENT '过期时间，为空表示永不过期',
+  `last_used_at` datetime NULL DEFAULT NULL COMMENT '最近一次使用时间',
+  `created_at` datetime NOT NULL DEFAULT CURRENT_TIMESTAMP COMMENT '创建时间',
+  PRIMARY KEY (`id`) USING BTREE,
+  UNIQUE INDEX `UQ_api_keys_key_hash`(`key_hash` ASC) USING BTREE COMMENT '按摘要查找密钥',
+  INDEX `IDX_api_keys_user_uuid`(`user_uuid` ASC) USING BTREE COMMENT '按用户查询密钥'
+) ENGINE = InnoDB CHARACTER SET = utf8mb4 COLLATE = utf8mb4_0900_ai_ci ROW_FORMAT = Dynamic COMMENT='个人API密钥表';
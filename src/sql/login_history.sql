@@ -0,0 +1,14 @@
+DROP TABLE IF EXISTS `login_history`;
+CREATE TABLE `login_history`  (
+  `id` int NOT NULL AUTO_INCREMENT COMMENT '唯一标识符，主键',
+  `user_uuid` char(36) CHARACTER SET utf8mb4 COLLATE utf8mb4_0900_ai_ci NULL DEFAULT NULL COMMENT '用户UUID，用户名不存在时为空',
+  `user_name` varchar(255) CHARACTER SET utf8mb4 COLLATE utf8mb4_0900_ai_ci NOT NULL COMMENT '登录时提交的用户名',
+  `ip_address` varchar(64) CHARACTER SET utf8mb4 COLLATE utf8mb4_0900_ai_ci NOT NULL COMMENT '客户端IP地址',
+  `user_agent` varchar(255) CHARACTER SET utf8mb4 COLLATE utf8mb4_0900_ai_ci NULL DEFAULT NULL COMMENT '客户端User-Agent',
+  `success` tinyint NOT NULL DEFAULT 0 COMMENT '是否登录成功：1表示成功，0表示失败',
+  `reason` varchar(50) CHARACTER SET utf8mb4 COLLATE utf8mb4_0900_ai_ci NULL DEFAULT NULL COMMENT '失败原因',
+  `created_at` datetime NOT NULL DEFAULT CURRENT_TIMESTAMP COMMENT '登录时间',
+  PRIMARY KEY (`id`) USING BTREE,
+  INDEX `IDX_login_history_user_uuid`(`user_uuid` ASC) USING BTREE COMMENT '按用户查询登录记录',
+  INDEX `IDX_login_history_created_at`(`created_at` ASC) USING BTREE COMMENT '按时间查询登录记录'
+) ENGINE = InnoDB CHARACTER SET = utf8mb4 COLLATE = utf8mb4_0900_ai_ci ROW_FORMAT = Dynamic COMMENT='登录记录表，记录每次登录尝试的结果';
@@ -0,0 +1,12 @@
+DROP TABLE IF EXISTS `password_reset_tokens`;
+CREATE TABLE `password_reset_tokens`  (
+  `id` int NOT NULL AUTO_INCREMENT COMMENT '唯一标识符，主键',
+  `user_uuid` char(36) CHARACTER SET utf8mb4 COLLATE utf8mb4_0900_ai_ci NOT NULL COMMENT '所属用户的UUID，关联users表的uuid字段',
+  `token_hash` char(64) CHARACTER SET utf8mb4 COLLATE utf8mb4_0900_ai_ci NOT NULL COMMENT '重置令牌的SHA-256哈希（十六进制）',
+  `expires_at` datetime NOT NULL COMMENT '过期时间',
+  `used_at` datetime NULL DEFAULT NULL COMMENT '使用时间，为空表示尚未使用',
+  `created_at` datetime NOT NULL DEFAULT CURRENT_TIMESTAMP COMMENT '记录创建时间',
+  PRIMARY KEY (`id`) USING BTREE,
+  UNIQUE INDEX `IDX_password_reset_tokens_hash`(`token_hash` ASC) USING BTREE COMMENT '按令牌哈希查询',
+  INDEX `IDX_password_reset_tokens_user_uuid`(`user_uuid` ASC) USING BTREE COMMENT '按用户查询'
+) ENGINE = InnoDB CHARACTER SET = utf8mb4 COLLATE = utf8mb4_0900_ai_ci ROW_FORMAT = Dynamic COMMENT='密码重置令牌表，令牌一次性使用且有过期时间';
@@ -0,0 +1,12 @@
+DROP TABLE IF EXISTS `roles`;
+CREATE TABLE `roles`  (
+  `id` int NOT NULL AUTO_INCREMENT COMMENT '唯一标识符，主键',
+  `name` varchar(50) CHARACTER SET utf8mb4 COLLATE utf8mb4_0900_ai_ci NOT NULL COMMENT '角色名称',
+  `description` varchar(255) CHARACTER SET utf8mb4 COLLATE utf8mb4_0900_ai_ci NULL DEFAULT NULL COMMENT '角色说明',
+  `permissions` varchar(32) CHARACTER SET utf8mb4 COLLATE utf8mb4_0900_ai_ci NOT NULL DEFAULT '0' COMMENT '角色拥有的权限位',
+  `is_builtin` tinyint NOT NULL DEFAULT 0 COMMENT '是否为内置角色：1表示内置，内置角色不能删除',
+  `created_at` datetime NOT NULL DEFAULT CURRENT_TIMESTAMP COMMENT '创建时间',
+  `updated_at` datetime NOT NULL DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP COMMENT '更新时间',
+  PRIMARY KEY (`id`) USING BTREE,
+  UNIQUE INDEX `UQ_roles_name`(`name` ASC) USING BTREE COMMENT '角色名称唯一'
+) ENGINE = InnoDB CHARACTER SET = utf8mb4 COLLATE = utf8mb4_0900_ai_ci ROW_FORMAT = Dynamic COMMENT='角色表，一个角色对应一组权限';
@@ -0,0 +1,10 @@
+DROP TABLE IF EXISTS `two_factor_recovery_codes`;
+CREATE TABLE `two_factor_recovery_codes`  (
+  `id` int NOT NULL AUTO_INCREMENT COMMENT '唯一标识符，主键',
+  `user_uuid` char(36) CHARACTER SET utf8mb4 COLLATE utf8mb4_0900_ai_ci NOT NULL COMMENT '所属用户的UUID，关联users表的uuid字段',
+  `code_hash` varchar(255) CHARACTER SET utf8mb4 COLLATE utf8mb4_0900_ai_ci NOT NULL COMMENT '恢复码的SHA-256摘要（十六进制），恢复码为高熵随机串，按摘要直接查询',
+  `used_at` datetime NULL DEFAULT NULL COMMENT '使用时间，为空表示尚未使用',
+  `created_at` datetime NOT NULL DEFAULT CURRENT_TIMESTAMP COMMENT '记录创建时间',
+  PRIMARY KEY (`id`) USING BTREE,
+  INDEX `IDX_recovery_codes_user_uuid`(`user_uuid` ASC) USING BTREE COMMENT '按用户查询恢复码'
+) ENGINE = InnoDB CHARACTER SET = utf8mb4 COLLATE = utf8mb4_0900_ai_ci ROW_FORMAT = Dynamic COMMENT='双重验证恢复码表，每个恢复码只能使用一次';
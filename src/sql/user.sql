@@ -3,16 +3,23 @@ CREATE TABLE users (
     uuid CHAR(36) NOT NULL COMMENT '全局唯一标识符（字符串格式）',
     user_name VARCHAR(255) NOT NULL COMMENT '用户名',
     pass_word VARCHAR(255) NOT NULL COMMENT '密码',
-    email VARCHAR(255) COMMENT '电子邮箱',
+    email VARCHAR(255) COMMENT '电子邮箱（小写）',
+    email_verified_at DATETIME NULL COMMENT '邮箱验证时间，未验证为NULL',
     image VARCHAR(255) COMMENT '头像图片路径',
     phone VARCHAR(20) COMMENT '手机号码',
     role VARCHAR(50) COMMENT '用户角色',
-    permissions TEXT COMMENT '用户权限',
-    binding VARCHAR(255) COMMENT '绑定信息',
+    permissions TEXT COMMENT '额外授予的权限位，与角色权限取并集',
+    denied_permissions TEXT COMMENT '禁止的权限位，从有效权限中移除',
+    binding VARCHAR(255) COMMENT '绑定信息（加密后的TOTP密钥）',
+    pending_binding VARCHAR(255) COMMENT '待确认的TOTP密钥（加密）',
+    status ENUM('active', 'disabled', 'deleted') NOT NULL DEFAULT 'active' COMMENT '账户状态：正常、禁用、已注销',
+    deleted_at DATETIME NULL COMMENT '注销时间，超过保留期后匿名化',
+    anonymized_at DATETIME NULL COMMENT '匿名化时间，匿名化后账户不可恢复',
     created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP COMMENT '创建时间',
-    updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP COMMENT '更新时间',
+    updated_at DATETIME(3) NOT NULL DEFAULT CURRENT_TIMESTAMP(3) ON UPDATE CURRENT_TIMESTAMP(3) COMMENT '更新时间（毫秒），修改时作为乐观锁的版本条件',
     UNIQUE KEY unique_uuid (uuid),
     UNIQUE KEY unique_email (email),
     UNIQUE KEY unique_user_name (user_name),
-    UNIQUE KEY unique_phone (phone)
+    UNIQUE KEY unique_phone (phone),
+    KEY idx_status_deleted_at (status, deleted_at)
 ) COMMENT='用户信息表';
\ No newline at end of file
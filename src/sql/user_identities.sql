@@ -0,0 +1,13 @@
+DROP TABLE IF EXISTS `user_identities`;
+CREATE TABLE `user_identities`  (
+  `id` int NOT NULL AUTO_INCREMENT COMMENT '唯一标识符，主键',
+  `user_uuid` char(36) CHARACTER SET utf8mb4 COLLATE utf8mb4_0900_ai_ci NOT NULL COMMENT '关联的用户UUID',
+  `provider` varchar(50) CHARACTER SET utf8mb4 COLLATE utf8mb4_0900_ai_ci NOT NULL COMMENT '第三方登录提供方，例如 github',
+  `subject` varchar(255) CHARACTER SET utf8mb4 COLLATE utf8mb4_0900_ai_ci NOT NULL COMMENT '用户在提供方的唯一标识',
+  `email` varchar(255) CHARACTER SET utf8mb4 COLLATE utf8mb4_0900_ai_ci NULL DEFAULT NULL COMMENT '提供方返回的邮箱',
+  `created_at` datetime NOT NULL DEFAULT CURRENT_TIMESTAMP COMMENT '绑定时间',
+  `last_login_at` datetime NULL DEFAULT NULL COMMENT '最近一次通过该身份登录的时间',
+  PRIMARY KEY (`id`) USING BTREE,
+  UNIQUE INDEX `UQ_user_identities_provider_subject`(`provider` ASC, `subject` ASC) USING BTREE COMMENT '同一提供方的身份只能绑定一个用户',
+  INDEX `IDX_user_identities_user_uuid`(`user_uuid` ASC) USING BTREE COMMENT '按用户查询已绑定的身份'
+) ENGINE = InnoDB CHARACTER SET = utf8mb4 COLLATE = utf8mb4_0900_ai_ci ROW_FORMAT = Dynamic COMMENT='第三方登录身份绑定表';
@@ -0,0 +1,10 @@
+DROP TABLE IF EXISTS `user_roles`;
+CREATE TABLE `user_roles`  (
+  `id` int NOT NULL AUTO_INCREMENT COMMENT '唯一标识符，主键',
+  `user_uuid` char(36) CHARACTER SET utf8mb4 COLLATE utf8mb4_0900_ai_ci NOT NULL COMMENT '用户UUID',
+  `role_id` int NOT NULL COMMENT '角色ID',
+  `created_at` datetime NOT NULL DEFAULT CURRENT_TIMESTAMP COMMENT '分配时间',
+  PRIMARY KEY (`id`) USING BTREE,
+  UNIQUE INDEX `UQ_user_roles_user_role`(`user_uuid` ASC, `role_id` ASC) USING BTREE COMMENT '同一角色不能重复分配',
+  INDEX `IDX_user_roles_role_id`(`role_id` ASC) USING BTREE COMMENT '按角色查询用户'
+) ENGINE = InnoDB CHARACTER SET = utf8mb4 COLLATE = utf8mb4_0900_ai_ci ROW_FORMAT = Dynamic COMMENT='用户角色关联表';
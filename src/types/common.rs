@@ -1,5 +1,13 @@
+use crate::error::error::AppError;
+use crate::models::sea_orm_active_enums::UserStatus;
+use crate::utils::cursor::cursor_paginate;
+use sea_orm::sea_query::IntoCondition;
+use sea_orm::{
+    ConnectionTrait, EntityTrait, FromQueryResult, IdenStatic, Iterable, Order, PaginatorTrait,
+    PrimaryKeyToColumn, QueryFilter, QueryOrder, QuerySelect, Select,
+};
 use serde::{Deserialize, Serialize};
-use utoipa::ToSchema;
+use utoipa::{IntoParams, ToSchema};
 use validator::Validate;
 
 #[derive(Debug, Serialize, ToSchema, Deserialize, Clone)]
@@ -21,7 +29,9 @@ pub struct PaginationInfo {
 
 pub const DEFAULT_PAGE_SIZE: u64 = 10;
 pub const MAX_PAGE_SIZE: u64 = 100;
-#[derive(Validate, Debug, Clone, Serialize, Deserialize, ToSchema)]
+// 批量操作一次最多处理的记录数
+pub const MAX_BATCH_SIZE: u64 = 100;
+#[derive(Validate, Debug, Clone, Serialize, Deserialize, ToSchema, IntoParams)]
 pub struct PaginationQuery {
     #[serde(default = "default_page")]
     #[validate(range(min = 1, message = "页码必须大于1"))]
@@ -30,6 +40,22 @@ pub struct PaginationQuery {
     #[serde(default = "default_size")]
     #[validate(range(max = MAX_PAGE_SIZE, message = "每页数量不能超过100"))]
     pub limit: Option<u64>,
+    // 传入游标（首页传空字符串）时使用游标分页，忽略 page 且不返回总数
+    pub cursor: Option<String>,
+}
+
+impl PageQuery for PaginationQuery {
+    fn page(&self) -> Option<u64> {
+        self.page
+    }
+
+    fn limit(&self) -> Option<u64> {
+        self.limit
+    }
+
+    fn cursor(&self) -> Option<&str> {
+        self.cursor.as_deref()
+    }
 }
 
 // 添加默认函数实现
@@ -41,12 +67,209 @@ fn default_size() -> Option<u64> {
     Some(DEFAULT_PAGE_SIZE)
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 pub struct PaginatedResponse<T> {
     pub data: Vec<T>,
     pub pagination: PaginationInfo,
 }
 
+// 游标分页不统计总数，通过 next_cursor/prev_cursor 翻页
+#[derive(Serialize, ToSchema)]
+pub struct CursorPaginationInfo {
+    pub limit: u64,
+    pub has_next: bool,
+    pub has_previous: bool,
+    pub next_cursor: Option<String>,
+    pub prev_cursor: Option<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct CursorPaginatedResponse<T> {
+    pub data: Vec<T>,
+    pub pagination: CursorPaginationInfo,
+}
+
+// 批量操作中单条记录的处理结果
+#[derive(Debug, Serialize, ToSchema)]
+pub struct BatchItemResult {
+    pub id: String,
+    pub success: bool,
+    // 失败原因
+    pub message: Option<String>,
+}
+
+#[derive(Debug, Default, Serialize, ToSchema)]
+pub struct BatchResult {
+    pub succeeded: usize,
+    pub failed: usize,
+    pub items: Vec<BatchItemResult>,
+}
+
+impl BatchResult {
+    pub fn ok(&mut self, id: impl ToString) {
+        self.succeeded += 1;
+        self.items.push(BatchItemResult {
+            id: id.to_string(),
+            success: true,
+            message: None,
+        });
+    }
+
+    pub fn fail(&mut self, id: impl ToString, message: impl Into<String>) {
+        self.failed += 1;
+        self.items.push(BatchItemResult {
+            id: id.to_string(),
+            success: false,
+            message: Some(message.into()),
+        });
+    }
+}
+
+// 去掉重复的标识，保持原有顺序
+pub fn dedup_ids<T: Clone + PartialEq>(ids: &[T]) -> Vec<T> {
+    let mut unique: Vec<T> = Vec::with_capacity(ids.len());
+    for id in ids {
+        if !unique.contains(id) {
+            unique.push(id.clone());
+        }
+    }
+    unique
+}
+
+// 列表接口的返回：默认按页码分页，传入 cursor 时为游标分页
+#[derive(Serialize, ToSchema)]
+#[serde(untagged)]
+pub enum ListPage<T> {
+    Offset(PaginatedResponse<T>),
+    Cursor(CursorPaginatedResponse<T>),
+}
+
+impl<T> ListPage<T> {
+    pub fn map<U>(self, f: impl FnMut(T) -> U) -> ListPage<U> {
+        match self {
+            ListPage::Offset(page) => ListPage::Offset(PaginatedResponse {
+                data: page.data.into_iter().map(f).collect(),
+                pagination: page.pagination,
+            }),
+            ListPage::Cursor(page) => ListPage::Cursor(CursorPaginatedResponse {
+                data: page.data.into_iter().map(f).collect(),
+                pagination: page.pagination,
+            }),
+        }
+    }
+}
+
+// 列表查询参数中的分页部分，由 paginated_query! 生成的结构体实现
+pub trait PageQuery: Validate {
+    fn page(&self) -> Option<u64>;
+    fn limit(&self) -> Option<u64>;
+    fn cursor(&self) -> Option<&str>;
+}
+
+// 每页数量为 0 时使用默认值，超过上限时取上限
+pub fn clamp_limit(limit: Option<u64>) -> u64 {
+    match limit.unwrap_or(DEFAULT_PAGE_SIZE) {
+        0 => DEFAULT_PAGE_SIZE,
+        limit => limit.min(MAX_PAGE_SIZE),
+    }
+}
+
+// 通用列表分页：声明筛选条件和排序字段后，按查询参数选择页码分页或游标分页
+pub struct Paginator<E: EntityTrait> {
+    select: Select<E>,
+    sort: Option<E::Column>,
+    order: SortOrder,
+}
+
+impl<E> Paginator<E>
+where
+    E: EntityTrait,
+    E::Model: FromQueryResult + Sized + Send + Sync,
+{
+    // 默认按主键倒序
+    pub fn new(select: Select<E>) -> Self {
+        Paginator {
+            select,
+            sort: None,
+            order: SortOrder::Desc,
+        }
+    }
+
+    pub fn filter<F: IntoCondition>(mut self, condition: F) -> Self {
+        self.select = self.select.filter(condition);
+        self
+    }
+
+    // 排序字段相同时按主键保证顺序稳定
+    pub fn sort_by(mut self, column: E::Column, order: SortOrder) -> Self {
+        self.sort = Some(column);
+        self.order = order;
+        self
+    }
+
+    fn sort_columns(&self) -> Vec<E::Column> {
+        let primary_keys: Vec<E::Column> =
+            E::PrimaryKey::iter().map(|pk| pk.into_column()).collect();
+        self.sort
+            .filter(|column| !primary_keys.iter().any(|pk| pk.as_str() == column.as_str()))
+            .into_iter()
+            .chain(primary_keys)
+            .collect()
+    }
+
+    pub async fn fetch<C, Q>(self, db: &C, query: &Q) -> Result<ListPage<E::Model>, AppError>
+    where
+        C: ConnectionTrait,
+        Q: PageQuery,
+    {
+        query.validate().map_err(|e| {
+            log::info!("分页参数验证失败: {:?}", e);
+            AppError::from(e)
+        })?;
+        let limit = clamp_limit(query.limit());
+        let columns = self.sort_columns();
+
+        if let Some(cursor) = query.cursor() {
+            let (data, pagination) =
+                cursor_paginate(db, self.select, &columns, self.order, cursor, limit).await?;
+            return Ok(ListPage::Cursor(CursorPaginatedResponse {
+                data,
+                pagination,
+            }));
+        }
+
+        let page = query.page().unwrap_or(1).max(1);
+        let order = match self.order {
+            SortOrder::Asc => Order::Asc,
+            SortOrder::Desc => Order::Desc,
+        };
+        let rows = columns
+            .into_iter()
+            .fold(self.select.clone(), |select, column| {
+                select.order_by(column, order.clone())
+            })
+            .offset(Some((page - 1) * limit))
+            .limit(Some(limit));
+        let (total, data) = tokio::try_join!(self.select.count(db), rows.all(db)).map_err(|e| {
+            log::error!("数据库操作获取列表失败: {}", e);
+            AppError::DatabaseError("服务器异常，请联系管理员".to_string())
+        })?;
+        let total_pages = total.div_ceil(limit); // 整数除法避免浮点误差
+
+        Ok(ListPage::Offset(PaginatedResponse {
+            data,
+            pagination: PaginationInfo {
+                total,
+                total_pages,
+                current_page: page,
+                limit,
+                has_next: page < total_pages,
+                has_previous: page > 1,
+            },
+        }))
+    }
+}
+
 // #[macro_export]
 // 定义宏来生成包含分页参数的结构体
 macro_rules! paginated_query {
@@ -62,11 +285,28 @@ macro_rules! paginated_query {
             #[validate(range(max = MAX_PAGE_SIZE, message = "每页数量不能超过100"))]
             pub limit: Option<u64>,
 
+            // 游标分页参数，首页传空字符串
+            pub cursor: Option<String>,
+
             // 自定义字段
             $(
                 pub $field: $type,
             )*
         }
+
+        impl PageQuery for $struct_name {
+            fn page(&self) -> Option<u64> {
+                self.page
+            }
+
+            fn limit(&self) -> Option<u64> {
+                self.limit
+            }
+
+            fn cursor(&self) -> Option<&str> {
+                self.cursor.as_deref()
+            }
+        }
     };
 }
 
@@ -77,3 +317,101 @@ paginated_query!(CategoryQuery {
 paginated_query!(TagsQuery {
     select: Option<String>
 });
+
+// 排序方向
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum SortOrder {
+    Asc,
+    #[default]
+    Desc,
+}
+
+// 用户列表可排序的字段
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum UserSortField {
+    #[default]
+    Id,
+    CreatedAt,
+    UpdatedAt,
+}
+
+// 管理员用户列表的搜索和筛选条件，时间使用 RFC 3339 格式
+paginated_query!(UserListQuery {
+    keyword: Option<String>,
+    role: Option<String>,
+    permission: Option<String>,
+    two_factor: Option<bool>,
+    status: Option<UserStatus>,
+    created_from: Option<String>,
+    created_to: Option<String>,
+    sort_by: Option<UserSortField>,
+    order: Option<SortOrder>,
+});
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::user;
+
+    #[test]
+    fn limit_is_clamped_to_allowed_range() {
+        assert_eq!(clamp_limit(None), DEFAULT_PAGE_SIZE);
+        assert_eq!(clamp_limit(Some(0)), DEFAULT_PAGE_SIZE);
+        assert_eq!(clamp_limit(Some(20)), 20);
+        assert_eq!(clamp_limit(Some(1000)), MAX_PAGE_SIZE);
+    }
+
+    fn column_names(paginator: &Paginator<user::Entity>) -> Vec<String> {
+        paginator
+            .sort_columns()
+            .iter()
+            .map(|c| c.as_str().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn primary_key_is_appended_to_sort_columns_once() {
+        let paginator = Paginator::new(user::Entity::find());
+        assert_eq!(column_names(&paginator), vec!["id"]);
+
+        let paginator = paginator.sort_by(user::Column::CreatedAt, SortOrder::Asc);
+        assert_eq!(column_names(&paginator), vec!["created_at", "id"]);
+        let paginator = paginator.sort_by(user::Column::Id, SortOrder::Asc);
+        assert_eq!(column_names(&paginator), vec!["id"]);
+    }
+
+    #[test]
+    fn list_page_serializes_without_variant_tag() {
+        let page = ListPage::Offset(PaginatedResponse {
+            data: vec![1, 2],
+            pagination: PaginationInfo {
+                total: 2,
+                total_pages: 1,
+                current_page: 1,
+                limit: 10,
+                has_next: false,
+                has_previous: false,
+            },
+        })
+        .map(|n| n * 10);
+        let json = serde_json::to_value(&page).unwrap();
+        assert_eq!(json["data"], serde_json::json!([10, 20]));
+        assert_eq!(json["pagination"]["total"], 2);
+    }
+
+    #[test]
+    fn batch_result_counts_items_and_ids_are_deduped() {
+        assert_eq!(dedup_ids(&["b", "a", "b", "c", "a"]), vec!["b", "a", "c"]);
+
+        let mut result = BatchResult::default();
+        result.ok(1);
+        result.fail(2, "标签仍被 3 篇文章使用");
+        let json = serde_json::to_value(&result).unwrap();
+        assert_eq!(json["succeeded"], 1);
+        assert_eq!(json["failed"], 1);
+        assert_eq!(json["items"][0]["message"], serde_json::Value::Null);
+        assert_eq!(json["items"][1]["id"], "2");
+    }
+}
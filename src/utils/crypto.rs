@@ -0,0 +1,96 @@
+use crate::config::two_factor::TWO_FACTOR_CONFIG;
+use crate::AppError;
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use base64::engine::general_purpose;
+use base64::engine::Engine as _;
+use sha2::{Digest, Sha256};
+
+// 加密后的密钥前缀，用于区分历史遗留的明文数据
+const ENCRYPTED_PREFIX: &str = "enc:v1:";
+const NONCE_LEN: usize = 12;
+
+fn cipher() -> Aes256Gcm {
+    Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(
+        &TWO_FACTOR_CONFIG.encryption_key,
+    ))
+}
+
+// 使用 AES-256-GCM 加密敏感字段，输出 前缀 + base64(nonce || 密文)
+pub fn encrypt_secret(plain: &str) -> Result<String, AppError> {
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    let ciphertext = cipher().encrypt(&nonce, plain.as_bytes()).map_err(|e| {
+        log::error!("加密密钥失败: {}", e);
+        AppError::InternalServerError("加密失败".into())
+    })?;
+
+    let mut payload = nonce.to_vec();
+    payload.extend_from_slice(&ciphertext);
+    Ok(format!(
+        "{}{}",
+        ENCRYPTED_PREFIX,
+        general_purpose::STANDARD.encode(payload)
+    ))
+}
+
+// 是否为 encrypt_secret 的输出，历史遗留的明文数据没有前缀
+pub fn is_encrypted(stored: &str) -> bool {
+    stored.starts_with(ENCRYPTED_PREFIX)
+}
+
+// 解密 encrypt_secret 的输出；没有前缀的旧数据按明文原样返回，
+// 在下次验证码校验通过后由 reencrypt_legacy_binding 改为加密保存
+pub fn decrypt_secret(stored: &str) -> Result<String, AppError> {
+    let Some(encoded) = stored.strip_prefix(ENCRYPTED_PREFIX) else {
+        return Ok(stored.to_string());
+    };
+
+    let payload = general_purpose::STANDARD.decode(encoded).map_err(|e| {
+        log::error!("密钥格式错误: {}", e);
+        AppError::InternalServerError("解密失败".into())
+    })?;
+    if payload.len() <= NONCE_LEN {
+        return Err(AppError::InternalServerError("解密失败".into()));
+    }
+
+    let (nonce, ciphertext) = payload.split_at(NONCE_LEN);
+    let plain = cipher()
+        .decrypt(Nonce::from_slice(nonce), ciphertext)
+        .map_err(|e| {
+            log::error!("解密密钥失败: {}", e);
+            AppError::InternalServerError("解密失败".into())
+        })?;
+    String::from_utf8(plain).map_err(|_| AppError::InternalServerError("解密失败".into()))
+}
+
+// 计算SHA-256摘要并输出十六进制字符串，用于保存一次性令牌和API密钥
+pub fn sha256_hex(value: &str) -> String {
+    Sha256::digest(value.as_bytes())
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn secrets_round_trip_and_legacy_plaintext_is_detected() {
+        let stored = encrypt_secret("JBSWY3DPEHPK3PXP").unwrap();
+        assert!(is_encrypted(&stored));
+        assert_ne!(stored, encrypt_secret("JBSWY3DPEHPK3PXP").unwrap());
+        assert_eq!(decrypt_secret(&stored).unwrap(), "JBSWY3DPEHPK3PXP");
+
+        assert!(!is_encrypted("JBSWY3DPEHPK3PXP"));
+        assert_eq!(
+            decrypt_secret("JBSWY3DPEHPK3PXP").unwrap(),
+            "JBSWY3DPEHPK3PXP"
+        );
+
+        // 篡改密文后无法解密
+        let mut tampered = stored.clone();
+        tampered.replace_range(stored.len() - 4.., "AAAA");
+        assert!(decrypt_secret(&tampered).is_err());
+    }
+}
@@ -0,0 +1,75 @@
+use crate::{config::permission::Permission, jsonwebtoken::TokenClaims, AppError};
+use actix_web::{dev::Payload, FromRequest, HttpMessage, HttpRequest};
+use std::future::{ready, Ready};
+
+// 认证中间件写入请求扩展的当前用户，处理函数和守卫直接读取，不再重复解析令牌
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CurrentUser {
+    pub uuid: String,
+    pub name: String,
+    pub permissions: Permission,
+    // JWT 的 jti，API密钥请求为 "api_key:<id>"
+    pub token_id: Option<String>,
+}
+
+impl CurrentUser {
+    pub fn has(&self, permission: Permission) -> bool {
+        self.permissions.contains(permission)
+    }
+}
+
+impl From<TokenClaims> for CurrentUser {
+    fn from(claims: TokenClaims) -> Self {
+        CurrentUser {
+            permissions: Permission::from_stored(claims.permissions.as_deref()),
+            uuid: claims.user_uuid,
+            name: claims.user_name,
+            token_id: claims.jti,
+        }
+    }
+}
+
+impl FromRequest for CurrentUser {
+    type Error = AppError;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        ready(
+            req.extensions()
+                .get::<CurrentUser>()
+                .cloned()
+                .ok_or_else(|| AppError::Unauthorized("请求未包含认证Token".into())),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::test::TestRequest;
+
+    #[actix_web::test]
+    async fn extractor_reads_user_from_extensions() {
+        let user = CurrentUser {
+            uuid: "u1".to_string(),
+            name: "tester".to_string(),
+            permissions: Permission::READ_ARTICLE,
+            token_id: Some("jti".to_string()),
+        };
+        let req = TestRequest::default().to_http_request();
+        req.extensions_mut().insert(user.clone());
+        let extracted = CurrentUser::extract(&req).await.unwrap();
+        assert_eq!(extracted, user);
+        assert!(extracted.has(Permission::READ_ARTICLE));
+        assert!(!extracted.has(Permission::READ_WRITE_ARTICLE));
+
+        // 未经过认证中间件时不会再解析请求头
+        let req = TestRequest::default()
+            .insert_header(("Authorization", "Bearer invalid"))
+            .to_http_request();
+        assert!(matches!(
+            CurrentUser::extract(&req).await,
+            Err(AppError::Unauthorized(_))
+        ));
+    }
+}
@@ -0,0 +1,280 @@
+use crate::common::{CursorPaginationInfo, SortOrder};
+use crate::AppError;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::engine::Engine as _;
+use chrono::{DateTime, NaiveDateTime, Utc};
+use sea_orm::sea_query::{DynIden, SeaRc, ValueTuple};
+use sea_orm::{
+    ConnectionTrait, EntityTrait, FromQueryResult, IdenStatic, Identity, ModelTrait, Select, Value,
+};
+use serde::{Deserialize, Serialize};
+
+// 翻页方向：向后取下一页，或向前取上一页
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CursorDirection {
+    #[serde(rename = "n")]
+    Next,
+    #[serde(rename = "p")]
+    Prev,
+}
+
+// 游标中保存的排序字段值，只支持可以比较大小且不为空的类型
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CursorValue {
+    #[serde(rename = "i")]
+    Int(i64),
+    #[serde(rename = "u")]
+    Unsigned(u64),
+    #[serde(rename = "s")]
+    Text(String),
+    #[serde(rename = "t")]
+    TimeUtc(DateTime<Utc>),
+    #[serde(rename = "n")]
+    Time(NaiveDateTime),
+}
+
+impl TryFrom<Value> for CursorValue {
+    type Error = AppError;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::TinyInt(Some(v)) => Ok(CursorValue::Int(v.into())),
+            Value::SmallInt(Some(v)) => Ok(CursorValue::Int(v.into())),
+            Value::Int(Some(v)) => Ok(CursorValue::Int(v.into())),
+            Value::BigInt(Some(v)) => Ok(CursorValue::Int(v)),
+            Value::TinyUnsigned(Some(v)) => Ok(CursorValue::Unsigned(v.into())),
+            Value::SmallUnsigned(Some(v)) => Ok(CursorValue::Unsigned(v.into())),
+            Value::Unsigned(Some(v)) => Ok(CursorValue::Unsigned(v.into())),
+            Value::BigUnsigned(Some(v)) => Ok(CursorValue::Unsigned(v)),
+            Value::String(Some(v)) => Ok(CursorValue::Text(*v)),
+            Value::ChronoDateTimeUtc(Some(v)) => Ok(CursorValue::TimeUtc(*v)),
+            Value::ChronoDateTime(Some(v)) => Ok(CursorValue::Time(*v)),
+            other => {
+                log::error!("不支持作为游标的排序字段值: {:?}", other);
+                Err(AppError::InternalServerError("生成分页游标失败".into()))
+            }
+        }
+    }
+}
+
+impl From<CursorValue> for Value {
+    fn from(value: CursorValue) -> Self {
+        match value {
+            CursorValue::Int(v) => v.into(),
+            CursorValue::Unsigned(v) => v.into(),
+            CursorValue::Text(v) => v.into(),
+            CursorValue::TimeUtc(v) => v.into(),
+            CursorValue::Time(v) => v.into(),
+        }
+    }
+}
+
+// 不透明游标：记录当前页边界行的排序键，客户端原样传回即可
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PageCursor {
+    // 生成游标时的排序方式，排序变化后旧游标失效
+    #[serde(rename = "o")]
+    pub sort: String,
+    #[serde(rename = "d")]
+    pub direction: CursorDirection,
+    #[serde(rename = "k")]
+    pub key: Vec<CursorValue>,
+}
+
+impl PageCursor {
+    pub fn encode(&self) -> String {
+        // 只包含基础类型，序列化不会失败
+        URL_SAFE_NO_PAD.encode(serde_json::to_vec(self).unwrap_or_default())
+    }
+
+    pub fn decode(raw: &str) -> Result<Self, AppError> {
+        URL_SAFE_NO_PAD
+            .decode(raw)
+            .ok()
+            .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+            .ok_or_else(|| AppError::BadRequest("无效的分页游标".into()))
+    }
+}
+
+// 游标分页：排序字段的最后一个必须唯一（通常是主键），保证翻页时不重复不遗漏。
+// cursor 为空字符串表示以游标方式获取第一页
+pub async fn cursor_paginate<E, C>(
+    db: &C,
+    select: Select<E>,
+    columns: &[E::Column],
+    order: SortOrder,
+    cursor: &str,
+    limit: u64,
+) -> Result<(Vec<E::Model>, CursorPaginationInfo), AppError>
+where
+    E: EntityTrait,
+    E::Model: FromQueryResult + Sized + Send + Sync,
+    C: ConnectionTrait,
+{
+    let sort = sort_signature(columns, order);
+    let cursor = match cursor {
+        "" => None,
+        raw => Some(PageCursor::decode(raw)?),
+    };
+    if let Some(cursor) = &cursor {
+        if cursor.sort != sort || cursor.key.len() != columns.len() {
+            return Err(AppError::BadRequest("分页游标与当前排序方式不匹配".into()));
+        }
+    }
+
+    let identity = Identity::Many(columns.iter().map(|c| SeaRc::new(*c) as DynIden).collect());
+    let mut query = select.cursor_by(identity);
+    if order == SortOrder::Desc {
+        query.desc();
+    }
+    // 多取一行用来判断这个方向上是否还有数据
+    let direction = match &cursor {
+        Some(c) => {
+            let key = ValueTuple::Many(c.key.iter().cloned().map(Value::from).collect());
+            match c.direction {
+                CursorDirection::Next => query.after(key).first(limit + 1),
+                CursorDirection::Prev => query.before(key).last(limit + 1),
+            };
+            Some(c.direction)
+        }
+        None => {
+            query.first(limit + 1);
+            None
+        }
+    };
+    let rows = query.all(db).await?;
+
+    build_page(rows, limit, direction, &sort, |model| {
+        columns
+            .iter()
+            .map(|c| CursorValue::try_from(model.get(*c)))
+            .collect()
+    })
+}
+
+fn sort_signature<T: IdenStatic>(columns: &[T], order: SortOrder) -> String {
+    let columns: Vec<&str> = columns.iter().map(|c| c.as_str()).collect();
+    format!("{}:{:?}", columns.join(","), order)
+}
+
+// 根据多取的一行裁剪结果并生成前后游标，direction 为空表示第一页
+fn build_page<M, F>(
+    mut rows: Vec<M>,
+    limit: u64,
+    direction: Option<CursorDirection>,
+    sort: &str,
+    key: F,
+) -> Result<(Vec<M>, CursorPaginationInfo), AppError>
+where
+    F: Fn(&M) -> Result<Vec<CursorValue>, AppError>,
+{
+    let has_more = rows.len() as u64 > limit;
+    let (has_next, has_previous) = match direction {
+        Some(CursorDirection::Prev) => {
+            if has_more {
+                rows.remove(0);
+            }
+            (true, has_more)
+        }
+        Some(CursorDirection::Next) | None => {
+            rows.truncate(limit as usize);
+            (has_more, direction.is_some())
+        }
+    };
+
+    let make_cursor = |row: Option<&M>, direction| -> Result<Option<String>, AppError> {
+        row.map(|row| {
+            Ok(PageCursor {
+                sort: sort.to_string(),
+                direction,
+                key: key(row)?,
+            }
+            .encode())
+        })
+        .transpose()
+    };
+    let next_cursor = match has_next {
+        true => make_cursor(rows.last(), CursorDirection::Next)?,
+        false => None,
+    };
+    let prev_cursor = match has_previous {
+        true => make_cursor(rows.first(), CursorDirection::Prev)?,
+        false => None,
+    };
+
+    let pagination = CursorPaginationInfo {
+        limit,
+        has_next: next_cursor.is_some(),
+        has_previous: prev_cursor.is_some(),
+        next_cursor,
+        prev_cursor,
+    };
+    Ok((rows, pagination))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(row: &i32) -> Result<Vec<CursorValue>, AppError> {
+        Ok(vec![CursorValue::Int((*row).into())])
+    }
+
+    fn decoded(raw: &Option<String>) -> PageCursor {
+        PageCursor::decode(raw.as_deref().unwrap()).unwrap()
+    }
+
+    #[test]
+    fn cursor_round_trips_and_rejects_garbage() {
+        let cursor = PageCursor {
+            sort: "created_at,id:Desc".to_string(),
+            direction: CursorDirection::Next,
+            key: vec![
+                CursorValue::TimeUtc(Utc::now()),
+                CursorValue::Int(42),
+                CursorValue::Text("a".into()),
+            ],
+        };
+        assert_eq!(PageCursor::decode(&cursor.encode()).unwrap(), cursor);
+        assert!(matches!(
+            PageCursor::decode("not-a-cursor"),
+            Err(AppError::BadRequest(_))
+        ));
+        assert!(CursorValue::try_from(Value::Int(None)).is_err());
+    }
+
+    #[test]
+    fn first_page_only_has_next_cursor() {
+        let (rows, info) = build_page(vec![10, 9, 8], 2, None, "id:Desc", key).unwrap();
+        assert_eq!(rows, vec![10, 9]);
+        assert!(info.has_next && !info.has_previous);
+        let next = decoded(&info.next_cursor);
+        assert_eq!(next.direction, CursorDirection::Next);
+        assert_eq!(next.key, vec![CursorValue::Int(9)]);
+
+        // 最后一页没有下一页
+        let (_, info) = build_page(vec![10], 2, None, "id:Desc", key).unwrap();
+        assert!(info.next_cursor.is_none() && info.prev_cursor.is_none());
+    }
+
+    #[test]
+    fn backward_page_drops_extra_leading_row() {
+        let (rows, info) = build_page(
+            vec![7, 6, 5],
+            2,
+            Some(CursorDirection::Prev),
+            "id:Desc",
+            key,
+        )
+        .unwrap();
+        assert_eq!(rows, vec![6, 5]);
+        assert_eq!(decoded(&info.prev_cursor).key, vec![CursorValue::Int(6)]);
+        assert_eq!(decoded(&info.next_cursor).key, vec![CursorValue::Int(5)]);
+
+        // 已经回到第一页
+        let (rows, info) =
+            build_page(vec![6, 5], 2, Some(CursorDirection::Prev), "id:Desc", key).unwrap();
+        assert_eq!(rows, vec![6, 5]);
+        assert!(info.prev_cursor.is_none() && info.has_next);
+    }
+}
@@ -1,43 +1,411 @@
-use log::*;
-use serde_json::Value;
-use std::collections::HashSet;
+use serde_json::{Map, Value};
 
-pub fn deep_filter_data<T>(data: Vec<T>, exclude: Vec<&str>) -> Vec<Value>
+// 字段路径：用 . 分隔层级，* 匹配对象的任意字段，[*] 匹配数组的任意元素，[n] 匹配第 n 个元素
+// 例如 "author.pass_word"、"items[*].secret"、"tags[*].id"
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Segment {
+    Key(String),
+    AnyKey,
+    Index(usize),
+    AnyIndex,
+}
+
+impl Segment {
+    fn matches_key(&self, key: &str) -> bool {
+        match self {
+            Segment::Key(k) => k == key,
+            Segment::AnyKey => true,
+            Segment::Index(_) | Segment::AnyIndex => false,
+        }
+    }
+
+    fn matches_index(&self, index: usize) -> bool {
+        match self {
+            Segment::Index(i) => *i == index,
+            Segment::AnyIndex => true,
+            Segment::Key(_) | Segment::AnyKey => false,
+        }
+    }
+}
+
+fn parse_path(path: &str) -> Vec<Segment> {
+    let mut segments = Vec::new();
+    for part in path.split('.').filter(|p| !p.is_empty()) {
+        let (name, mut rest) = match part.find('[') {
+            Some(pos) => part.split_at(pos),
+            None => (part, ""),
+        };
+        let mut indexes = Vec::new();
+        while let Some(inner) = rest.strip_prefix('[').and_then(|r| r.split_once(']')) {
+            let segment = match inner.0 {
+                "*" => Segment::AnyIndex,
+                n => match n.parse() {
+                    Ok(i) => Segment::Index(i),
+                    Err(_) => break,
+                },
+            };
+            indexes.push(segment);
+            rest = inner.1;
+        }
+        // 无法识别的下标按普通字段名处理
+        if !rest.is_empty() {
+            segments.push(Segment::Key(part.to_string()));
+            continue;
+        }
+        match name {
+            "" => {}
+            "*" => segments.push(Segment::AnyKey),
+            _ => segments.push(Segment::Key(name.to_string())),
+        }
+        segments.extend(indexes);
+    }
+    segments
+}
+
+// 所有路径合并成的前缀树，同一层可能同时命中多个分支（例如 a.b 和 *.c）
+#[derive(Debug, Default)]
+struct PathTree {
+    terminal: bool,
+    children: Vec<(Segment, PathTree)>,
+}
+
+impl PathTree {
+    fn build(paths: &[&str]) -> Self {
+        let mut root = PathTree::default();
+        for path in paths {
+            let segments = parse_path(path);
+            if segments.is_empty() {
+                continue;
+            }
+            let mut node = &mut root;
+            for segment in segments {
+                let pos = match node.children.iter().position(|(s, _)| *s == segment) {
+                    Some(pos) => pos,
+                    None => {
+                        node.children.push((segment, PathTree::default()));
+                        node.children.len() - 1
+                    }
+                };
+                node = &mut node.children[pos].1;
+            }
+            node.terminal = true;
+        }
+        root
+    }
+
+    fn has_index_children(&self) -> bool {
+        self.children
+            .iter()
+            .any(|(s, _)| matches!(s, Segment::Index(_) | Segment::AnyIndex))
+    }
+}
+
+fn next_for_key<'a>(nodes: &[&'a PathTree], key: &str) -> Vec<&'a PathTree> {
+    nodes
+        .iter()
+        .flat_map(|n| n.children.iter())
+        .filter(|(s, _)| s.matches_key(key))
+        .map(|(_, child)| child)
+        .collect()
+}
+
+fn next_for_index<'a>(nodes: &[&'a PathTree], index: usize) -> Vec<&'a PathTree> {
+    nodes
+        .iter()
+        .flat_map(|n| n.children.iter())
+        .filter(|(s, _)| s.matches_index(index))
+        .map(|(_, child)| child)
+        .collect()
+}
+
+fn exclude_paths(value: &mut Value, nodes: &[&PathTree]) {
+    match value {
+        Value::Object(map) => {
+            map.retain(|key, v| {
+                let next = next_for_key(nodes, key);
+                if next.iter().any(|n| n.terminal) {
+                    return false;
+                }
+                if !next.is_empty() {
+                    exclude_paths(v, &next);
+                }
+                true
+            });
+        }
+        Value::Array(arr) => {
+            let mut index = 0;
+            arr.retain_mut(|v| {
+                let next = next_for_index(nodes, index);
+                index += 1;
+                if next.iter().any(|n| n.terminal) {
+                    return false;
+                }
+                if !next.is_empty() {
+                    exclude_paths(v, &next);
+                }
+                true
+            });
+        }
+        _ => {}
+    }
+}
+
+fn select_paths(value: Value, nodes: &[&PathTree]) -> Option<Value> {
+    if nodes.iter().any(|n| n.terminal) {
+        return Some(value);
+    }
+    match value {
+        Value::Object(map) => {
+            let selected: Map<String, Value> = map
+                .into_iter()
+                .filter_map(|(key, v)| {
+                    let next = next_for_key(nodes, &key);
+                    if next.is_empty() {
+                        return None;
+                    }
+                    select_paths(v, &next).map(|v| (key, v))
+                })
+                .collect();
+            Some(Value::Object(selected))
+        }
+        Value::Array(arr) => Some(Value::Array(
+            arr.into_iter()
+                .enumerate()
+                .filter_map(|(index, v)| {
+                    let next = next_for_index(nodes, index);
+                    if next.is_empty() {
+                        return None;
+                    }
+                    select_paths(v, &next)
+                })
+                .collect(),
+        )),
+        // 路径还没走完就遇到了普通值，说明该路径不存在
+        _ => None,
+    }
+}
+
+// 列表数据逐条过滤，等同于对每个元素调用 filter_value
+pub fn deep_filter_data<T>(data: Vec<T>, exclude: &[&str]) -> Vec<Value>
 where
-    T: Into<Value> + TryFrom<Value>,
+    T: Into<Value>,
 {
     data.into_iter()
-        .map(|item| {
-            // 将输入类型转换为 Value
-            let value: Value = item.into();
-            // 过滤处理
-            filter_value(value, exclude.clone())
-        })
+        .map(|item| filter_value(item.into(), exclude))
         .collect()
 }
 
-pub fn filter_value(value: Value, exclude: Vec<&str>) -> Value {
-    let sensitive_fields: HashSet<String> = exclude.into_iter().map(String::from).collect();
-    match value {
-        Value::Object(mut map) => {
-            // 过滤当前层
-            for field in &sensitive_fields {
-                error!("exclude field: {}", field);
-                map.remove(field);
+// 按路径删除字段，顶层是数组且路径没有指定下标时对每个元素生效
+pub fn filter_value(mut value: Value, exclude: &[&str]) -> Value {
+    let tree = PathTree::build(exclude);
+    match &mut value {
+        Value::Array(arr) if !tree.has_index_children() => {
+            for item in arr.iter_mut() {
+                exclude_paths(item, &[&tree]);
             }
-
-            // 递归处理嵌套结构
-            // for (_, v) in map.iter_mut() {
-            //     *v = filter_value(v.clone(), exclude);
-            // }
-            error!("map: {:?}", map);
-            Value::Object(map)
         }
-        Value::Array(arr) => Value::Array(
+        _ => exclude_paths(&mut value, &[&tree]),
+    }
+    value
+}
+
+// 白名单模式：只保留路径命中的字段，不存在的路径会被忽略
+pub fn select_value(value: Value, include: &[&str]) -> Value {
+    let tree = PathTree::build(include);
+    match value {
+        Value::Array(arr) if !tree.has_index_children() => Value::Array(
             arr.into_iter()
-                .map(|v| filter_value(v, sensitive_fields.iter().map(|s| s.as_str()).collect()))
+                .filter_map(|item| select_paths(item, &[&tree]))
                 .collect(),
         ),
-        _ => value,
+        value => select_paths(value, &[&tree]).unwrap_or(Value::Null),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+    use serde_json::json;
+
+    fn article() -> Value {
+        json!({
+            "uuid": "a1",
+            "title": "hello",
+            "author": { "user_name": "tester", "pass_word": "hash" },
+            "category": { "id": 1, "name": "rust" },
+            "tags": [
+                { "id": 1, "name": "web", "secret": "x" },
+                { "id": 2, "name": "db", "secret": "y" }
+            ]
+        })
+    }
+
+    #[test]
+    fn parses_dotted_paths_and_wildcards() {
+        assert_eq!(
+            parse_path("items[*].secret"),
+            vec![
+                Segment::Key("items".into()),
+                Segment::AnyIndex,
+                Segment::Key("secret".into())
+            ]
+        );
+        assert_eq!(
+            parse_path("[0].*"),
+            vec![Segment::Index(0), Segment::AnyKey]
+        );
+        // 无法识别的下标作为普通字段名
+        assert_eq!(parse_path("a[x]"), vec![Segment::Key("a[x]".into())]);
+    }
+
+    #[test]
+    fn excludes_nested_relation_fields() {
+        let value = filter_value(
+            article(),
+            &["author.pass_word", "tags[*].secret", "category.id"],
+        );
+        assert_eq!(
+            value,
+            json!({
+                "uuid": "a1",
+                "title": "hello",
+                "author": { "user_name": "tester" },
+                "category": { "name": "rust" },
+                "tags": [
+                    { "id": 1, "name": "web" },
+                    { "id": 2, "name": "db" }
+                ]
+            })
+        );
+        // 顶层数组对每个元素生效
+        let list = filter_value(json!([article(), article()]), &["author", "tags"]);
+        assert_eq!(
+            list[1],
+            json!({"uuid": "a1", "title": "hello", "category": { "id": 1, "name": "rust" }})
+        );
+    }
+
+    #[test]
+    fn selects_only_allowed_paths() {
+        let value = select_value(article(), &["uuid", "author.user_name", "tags[*].name"]);
+        assert_eq!(
+            value,
+            json!({
+                "uuid": "a1",
+                "author": { "user_name": "tester" },
+                "tags": [{ "name": "web" }, { "name": "db" }]
+            })
+        );
+        assert_eq!(
+            select_value(article(), &["tags[1].id"])["tags"],
+            json!([{ "id": 2 }])
+        );
+    }
+
+    fn json_value() -> impl Strategy<Value = Value> {
+        let leaf = prop_oneof![
+            Just(Value::Null),
+            any::<bool>().prop_map(Value::Bool),
+            any::<i64>().prop_map(|n| json!(n)),
+            "[a-z]{0,4}".prop_map(Value::String),
+        ];
+        leaf.prop_recursive(4, 32, 4, |inner| {
+            prop_oneof![
+                prop::collection::vec(inner.clone(), 0..4).prop_map(Value::Array),
+                prop::collection::btree_map("[a-d]", inner, 0..4)
+                    .prop_map(|m| Value::Object(m.into_iter().collect())),
+            ]
+        })
+    }
+
+    fn path() -> impl Strategy<Value = String> {
+        prop::collection::vec(
+            prop_oneof!["[a-d]", Just("*".to_string()), Just("b[*]".to_string())],
+            1..4,
+        )
+        .prop_map(|parts| parts.join("."))
+    }
+
+    // 按路径查找所有命中的值，用于检查过滤结果
+    fn lookup<'a>(value: &'a Value, segments: &[Segment]) -> Vec<&'a Value> {
+        let Some((first, rest)) = segments.split_first() else {
+            return vec![value];
+        };
+        match value {
+            Value::Object(map) => map
+                .iter()
+                .filter(|(k, _)| first.matches_key(k))
+                .flat_map(|(_, v)| lookup(v, rest))
+                .collect(),
+            Value::Array(arr) => arr
+                .iter()
+                .enumerate()
+                .filter(|(i, _)| first.matches_index(*i))
+                .flat_map(|(_, v)| lookup(v, rest))
+                .collect(),
+            _ => vec![],
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn empty_exclude_is_identity(value in json_value()) {
+            prop_assert_eq!(filter_value(value.clone(), &[]), value);
+        }
+
+        #[test]
+        fn missing_paths_are_ignored(value in json_value()) {
+            // 生成的字段名只有 a-d
+            prop_assert_eq!(filter_value(value.clone(), &["zz", "x.y", "q[*].r"]), value);
+        }
+
+        #[test]
+        fn excluded_paths_are_gone(value in json_value(), paths in prop::collection::vec(path(), 1..4)) {
+            let refs: Vec<&str> = paths.iter().map(String::as_str).collect();
+            let filtered = filter_value(value.clone(), &refs);
+            if !value.is_array() {
+                for p in &refs {
+                    prop_assert!(lookup(&filtered, &parse_path(p)).is_empty());
+                }
+            }
+            // 重复过滤不会再有变化
+            prop_assert_eq!(filter_value(filtered.clone(), &refs), filtered);
+        }
+
+        #[test]
+        fn top_level_keys_are_split_between_modes(value in json_value(), key in "[a-d]") {
+            if let Value::Object(original) = &value {
+                let excluded = filter_value(value.clone(), &[&key]);
+                let selected = select_value(value.clone(), &[&key]);
+                let excluded = excluded.as_object().unwrap();
+                let selected = selected.as_object().unwrap();
+                prop_assert_eq!(excluded.len() + selected.len(), original.len());
+                prop_assert!(selected.keys().all(|k| k == &key));
+                prop_assert_eq!(selected.get(&key), original.get(&key));
+            }
+        }
+
+        #[test]
+        fn select_everything_is_identity(value in json_value()) {
+            if value.is_object() {
+                prop_assert_eq!(select_value(value.clone(), &["*"]), value);
+            }
+        }
+
+        #[test]
+        fn selected_values_are_subset(value in json_value(), paths in prop::collection::vec(path(), 1..4)) {
+            let refs: Vec<&str> = paths.iter().map(String::as_str).collect();
+            if value.is_object() {
+                let selected = select_value(value.clone(), &refs);
+                // 选出的整段值都来自原始数据的同一路径
+                for p in &refs {
+                    let segments = parse_path(p);
+                    for v in lookup(&selected, &segments) {
+                        prop_assert!(lookup(&value, &segments).contains(&v));
+                    }
+                }
+            }
+        }
     }
 }
@@ -1,50 +1,141 @@
+use crate::error::error::ErrorResponse;
+use crate::middleware::request_id::RequestId;
 use crate::AppError;
 use actix_web::{
-    body::MessageBody, dev::ServiceResponse, http::StatusCode, middleware::ErrorHandlerResponse,
-    HttpMessage, HttpResponse, Result,
+    body::MessageBody,
+    dev::ServiceResponse,
+    http::{header, StatusCode},
+    middleware::ErrorHandlerResponse,
+    Error, HttpMessage, Result,
 };
-use log::error;
-use serde_json::json;
+use log::{error, warn};
 use std::cell::RefCell;
 use std::rc::Rc;
 
+// 把错误转换为统一的错误响应体；不是 AppError 的错误（路由不存在、路径参数解析失败等）按状态码生成错误码
+pub fn error_body(err: Option<&Error>, status: StatusCode) -> ErrorResponse {
+    if let Some(app_error) = err.and_then(|e| e.as_error::<AppError>()) {
+        return app_error.to_error_response();
+    }
+    let message = match (err, status) {
+        (Some(err), _) => err.to_string(),
+        (None, StatusCode::NOT_FOUND) => "接口不存在".to_string(),
+        (None, StatusCode::METHOD_NOT_ALLOWED) => "请求方法不被允许".to_string(),
+        (None, status) => status.canonical_reason().unwrap_or("未知错误").to_string(),
+    };
+    ErrorResponse::from_status(status, message)
+}
+
+// 所有 4xx/5xx 响应都改写为统一的错误响应体，并带上请求ID
 pub fn add_error_header<B: MessageBody + 'static>(
     res: ServiceResponse<B>,
 ) -> Result<ErrorHandlerResponse<B>> {
-    let (req, _res) = res.into_parts();
-
-    if let Some(app_err) = req.extensions().get::<Rc<RefCell<Option<AppError>>>>() {
-        let app_err = app_err.borrow();
-        if let Some(app_error) = app_err.as_ref() {
-            let error_message = app_error.to_string();
-            error!("拦截到应用错误: {}", error_message);
-
-            let status_code = match app_error {
-                AppError::Forbidden(_) => StatusCode::FORBIDDEN,
-                AppError::Unauthorized(_) => StatusCode::UNAUTHORIZED,
-                AppError::NotFound(_) => StatusCode::NOT_FOUND,
-                AppError::BadRequest(_) => StatusCode::BAD_REQUEST,
-                AppError::DeserializeError(_) => StatusCode::BAD_REQUEST,
-                AppError::Conflict(_) => StatusCode::CONFLICT,
-                _ => StatusCode::INTERNAL_SERVER_ERROR,
-            };
-
-            let error_response = HttpResponse::build(status_code).json(json!({
-                "message": error_message,
-                "code": status_code.as_u16()
-            }));
-
-            let new_res = ServiceResponse::new(req.clone(), error_response)
-                .map_into_boxed_body()
-                .map_into_right_body();
-
-            return Ok(ErrorHandlerResponse::Response(new_res));
+    let (req, res) = res.into_parts();
+
+    // 权限守卫拒绝时路由不会匹配，拒绝原因由守卫写入请求扩展
+    let guard_error = req
+        .extensions()
+        .get::<Rc<RefCell<Option<AppError>>>>()
+        .and_then(|err| err.borrow_mut().take());
+    let body = match guard_error {
+        Some(app_error) => app_error.to_error_response(),
+        None => error_body(res.error(), res.status()),
+    }
+    .with_request_id(RequestId::of(&req));
+
+    match body.status().is_server_error() {
+        true => error!("{} {} 请求失败: {:?}", req.method(), req.path(), body),
+        false => warn!("{} {} 请求失败: {:?}", req.method(), req.path(), body),
+    }
+
+    // 保留原响应中除响应体相关以外的头，例如 405 的 Allow
+    let mut new_res = body.into_response();
+    for (name, value) in res.headers() {
+        if name != header::CONTENT_TYPE && name != header::CONTENT_LENGTH {
+            new_res.headers_mut().insert(name.clone(), value.clone());
         }
     }
 
     Ok(ErrorHandlerResponse::Response(
-        ServiceResponse::new(req, _res)
+        ServiceResponse::new(req, new_res)
             .map_into_boxed_body()
             .map_into_right_body(),
     ))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::middleware::request_id::{RequestIdTransform, REQUEST_ID_HEADER};
+    use actix_web::{middleware::ErrorHandlers, test, web, App, HttpResponse};
+    use validator::Validate;
+
+    #[derive(Validate)]
+    struct Payload {
+        #[validate(length(min = 3, message = "用户名至少3个字符"))]
+        user_name: String,
+    }
+
+    #[actix_web::test]
+    async fn every_error_uses_the_same_envelope() {
+        let app = test::init_service(
+            App::new()
+                .wrap(ErrorHandlers::new().default_handler(add_error_header))
+                .wrap(RequestIdTransform)
+                .route(
+                    "/conflict",
+                    web::get().to(|| async {
+                        Err::<HttpResponse, _>(AppError::Conflict("用户名已存在".into()))
+                    }),
+                )
+                .route(
+                    "/invalid",
+                    web::get().to(|| async {
+                        let payload = Payload {
+                            user_name: "ab".into(),
+                        };
+                        payload.validate().map_err(AppError::from)?;
+                        Ok::<_, AppError>(HttpResponse::Ok().finish())
+                    }),
+                ),
+        )
+        .await;
+
+        let req = test::TestRequest::get()
+            .uri("/conflict")
+            .insert_header((REQUEST_ID_HEADER, "req-1"))
+            .to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), 409);
+        let body: ErrorResponse = test::read_body_json(res).await;
+        assert_eq!(
+            body,
+            ErrorResponse {
+                code: 409,
+                error: "CONFLICT".into(),
+                message: "用户名已存在".into(),
+                fields: None,
+                request_id: Some("req-1".into()),
+            }
+        );
+
+        let req = test::TestRequest::get().uri("/invalid").to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), 400);
+        let body: ErrorResponse = test::read_body_json(res).await;
+        assert_eq!(body.error, "VALIDATION_FAILED");
+        assert_eq!(
+            body.fields.unwrap()["user_name"],
+            vec!["用户名至少3个字符".to_string()]
+        );
+        assert!(body.request_id.is_some());
+
+        // 没有匹配的路由
+        let req = test::TestRequest::get().uri("/missing").to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), 404);
+        let body: ErrorResponse = test::read_body_json(res).await;
+        assert_eq!(body.error, "NOT_FOUND");
+        assert_eq!(body.message, "接口不存在");
+    }
+}
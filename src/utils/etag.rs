@@ -0,0 +1,153 @@
+use crate::middleware::helpers::{Resp, SimpleResp};
+use crate::utils::crypto::sha256_hex;
+use crate::AppError;
+use actix_web::http::header::{self, ETag, EntityTag, Header, IfMatch, IfNoneMatch};
+use actix_web::{HttpRequest, HttpResponse};
+use sea_orm::{ActiveModelTrait, Condition, ConnectionTrait, EntityTrait, QueryFilter};
+use serde::Serialize;
+
+// 根据返回给客户端的资源内容生成强 ETag，内容中包含精确到毫秒的更新时间
+pub fn etag_of<T: Serialize>(resource: &T) -> EntityTag {
+    let body = serde_json::to_string(resource).unwrap_or_default();
+    EntityTag::new_strong(sha256_hex(&body)[..32].to_string())
+}
+
+// 修改和删除前校验 If-Match，未携带时不做限制；不匹配说明资源已被他人修改
+pub fn check_if_match(req: &HttpRequest, current: &EntityTag) -> Result<(), AppError> {
+    if !req.headers().contains_key(header::IF_MATCH) {
+        return Ok(());
+    }
+    let matched = match IfMatch::parse(req) {
+        Ok(IfMatch::Any) => true,
+        Ok(IfMatch::Items(tags)) => tags.iter().any(|tag| tag.strong_eq(current)),
+        Err(_) => return Err(AppError::BadRequest("If-Match 请求头格式错误".into())),
+    };
+    match matched {
+        true => Ok(()),
+        false => Err(AppError::PreconditionFailed(
+            "资源已被修改，请重新获取后再提交".into(),
+        )),
+    }
+}
+
+// 条件写入：只有行仍满足 unchanged（通常是主键加读取时的更新时间）才会修改。
+// 校验 If-Match 之后、写入之前如果被他人修改，条件不成立，返回 412 而不是覆盖对方的修改
+pub async fn update_if_unchanged<A, C>(
+    db: &C,
+    active: A,
+    unchanged: Condition,
+) -> Result<(), AppError>
+where
+    A: ActiveModelTrait,
+    C: ConnectionTrait,
+{
+    let result = A::Entity::update_many()
+        .set(active)
+        .filter(unchanged)
+        .exec(db)
+        .await?;
+    match result.rows_affected {
+        0 => Err(AppError::PreconditionFailed(
+            "资源已被修改，请重新获取后再提交".into(),
+        )),
+        _ => Ok(()),
+    }
+}
+
+// 客户端缓存的版本是否仍然有效，按弱比较处理 If-None-Match
+fn not_modified(req: &HttpRequest, current: &EntityTag) -> bool {
+    match IfNoneMatch::parse(req) {
+        Ok(IfNoneMatch::Any) => true,
+        Ok(IfNoneMatch::Items(tags)) => tags.iter().any(|tag| tag.weak_eq(current)),
+        Err(_) => false,
+    }
+}
+
+// 单个资源的 GET 响应：带上 ETag，If-None-Match 命中时返回 304
+pub fn conditional_ok<T: Serialize>(req: &HttpRequest, data: T, message: &str) -> SimpleResp {
+    let etag = etag_of(&data);
+    if not_modified(req, &etag) {
+        return Ok(HttpResponse::NotModified()
+            .insert_header(ETag(etag))
+            .finish());
+    }
+    tagged_ok(data, message)
+}
+
+// 返回资源并附带当前版本的 ETag，修改成功后客户端可以直接用它继续提交
+pub fn tagged_ok<T: Serialize>(data: T, message: &str) -> SimpleResp {
+    let etag = etag_of(&data);
+    Ok(HttpResponse::Ok()
+        .insert_header(ETag(etag))
+        .json(Resp::ok(data, message)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::test::TestRequest;
+    use serde_json::json;
+
+    #[test]
+    fn etag_changes_with_content() {
+        let a = etag_of(&json!({"id": 1, "updated_at": "2024-01-01T00:00:00Z"}));
+        let b = etag_of(&json!({"id": 1, "updated_at": "2024-01-01T00:00:00Z"}));
+        let c = etag_of(&json!({"id": 1, "updated_at": "2024-01-01T00:00:01Z"}));
+        assert!(a.strong_eq(&b));
+        assert!(!a.strong_eq(&c));
+    }
+
+    #[test]
+    fn if_match_is_optional_but_enforced_when_present() {
+        let current = etag_of(&json!({"id": 1}));
+        let stale = etag_of(&json!({"id": 2}));
+
+        let req = TestRequest::default().to_http_request();
+        assert!(check_if_match(&req, &current).is_ok());
+
+        let req = TestRequest::default()
+            .insert_header((header::IF_MATCH, current.to_string()))
+            .to_http_request();
+        assert!(check_if_match(&req, &current).is_ok());
+
+        let req = TestRequest::default()
+            .insert_header((header::IF_MATCH, format!("{}, {}", stale, current)))
+            .to_http_request();
+        assert!(check_if_match(&req, &current).is_ok());
+
+        let req = TestRequest::default()
+            .insert_header((header::IF_MATCH, stale.to_string()))
+            .to_http_request();
+        assert!(matches!(
+            check_if_match(&req, &current),
+            Err(AppError::PreconditionFailed(_))
+        ));
+
+        // 弱 ETag 不能用于 If-Match
+        let weak = EntityTag::new_weak(current.tag().to_string());
+        let req = TestRequest::default()
+            .insert_header((header::IF_MATCH, weak.to_string()))
+            .to_http_request();
+        assert!(check_if_match(&req, &current).is_err());
+    }
+
+    #[test]
+    fn conditional_get_returns_not_modified() {
+        let data = json!({"id": 1});
+        let etag = etag_of(&data);
+
+        let req = TestRequest::default()
+            .insert_header((header::IF_NONE_MATCH, etag.to_string()))
+            .to_http_request();
+        let res = conditional_ok(&req, data.clone(), "ok").unwrap();
+        assert_eq!(res.status(), 304);
+        assert_eq!(res.headers().get(header::ETAG).unwrap(), &etag.to_string());
+
+        let req = TestRequest::default()
+            .insert_header((header::IF_NONE_MATCH, "\"other\""))
+            .to_http_request();
+        let res = conditional_ok(&req, data, "ok").unwrap();
+        assert_eq!(res.status(), 200);
+        assert_eq!(res.headers().get(header::ETAG).unwrap(), &etag.to_string());
+    }
+}
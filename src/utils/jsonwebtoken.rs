@@ -1,19 +1,51 @@
+use crate::AppError;
 use actix_web::http::header::HeaderMap;
-use jsonwebtoken::{decode, Algorithm, DecodingKey, TokenData, Validation};
+use actix_web::HttpRequest;
+use jsonwebtoken::{
+    decode, encode, Algorithm, DecodingKey, EncodingKey, Header, TokenData, Validation,
+};
 use log::error;
 use serde::{Deserialize, Serialize};
-#[derive(Debug, Serialize, Deserialize)]
+use std::time::SystemTime;
+
+pub const JWT_SECRET: &str = "secret_key";
+// 登录二次验证挑战令牌的用途标识
+pub const TWO_FACTOR_CHALLENGE_PURPOSE: &str = "2fa_challenge";
+// 邮箱验证链接令牌的用途标识
+pub const EMAIL_VERIFY_PURPOSE: &str = "email_verify";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TokenClaims {
     pub user_uuid: String,
     pub user_name: String,
     pub exp: usize, // 令牌过期时间
     pub permissions: Option<String>,
+    // 令牌唯一标识，旧版本签发的令牌没有该字段
+    #[serde(default)]
+    pub jti: Option<String>,
+}
+
+// 密码校验通过、等待TOTP验证码时签发的短期令牌
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TwoFactorChallengeClaims {
+    pub user_uuid: String,
+    pub purpose: String,
+    pub exp: usize,
+}
+
+// 邮箱验证链接中携带的令牌，绑定签发时的邮箱地址，邮箱变更后旧链接自动失效
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EmailVerifyClaims {
+    pub user_uuid: String,
+    pub email: String,
+    pub purpose: String,
+    pub exp: usize,
 }
 
 pub fn has_permission(token: &str) -> Result<TokenData<TokenClaims>, Box<dyn std::error::Error>> {
     let token_message = decode::<TokenClaims>(
         token,
-        &DecodingKey::from_secret("secret_key".as_bytes()),
+        &DecodingKey::from_secret(JWT_SECRET.as_bytes()),
         &Validation::new(Algorithm::HS256),
     );
 
@@ -27,14 +59,127 @@ pub fn has_permission(token: &str) -> Result<TokenData<TokenClaims>, Box<dyn std
     }
 }
 
-pub fn extract_token(headers: &HeaderMap) -> Option<String> {
-    if let Some(authorization_header) = headers.get("Authorization") {
-        if let Ok(authorization_str) = authorization_header.to_str() {
-            // 假设令牌格式为 "Bearer <token>"
-            if let Some(token) = authorization_str.strip_prefix("Bearer ") {
-                return Some(token.to_string());
-            }
-        }
+// 请求携带的凭证
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AuthToken {
+    // Authorization: Bearer <jwt>
+    Jwt(String),
+    // Authorization: ApiKey <key>
+    ApiKey(String),
+}
+
+pub fn extract_token(headers: &HeaderMap) -> Option<AuthToken> {
+    let authorization_str = headers.get("Authorization")?.to_str().ok()?;
+    if let Some(token) = authorization_str.strip_prefix("Bearer ") {
+        return Some(AuthToken::Jwt(token.trim().to_string()));
+    }
+    if let Some(key) = authorization_str.strip_prefix("ApiKey ") {
+        return Some(AuthToken::ApiKey(key.trim().to_string()));
     }
     None
 }
+
+// 解析请求头中的JWT，用于认证中间件之外（如公开接口上可选的登录状态）
+pub fn claims_from_request(req: &HttpRequest) -> Result<TokenClaims, AppError> {
+    match extract_token(req.headers()) {
+        Some(AuthToken::Jwt(token)) => has_permission(&token)
+            .map(|token_data| token_data.claims)
+            .map_err(|e| AppError::Unauthorized(e.to_string())),
+        // API密钥需要查询数据库，只能由认证中间件解析
+        Some(AuthToken::ApiKey(_)) => Err(AppError::Unauthorized("API密钥无效".into())),
+        None => Err(AppError::Unauthorized("请求未包含认证Token".into())),
+    }
+}
+
+// 生成双重验证挑战令牌
+pub fn generate_challenge_token(user_uuid: &str, expires_in: u64) -> Result<String, AppError> {
+    let exp = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+        + expires_in;
+
+    let claims = TwoFactorChallengeClaims {
+        user_uuid: user_uuid.to_string(),
+        purpose: TWO_FACTOR_CHALLENGE_PURPOSE.to_string(),
+        exp: exp as usize,
+    };
+
+    encode(
+        &Header::default(),
+        &claims,
+        &EncodingKey::from_secret(JWT_SECRET.as_bytes()),
+    )
+    .map_err(|e| {
+        error!("挑战令牌生成失败: {}", e);
+        AppError::InternalServerError("登录服务暂时不可用".into())
+    })
+}
+
+// 校验双重验证挑战令牌，过期或用途不符都视为无效
+pub fn decode_challenge_token(token: &str) -> Result<TwoFactorChallengeClaims, AppError> {
+    let claims = decode::<TwoFactorChallengeClaims>(
+        token,
+        &DecodingKey::from_secret(JWT_SECRET.as_bytes()),
+        &Validation::new(Algorithm::HS256),
+    )
+    .map_err(|e| {
+        error!("解码挑战令牌时发生错误: {:?}", e);
+        AppError::Unauthorized("挑战令牌无效或已过期".into())
+    })?
+    .claims;
+
+    if claims.purpose != TWO_FACTOR_CHALLENGE_PURPOSE {
+        return Err(AppError::Unauthorized("挑战令牌无效或已过期".into()));
+    }
+    Ok(claims)
+}
+
+// 生成邮箱验证令牌
+pub fn generate_email_verify_token(
+    user_uuid: &str,
+    email: &str,
+    expires_in: u64,
+) -> Result<String, AppError> {
+    let exp = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+        + expires_in;
+
+    let claims = EmailVerifyClaims {
+        user_uuid: user_uuid.to_string(),
+        email: email.to_string(),
+        purpose: EMAIL_VERIFY_PURPOSE.to_string(),
+        exp: exp as usize,
+    };
+
+    encode(
+        &Header::default(),
+        &claims,
+        &EncodingKey::from_secret(JWT_SECRET.as_bytes()),
+    )
+    .map_err(|e| {
+        error!("邮箱验证令牌生成失败: {}", e);
+        AppError::InternalServerError("邮箱验证服务暂时不可用".into())
+    })
+}
+
+// 校验邮箱验证令牌，过期或用途不符都视为无效
+pub fn decode_email_verify_token(token: &str) -> Result<EmailVerifyClaims, AppError> {
+    let claims = decode::<EmailVerifyClaims>(
+        token,
+        &DecodingKey::from_secret(JWT_SECRET.as_bytes()),
+        &Validation::new(Algorithm::HS256),
+    )
+    .map_err(|e| {
+        error!("解码邮箱验证令牌时发生错误: {:?}", e);
+        AppError::BadRequest("验证链接无效或已过期".into())
+    })?
+    .claims;
+
+    if claims.purpose != EMAIL_VERIFY_PURPOSE {
+        return Err(AppError::BadRequest("验证链接无效或已过期".into()));
+    }
+    Ok(claims)
+}
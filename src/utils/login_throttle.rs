@@ -0,0 +1,247 @@
+use crate::config::throttle::{LoginThrottleConfig, ThrottlePolicy};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+// 过期记录的清理间隔，避免每次失败都遍历整张表
+const PRUNE_INTERVAL: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, Copy)]
+struct AttemptState {
+    failures: u32,
+    last_failure: Instant,
+    blocked_until: Option<Instant>,
+}
+
+struct AttemptMap {
+    entries: HashMap<String, AttemptState>,
+    last_pruned: Instant,
+}
+
+impl AttemptMap {
+    fn new() -> Self {
+        AttemptMap {
+            entries: HashMap::new(),
+            last_pruned: Instant::now(),
+        }
+    }
+
+    // 定期清理过期记录，避免无效用户名撑大内存
+    fn prune(&mut self, policy: &ThrottlePolicy, now: Instant) {
+        if now.duration_since(self.last_pruned) < PRUNE_INTERVAL {
+            return;
+        }
+        self.entries
+            .retain(|_, state| now.duration_since(state.last_failure) < policy.lockout_duration);
+        self.last_pruned = now;
+    }
+}
+
+// 登录失败计数器，按用户名和IP分别统计，支持指数退避和临时锁定
+#[derive(Clone)]
+pub struct LoginThrottle {
+    config: Arc<LoginThrottleConfig>,
+    users: Arc<Mutex<AttemptMap>>,
+    ips: Arc<Mutex<AttemptMap>>,
+}
+
+impl Default for LoginThrottle {
+    fn default() -> Self {
+        Self::new(LoginThrottleConfig::from_env())
+    }
+}
+
+impl LoginThrottle {
+    pub fn new(config: LoginThrottleConfig) -> Self {
+        LoginThrottle {
+            config: Arc::new(config),
+            users: Arc::new(Mutex::new(AttemptMap::new())),
+            ips: Arc::new(Mutex::new(AttemptMap::new())),
+        }
+    }
+
+    // 检查是否允许本次登录尝试，被限制时返回需要等待的时长
+    pub fn check(&self, user_name: &str, ip: &str) -> Result<(), Duration> {
+        let now = Instant::now();
+        let wait = [
+            remaining(&self.users, &user_key(user_name), now),
+            remaining(&self.ips, ip, now),
+        ]
+        .into_iter()
+        .flatten()
+        .max();
+        match wait {
+            Some(wait) => Err(wait),
+            None => Ok(()),
+        }
+    }
+
+    pub fn record_failure(&self, user_name: &str, ip: &str) {
+        let now = Instant::now();
+        fail(&self.users, &self.config.user, user_key(user_name), now);
+        fail(&self.ips, &self.config.ip, ip.to_string(), now);
+    }
+
+    // 登录成功只清除用户名的失败记录，IP计数保持不变
+    pub fn record_success(&self, user_name: &str) {
+        self.users
+            .lock()
+            .unwrap()
+            .entries
+            .remove(&user_key(user_name));
+    }
+
+    // 管理员解锁账户
+    pub fn unlock(&self, user_name: &str) -> bool {
+        self.users
+            .lock()
+            .unwrap()
+            .entries
+            .remove(&user_key(user_name))
+            .is_some()
+    }
+}
+
+fn user_key(user_name: &str) -> String {
+    user_name.trim().to_lowercase()
+}
+
+fn remaining(map: &Mutex<AttemptMap>, key: &str, now: Instant) -> Option<Duration> {
+    let map = map.lock().unwrap();
+    map.entries
+        .get(key)
+        .and_then(|state| state.blocked_until)
+        .filter(|until| *until > now)
+        .map(|until| until - now)
+}
+
+fn fail(map: &Mutex<AttemptMap>, policy: &ThrottlePolicy, key: String, now: Instant) {
+    let mut map = map.lock().unwrap();
+    map.prune(policy, now);
+
+    let state = map.entries.entry(key).or_insert(AttemptState {
+        failures: 0,
+        last_failure: now,
+        blocked_until: None,
+    });
+    state.failures += 1;
+    state.last_failure = now;
+    state.blocked_until = block_duration(policy, state.failures).map(|d| now + d);
+}
+
+// 计算第 failures 次失败之后需要等待的时长
+fn block_duration(policy: &ThrottlePolicy, failures: u32) -> Option<Duration> {
+    if failures >= policy.lockout_threshold {
+        return Some(policy.lockout_duration);
+    }
+    if failures <= policy.free_attempts {
+        return None;
+    }
+    let exponent = (failures - policy.free_attempts - 1).min(16);
+    Some(
+        policy
+            .base_delay
+            .saturating_mul(1 << exponent)
+            .min(policy.max_delay),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy() -> ThrottlePolicy {
+        ThrottlePolicy {
+            free_attempts: 3,
+            base_delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(10),
+            lockout_threshold: 8,
+            lockout_duration: Duration::from_secs(900),
+        }
+    }
+
+    fn throttle() -> LoginThrottle {
+        LoginThrottle::new(LoginThrottleConfig {
+            user: policy(),
+            ip: ThrottlePolicy {
+                free_attempts: 100,
+                lockout_threshold: 200,
+                ..policy()
+            },
+        })
+    }
+
+    #[test]
+    fn delay_doubles_after_free_attempts_up_to_the_cap() {
+        let policy = policy();
+        let delays: Vec<_> = (1..=7)
+            .map(|failures| block_duration(&policy, failures).map(|d| d.as_secs()))
+            .collect();
+        assert_eq!(
+            delays,
+            [None, None, None, Some(1), Some(2), Some(4), Some(8)]
+        );
+        // 超过上限后不再翻倍
+        let capped = ThrottlePolicy {
+            lockout_threshold: 100,
+            ..policy.clone()
+        };
+        assert_eq!(block_duration(&capped, 9), Some(Duration::from_secs(10)));
+        assert_eq!(block_duration(&capped, 90), Some(Duration::from_secs(10)));
+    }
+
+    #[test]
+    fn account_is_locked_at_threshold() {
+        let policy = policy();
+        assert_eq!(block_duration(&policy, 8), Some(policy.lockout_duration));
+
+        let throttle = throttle();
+        for _ in 0..8 {
+            throttle.record_failure("Alice", "10.0.0.1");
+        }
+        let wait = throttle.check("alice", "10.0.0.2").unwrap_err();
+        assert!(wait > Duration::from_secs(890));
+        // 其他账户不受影响
+        assert!(throttle.check("bob", "10.0.0.1").is_ok());
+
+        assert!(throttle.unlock(" ALICE "));
+        assert!(throttle.check("alice", "10.0.0.1").is_ok());
+    }
+
+    #[test]
+    fn success_resets_user_failures_but_not_ip() {
+        let throttle = throttle();
+        for _ in 0..3 {
+            throttle.record_failure("alice", "10.0.0.1");
+        }
+        throttle.record_success("alice");
+        // 计数已清零，再失败一次仍在免限制次数内
+        throttle.record_failure("alice", "10.0.0.1");
+        assert!(throttle.check("alice", "10.0.0.1").is_ok());
+        assert_eq!(throttle.ips.lock().unwrap().entries["10.0.0.1"].failures, 4);
+    }
+
+    #[test]
+    fn expired_entries_are_pruned_periodically() {
+        let policy = ThrottlePolicy {
+            lockout_duration: Duration::from_secs(30),
+            ..policy()
+        };
+        let start = Instant::now();
+        let map = Mutex::new(AttemptMap::new());
+        fail(&map, &policy, "old".into(), start);
+        // 还没到清理间隔，过期记录暂时保留
+        fail(&map, &policy, "new".into(), start + Duration::from_secs(40));
+        assert_eq!(map.lock().unwrap().entries.len(), 2);
+
+        fail(
+            &map,
+            &policy,
+            "new".into(),
+            start + PRUNE_INTERVAL + Duration::from_secs(1),
+        );
+        let map = map.lock().unwrap();
+        assert!(!map.entries.contains_key("old"));
+        assert_eq!(map.entries["new"].failures, 2);
+    }
+}
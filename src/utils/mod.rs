@@ -1,7 +1,16 @@
 pub mod common_guard;
+pub mod crypto;
+pub mod current_user;
+pub mod cursor;
 pub mod data_processing;
 pub mod error_handler;
+pub mod etag;
 pub mod jsonwebtoken;
+pub mod login_throttle;
+pub mod notifier;
+pub mod oauth_state;
+pub mod ownership;
+pub mod password_hash;
 pub mod permission_guard;
 pub mod query_parameter;
 pub mod serde;
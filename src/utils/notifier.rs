@@ -0,0 +1,153 @@
+use crate::config::mail::{MailConfig, MailTransport, SmtpSettings};
+use crate::AppError;
+use async_trait::async_trait;
+use chrono::Utc;
+use lettre::message::header::ContentType;
+use lettre::message::Mailbox;
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+// 发送给用户的一条通知
+#[derive(Debug, Clone)]
+pub struct Notification {
+    pub recipient: String,
+    pub subject: String,
+    pub body: String,
+}
+
+// 通知投递渠道，具体实现通过 web::Data<dyn Notifier> 注入
+#[async_trait]
+pub trait Notifier: Send + Sync {
+    async fn send(&self, notification: &Notification) -> Result<(), AppError>;
+}
+
+// 按配置创建通知渠道
+pub fn notifier_from_config(config: &MailConfig) -> Result<Arc<dyn Notifier>, AppError> {
+    let notifier: Arc<dyn Notifier> = match config.transport {
+        MailTransport::Log => Arc::new(LogNotifier),
+        MailTransport::File => Arc::new(FileNotifier::new(config.file_dir.clone())),
+        MailTransport::Smtp => Arc::new(SmtpNotifier::new(&config.smtp, &config.from)?),
+    };
+    Ok(notifier)
+}
+
+// 只把通知写入日志，用于本地开发和测试
+pub struct LogNotifier;
+
+#[async_trait]
+impl Notifier for LogNotifier {
+    async fn send(&self, notification: &Notification) -> Result<(), AppError> {
+        log::info!(
+            "通知 -> {} | {}\n{}",
+            notification.recipient,
+            notification.subject,
+            notification.body
+        );
+        Ok(())
+    }
+}
+
+// 每条通知写成目录下的一个 .eml 文件
+pub struct FileNotifier {
+    dir: PathBuf,
+}
+
+impl FileNotifier {
+    pub fn new(dir: PathBuf) -> Self {
+        FileNotifier { dir }
+    }
+}
+
+#[async_trait]
+impl Notifier for FileNotifier {
+    async fn send(&self, notification: &Notification) -> Result<(), AppError> {
+        let write_error = |e: std::io::Error| {
+            log::error!("写入邮件文件失败: {}", e);
+            AppError::InternalServerError("邮件发送失败".into())
+        };
+        tokio::fs::create_dir_all(&self.dir)
+            .await
+            .map_err(write_error)?;
+
+        let now = Utc::now();
+        let path = self.dir.join(format!(
+            "{}-{}.eml",
+            now.format("%Y%m%d%H%M%S"),
+            uuid::Uuid::new_v4()
+        ));
+        let content = format!(
+            "Date: {}\r\nTo: {}\r\nSubject: {}\r\n\r\n{}\r\n",
+            now.to_rfc2822(),
+            notification.recipient,
+            notification.subject,
+            notification.body
+        );
+        tokio::fs::write(&path, content)
+            .await
+            .map_err(write_error)?;
+        log::info!("邮件已写入 {}", path.display());
+        Ok(())
+    }
+}
+
+// 通过SMTP发送纯文本邮件
+pub struct SmtpNotifier {
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+    from: Mailbox,
+}
+
+impl SmtpNotifier {
+    pub fn new(settings: &SmtpSettings, from: &str) -> Result<Self, AppError> {
+        let config_error = |e: String| {
+            log::error!("SMTP配置错误: {}", e);
+            AppError::InternalServerError("SMTP配置错误".into())
+        };
+        let builder = if settings.implicit_tls {
+            AsyncSmtpTransport::<Tokio1Executor>::relay(&settings.host)
+        } else {
+            AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&settings.host)
+        }
+        .map_err(|e| config_error(e.to_string()))?
+        .port(settings.port);
+        let builder = match (&settings.username, &settings.password) {
+            (Some(username), Some(password)) => {
+                builder.credentials(Credentials::new(username.clone(), password.clone()))
+            }
+            _ => builder,
+        };
+
+        Ok(SmtpNotifier {
+            transport: builder.build(),
+            from: from.parse().map_err(|e: lettre::address::AddressError| {
+                config_error(format!("MAIL_FROM 格式错误: {}", e))
+            })?,
+        })
+    }
+}
+
+#[async_trait]
+impl Notifier for SmtpNotifier {
+    async fn send(&self, notification: &Notification) -> Result<(), AppError> {
+        let to: Mailbox = notification.recipient.parse().map_err(|_| {
+            AppError::BadRequest(format!("收件地址无效: {}", notification.recipient))
+        })?;
+        let message = Message::builder()
+            .from(self.from.clone())
+            .to(to)
+            .subject(notification.subject.clone())
+            .header(ContentType::TEXT_PLAIN)
+            .body(notification.body.clone())
+            .map_err(|e| {
+                log::error!("构建邮件失败: {}", e);
+                AppError::InternalServerError("邮件发送失败".into())
+            })?;
+
+        self.transport.send(message).await.map_err(|e| {
+            log::error!("SMTP发送失败: {}", e);
+            AppError::InternalServerError("邮件发送失败".into())
+        })?;
+        Ok(())
+    }
+}
@@ -0,0 +1,64 @@
+use crate::config::oauth::OAUTH_CONFIG;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+// 发起授权时保存的信息，回调时凭 state 取回
+#[derive(Debug, Clone)]
+pub struct PendingAuthorization {
+    pub provider: String,
+    pub code_verifier: String,
+    // 已登录用户发起授权时记录其UUID，回调时绑定到该用户而不是登录
+    pub link_user_uuid: Option<String>,
+    created_at: Instant,
+}
+
+// 进行中的第三方授权请求，state 只能使用一次
+#[derive(Clone)]
+pub struct OAuthStateStore {
+    ttl: Duration,
+    pending: Arc<Mutex<HashMap<String, PendingAuthorization>>>,
+}
+
+impl Default for OAuthStateStore {
+    fn default() -> Self {
+        Self::new(OAUTH_CONFIG.state_ttl)
+    }
+}
+
+impl OAuthStateStore {
+    pub fn new(ttl: Duration) -> Self {
+        OAuthStateStore {
+            ttl,
+            pending: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    pub fn insert(
+        &self,
+        state: String,
+        provider: &str,
+        code_verifier: String,
+        link_user_uuid: Option<String>,
+    ) {
+        let now = Instant::now();
+        let mut pending = self.pending.lock().unwrap();
+        // 顺带清理已过期的授权请求，避免无人回调的记录一直占用内存
+        pending.retain(|_, p| now.duration_since(p.created_at) < self.ttl);
+        pending.insert(
+            state,
+            PendingAuthorization {
+                provider: provider.to_string(),
+                code_verifier,
+                link_user_uuid,
+                created_at: now,
+            },
+        );
+    }
+
+    // 取出并删除 state 对应的授权请求，过期视为不存在
+    pub fn take(&self, state: &str) -> Option<PendingAuthorization> {
+        let pending = self.pending.lock().unwrap().remove(state)?;
+        (pending.created_at.elapsed() < self.ttl).then_some(pending)
+    }
+}
@@ -0,0 +1,147 @@
+use crate::{config::permission::Permission, current_user::CurrentUser, models::article, AppError};
+use actix_web::{dev::Payload, web, FromRequest, HttpMessage, HttpRequest};
+use futures_util::future::LocalBoxFuture;
+use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter};
+use std::future::Future;
+use std::ops::Deref;
+
+// 归属于某个用户的资源，作者只能操作自己的资源
+pub trait OwnedResource: Sized + 'static {
+    // 资源名称，用于错误提示
+    const NAME: &'static str;
+    // 路由中标识资源的路径参数名
+    const PATH_PARAM: &'static str;
+    // 持有该权限的用户可以操作任何人的资源
+    const BYPASS_PERMISSION: Permission;
+
+    // 资源所有者的UUID，历史数据可能没有所有者
+    fn owner_uuid(&self) -> Option<&str>;
+
+    fn load(
+        db: &DatabaseConnection,
+        key: &str,
+    ) -> impl Future<Output = Result<Option<Self>, AppError>>;
+}
+
+// 校验当前用户是否可以操作该资源
+pub fn authorize_owner<T: OwnedResource>(
+    resource: &T,
+    current_user: &CurrentUser,
+) -> Result<(), AppError> {
+    if resource.owner_uuid() == Some(current_user.uuid.as_str()) {
+        return Ok(());
+    }
+    if current_user.has(T::BYPASS_PERMISSION) {
+        return Ok(());
+    }
+    Err(AppError::Forbidden(format!("只能操作自己的{}", T::NAME)))
+}
+
+// 提取器：按路径参数加载资源并校验归属，失败时返回 404/403
+pub struct Owned<T>(pub T);
+
+impl<T> Owned<T> {
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> Deref for Owned<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T: OwnedResource> FromRequest for Owned<T> {
+    type Error = AppError;
+    type Future = LocalBoxFuture<'static, Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        let db = req.app_data::<web::Data<DatabaseConnection>>().cloned();
+        let key = req.match_info().get(T::PATH_PARAM).map(str::to_string);
+        let current_user = req.extensions().get::<CurrentUser>().cloned();
+
+        Box::pin(async move {
+            let db = db.ok_or_else(|| AppError::InternalServerError("数据库未配置".into()))?;
+            let key = key.ok_or_else(|| AppError::BadRequest("缺少资源标识".into()))?;
+            let current_user =
+                current_user.ok_or_else(|| AppError::Unauthorized("请求未包含认证Token".into()))?;
+            let resource = T::load(db.as_ref(), &key)
+                .await?
+                .ok_or_else(|| AppError::NotFound(format!("{}不存在", T::NAME)))?;
+            authorize_owner(&resource, &current_user)?;
+            Ok(Owned(resource))
+        })
+    }
+}
+
+impl OwnedResource for article::Model {
+    const NAME: &'static str = "文章";
+    const PATH_PARAM: &'static str = "uuid";
+    const BYPASS_PERMISSION: Permission = Permission::MANAGE_ARTICLE;
+
+    fn owner_uuid(&self) -> Option<&str> {
+        self.author_uuid.as_deref()
+    }
+
+    async fn load(db: &DatabaseConnection, key: &str) -> Result<Option<Self>, AppError> {
+        Ok(article::Entity::find()
+            .filter(article::Column::Uuid.eq(key))
+            .filter(article::Column::IsDelete.eq(0))
+            .one(db)
+            .await?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::permission::BUILTIN_ROLES;
+
+    fn current_user(user_uuid: &str, permissions: Permission) -> CurrentUser {
+        CurrentUser {
+            uuid: user_uuid.to_string(),
+            name: "tester".to_string(),
+            permissions,
+            token_id: None,
+        }
+    }
+
+    fn article_by(author: Option<&str>) -> article::Model {
+        article::Model::fixture("a-1", author)
+    }
+
+    #[test]
+    fn owner_can_access_own_resource() {
+        let article = article_by(Some("u1"));
+        assert!(authorize_owner(&article, &current_user("u1", Permission::empty())).is_ok());
+    }
+
+    #[test]
+    fn other_users_are_rejected_even_with_write_permission() {
+        let article = article_by(Some("u1"));
+        let result = authorize_owner(&article, &current_user("u2", Permission::WRITE_ARTICLE));
+        assert!(matches!(result, Err(AppError::Forbidden(_))));
+        // 没有作者的历史数据只有文章管理员可以操作
+        let orphan = article_by(None);
+        assert!(authorize_owner(&orphan, &current_user("u2", Permission::WRITE_ARTICLE)).is_err());
+    }
+
+    #[test]
+    fn article_managers_can_edit_any_article() {
+        let article = article_by(Some("u1"));
+        // 系统管理权限不等于文章管理权限
+        assert!(authorize_owner(&article, &current_user("sys", Permission::WRITE_SYSTEM)).is_err());
+        let editor = BUILTIN_ROLES
+            .iter()
+            .find(|(name, _, _)| *name == "editor")
+            .map(|(_, _, permissions)| *permissions)
+            .unwrap();
+        assert!(authorize_owner(&article, &current_user("editor", editor)).is_ok());
+        assert!(
+            authorize_owner(&article_by(None), &current_user("admin", Permission::ALL)).is_ok()
+        );
+    }
+}
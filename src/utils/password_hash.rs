@@ -0,0 +1,89 @@
+use crate::config::password::PASSWORD_CONFIG;
+use crate::AppError;
+use argon2::password_hash::{rand_core::OsRng, PasswordHash, PasswordHasher, SaltString};
+use argon2::{Algorithm, Argon2, Params, PasswordVerifier, Version};
+
+// 按配置构造 Argon2id 哈希器
+fn argon2() -> Result<Argon2<'static>, AppError> {
+    let settings = PASSWORD_CONFIG.argon2;
+    let params = Params::new(
+        settings.memory_kib,
+        settings.iterations,
+        settings.parallelism,
+        None,
+    )
+    .map_err(|e| {
+        log::error!("Argon2参数配置错误: {}", e);
+        AppError::InternalServerError("密码加密失败".into())
+    })?;
+    Ok(Argon2::new(Algorithm::Argon2id, Version::V0x13, params))
+}
+
+// 使用 Argon2id 生成密码哈希（PHC字符串格式）
+pub fn hash_password(password: &str) -> Result<String, AppError> {
+    let salt = SaltString::generate(&mut OsRng);
+    argon2()?
+        .hash_password(password.as_bytes(), &salt)
+        .map(|hash| hash.to_string())
+        .map_err(|e| {
+            log::error!("密码加密失败: {}", e);
+            AppError::InternalServerError("密码加密失败".into())
+        })
+}
+
+// 校验密码，同时兼容 Argon2 和历史遗留的 bcrypt 哈希，格式错误视为不匹配
+pub fn verify_password(password: &str, password_hash: &str) -> bool {
+    if is_bcrypt(password_hash) {
+        return bcrypt::verify(password, password_hash).unwrap_or(false);
+    }
+    match PasswordHash::new(password_hash) {
+        // 参数以哈希中记录的为准，配置调整后旧哈希仍可验证
+        Ok(parsed) => Argon2::default()
+            .verify_password(password.as_bytes(), &parsed)
+            .is_ok(),
+        Err(_) => false,
+    }
+}
+
+// 哈希不是当前配置的 Argon2id 参数时需要在下次登录成功后重新生成
+pub fn needs_rehash(password_hash: &str) -> bool {
+    let Ok(parsed) = PasswordHash::new(password_hash) else {
+        return true;
+    };
+    if parsed.algorithm != Algorithm::Argon2id.ident() {
+        return true;
+    }
+    let settings = PASSWORD_CONFIG.argon2;
+    match Params::try_from(&parsed) {
+        Ok(params) => {
+            params.m_cost() != settings.memory_kib
+                || params.t_cost() != settings.iterations
+                || params.p_cost() != settings.parallelism
+        }
+        Err(_) => true,
+    }
+}
+
+fn is_bcrypt(password_hash: &str) -> bool {
+    ["$2a$", "$2b$", "$2x$", "$2y$"]
+        .iter()
+        .any(|prefix| password_hash.starts_with(prefix))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bcrypt_hash_is_verified_and_upgraded() {
+        let legacy = bcrypt::hash("Passw0rd", 4).unwrap();
+        assert!(verify_password("Passw0rd", &legacy));
+        assert!(!verify_password("wrong", &legacy));
+        assert!(needs_rehash(&legacy));
+
+        let upgraded = hash_password("Passw0rd").unwrap();
+        assert!(upgraded.starts_with("$argon2id$"));
+        assert!(verify_password("Passw0rd", &upgraded));
+        assert!(!needs_rehash(&upgraded));
+    }
+}
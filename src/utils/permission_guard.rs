@@ -1,24 +1,143 @@
-use crate::{
-    config::permission::Permission,
-    jsonwebtoken::{extract_token, has_permission},
-    AppError,
-};
+use crate::{config::permission::Permission, utils::current_user::CurrentUser, AppError};
 use actix_web::guard::{Guard, GuardContext};
 use log::{error, info};
 use std::cell::RefCell;
 use std::rc::Rc;
 
+// 路由所需权限的组合规则
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PermissionRule {
+    // 必须拥有全部权限
+    All(Permission),
+    // 拥有其中任意一个权限即可
+    Any(Permission),
+    And(Box<PermissionRule>, Box<PermissionRule>),
+    Or(Box<PermissionRule>, Box<PermissionRule>),
+    // 满足内部规则时拒绝访问
+    Not(Box<PermissionRule>),
+}
+
+impl PermissionRule {
+    // 规则中涉及的全部权限位
+    pub fn permissions(&self) -> Permission {
+        match self {
+            PermissionRule::All(p) | PermissionRule::Any(p) => *p,
+            PermissionRule::And(l, r) | PermissionRule::Or(l, r) => {
+                l.permissions() | r.permissions()
+            }
+            PermissionRule::Not(inner) => inner.permissions(),
+        }
+    }
+
+    pub fn allows(&self, granted: Permission) -> bool {
+        self.check(granted).is_ok()
+    }
+
+    // 校验权限，不满足时返回可直接展示的原因（包含缺少的权限名称）
+    pub fn check(&self, granted: Permission) -> Result<(), String> {
+        match self {
+            PermissionRule::All(required) => {
+                let missing = *required - granted;
+                if missing.is_empty() {
+                    Ok(())
+                } else {
+                    Err(format!("缺少权限: {}", missing.names().join(", ")))
+                }
+            }
+            PermissionRule::Any(required) => {
+                if granted.intersects(*required) {
+                    Ok(())
+                } else {
+                    Err(format!("需要以下任一权限: {}", required.names().join(", ")))
+                }
+            }
+            PermissionRule::And(left, right) => match (left.check(granted), right.check(granted)) {
+                (Ok(()), Ok(())) => Ok(()),
+                (Err(e), Ok(())) | (Ok(()), Err(e)) => Err(e),
+                (Err(left), Err(right)) => Err(format!("{}; {}", left, right)),
+            },
+            PermissionRule::Or(left, right) => match left.check(granted) {
+                Ok(()) => Ok(()),
+                Err(left) => right
+                    .check(granted)
+                    .map_err(|right| format!("需要满足以下任一条件: ({}) 或 ({})", left, right)),
+            },
+            PermissionRule::Not(inner) => match inner.check(granted) {
+                Ok(()) => Err(format!("持有以下权限时不允许访问: {}", inner)),
+                Err(_) => Ok(()),
+            },
+        }
+    }
+}
+
+impl std::fmt::Display for PermissionRule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PermissionRule::All(p) => write!(f, "全部({})", p.names().join(", ")),
+            PermissionRule::Any(p) => write!(f, "任一({})", p.names().join(", ")),
+            PermissionRule::And(l, r) => write!(f, "({} 且 {})", l, r),
+            PermissionRule::Or(l, r) => write!(f, "({} 或 {})", l, r),
+            PermissionRule::Not(inner) => write!(f, "非{}", inner),
+        }
+    }
+}
+
 pub struct PermissionGuard {
-    required_permission: Permission,
+    rule: PermissionRule,
 }
 
 impl PermissionGuard {
+    // 等同于 require_all，组合权限（如 READ_WRITE_ARTICLE）要求全部拥有
     pub fn new(required_permission: Permission) -> Self {
+        Self::require_all(required_permission)
+    }
+
+    pub fn require_all(required_permission: Permission) -> Self {
         PermissionGuard {
-            required_permission,
+            rule: PermissionRule::All(required_permission),
         }
     }
+
+    pub fn require_any(required_permission: Permission) -> Self {
+        PermissionGuard {
+            rule: PermissionRule::Any(required_permission),
+        }
+    }
+
+    pub fn and(self, other: PermissionGuard) -> Self {
+        PermissionGuard {
+            rule: PermissionRule::And(Box::new(self.rule), Box::new(other.rule)),
+        }
+    }
+
+    pub fn or(self, other: PermissionGuard) -> Self {
+        PermissionGuard {
+            rule: PermissionRule::Or(Box::new(self.rule), Box::new(other.rule)),
+        }
+    }
+
+    pub fn rule(&self) -> &PermissionRule {
+        &self.rule
+    }
 }
+
+impl From<PermissionRule> for PermissionGuard {
+    fn from(rule: PermissionRule) -> Self {
+        PermissionGuard { rule }
+    }
+}
+
+// !guard 表示满足原规则时拒绝访问
+impl std::ops::Not for PermissionGuard {
+    type Output = PermissionGuard;
+
+    fn not(self) -> Self::Output {
+        PermissionGuard {
+            rule: PermissionRule::Not(Box::new(self.rule)),
+        }
+    }
+}
+
 impl Guard for PermissionGuard {
     fn check(&self, ctx: &GuardContext<'_>) -> bool {
         match self.check_permission(ctx) {
@@ -33,40 +152,128 @@ impl Guard for PermissionGuard {
 
 impl PermissionGuard {
     fn check_permission(&self, ctx: &GuardContext<'_>) -> Result<bool, AppError> {
-        let head = ctx.head();
-        let token = extract_token(&head.headers);
-        let token_data = match token {
-            Some(token_str) => has_permission(&token_str).map_err(|err| {
-                eprintln!("权限检查错误: {}", err);
-                AppError::Forbidden(err.to_string())
-            })?,
-            None => {
-                error!("令牌未找到");
-                return Err(AppError::Forbidden("令牌未找到".to_string()));
-            }
-        };
+        // 权限来自认证中间件写入的当前用户（JWT或API密钥）
+        let current_user = ctx
+            .req_data()
+            .get::<CurrentUser>()
+            .cloned()
+            .ok_or_else(|| {
+                error!("请求未经过认证中间件");
+                AppError::Unauthorized("请求未包含认证Token".to_string())
+            })?;
+        let stored_permissions = current_user.permissions;
+        info!(
+            "存储的权限: {:?}, 必需的权限: {}",
+            stored_permissions, self.rule
+        );
 
-        let permissions = token_data.claims.permissions.ok_or_else(|| {
-            error!("权限字符串为空");
-            AppError::Forbidden("权限字符串为空".to_string())
-        })?;
+        self.rule
+            .check(stored_permissions)
+            .map(|_| true)
+            .map_err(AppError::Forbidden)
+    }
+}
 
-        let permissions_bits = permissions.parse::<u64>().map_err(|_| {
-            error!("权限字符串格式错误");
-            AppError::Forbidden("权限字符串格式错误".to_string())
-        })?;
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::permission::{PERMISSION_LIST, PERMISSION_MAP};
 
-        let stored_permissions =
-            Permission::from_bits(permissions_bits).unwrap_or(Permission::NONE);
-        info!(
-            "存储的权限: {:?}, 必需的权限: {:?}",
-            stored_permissions, self.required_permission
+    // define_permissions! 中包含多个权限位的组合权限
+    fn composite_flags() -> Vec<(&'static str, Permission)> {
+        PERMISSION_LIST
+            .iter()
+            .map(|(name, _)| (*name, PERMISSION_MAP[name]))
+            .filter(|(_, flag)| flag.bits().count_ones() > 1)
+            .collect()
+    }
+
+    // 组合权限包含的已命名单一权限
+    fn single_bits(flag: Permission) -> Vec<Permission> {
+        PERMISSION_LIST
+            .iter()
+            .map(|(name, _)| PERMISSION_MAP[name])
+            .filter(|bit| bit.bits().count_ones() == 1 && flag.contains(*bit))
+            .collect()
+    }
+
+    #[test]
+    fn test_every_composite_flag_is_covered() {
+        let names: Vec<_> = composite_flags().into_iter().map(|(n, _)| n).collect();
+        assert_eq!(
+            names,
+            [
+                "READ_WRITE_ARTICLE",
+                "READ_WRITE_COMMENT",
+                "READ_WRITE_USER",
+                "READ_WRITE_SYSTEM",
+                "READ_WRITE_FILE",
+                "READ_WRITE_TAG",
+                "READ_WRITE_CATEGORY",
+                "READ_WRITE_MESSAGE",
+                "READ",
+                "ALL",
+            ]
         );
+    }
+
+    #[test]
+    fn test_require_all_needs_every_bit_of_composite_flag() {
+        for (name, flag) in composite_flags() {
+            let rule = PermissionRule::All(flag);
+            assert!(rule.allows(flag), "{} 应允许完整权限", name);
+            for bit in single_bits(flag) {
+                let err = rule.check(flag - bit).unwrap_err();
+                let missing = bit.names();
+                assert_eq!(missing.len(), 1);
+                assert!(err.contains(&missing[0]), "{}: {}", name, err);
+                assert!(!rule.allows(bit), "{} 不应只凭 {:?} 通过", name, bit);
+            }
+        }
+    }
 
-        if stored_permissions.intersects(self.required_permission) {
-            Ok(true)
-        } else {
-            Err(AppError::Forbidden("权限不足".to_string()))
+    #[test]
+    fn test_require_any_accepts_each_bit_of_composite_flag() {
+        for (name, flag) in composite_flags() {
+            let rule = PermissionRule::Any(flag);
+            for bit in single_bits(flag) {
+                assert!(rule.allows(bit), "{} 应允许 {:?}", name, bit);
+            }
+            let outside = Permission::ALL - flag;
+            assert!(!rule.allows(outside), "{} 不应允许其他权限", name);
+            assert!(!rule.allows(Permission::NONE));
         }
     }
+
+    #[test]
+    fn test_read_write_article_no_longer_passes_with_read_only() {
+        let guard = PermissionGuard::new(Permission::READ_WRITE_ARTICLE);
+        assert_eq!(
+            guard.rule().check(Permission::READ_ARTICLE),
+            Err("缺少权限: WRITE_ARTICLE".to_string())
+        );
+    }
+
+    #[test]
+    fn test_guard_composition() {
+        let editor = PermissionGuard::require_all(Permission::WRITE_ARTICLE)
+            .and(PermissionGuard::require_any(Permission::READ_WRITE_TAG));
+        assert!(editor
+            .rule()
+            .allows(Permission::WRITE_ARTICLE | Permission::READ_TAG));
+        assert!(!editor.rule().allows(Permission::WRITE_ARTICLE));
+        let err = editor.rule().check(Permission::NONE).unwrap_err();
+        assert!(err.contains("WRITE_ARTICLE") && err.contains("READ_TAG"));
+
+        let either = PermissionGuard::require_all(Permission::WRITE_SYSTEM)
+            .or(PermissionGuard::require_all(Permission::WRITE_USER));
+        assert!(either.rule().allows(Permission::WRITE_SYSTEM));
+        assert!(either.rule().allows(Permission::WRITE_USER));
+        assert!(!either.rule().allows(Permission::READ_USER));
+
+        let not_admin = !PermissionGuard::require_all(Permission::WRITE_SYSTEM);
+        assert!(not_admin.rule().allows(Permission::READ));
+        let err = not_admin.rule().check(Permission::ALL).unwrap_err();
+        assert!(err.contains("WRITE_SYSTEM"));
+    }
 }
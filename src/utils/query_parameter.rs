@@ -3,7 +3,7 @@ use actix_web::{dev::Payload, error::QueryPayloadError, Error, FromRequest, Http
 use serde::de::DeserializeOwned;
 use std::{collections::HashMap, fmt, ops, sync::Arc};
 
-use crate::{middleware::helpers::Resp, AppError};
+use crate::AppError;
 
 #[derive(Clone, Default)]
 pub struct QueryConfig {
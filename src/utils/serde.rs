@@ -18,6 +18,7 @@ where
 
 // 定义枚举需要实现的trait
 pub trait EnumDeserialize {
+    #[allow(clippy::result_unit_err)]
     fn from_str(s: &str) -> Result<Self, ()>
     where
         Self: Sized;
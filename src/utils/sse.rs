@@ -6,6 +6,12 @@ pub struct SseNotifier {
     tx: broadcast::Sender<String>,
 }
 
+impl Default for SseNotifier {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl SseNotifier {
     pub fn new() -> Self {
         let (tx, _) = broadcast::channel(100);